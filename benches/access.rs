@@ -0,0 +1,121 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use cirru_edn::{edn_tag, Edn, EdnListView, EdnMapView, EdnTag};
+
+fn criterion_benchmark(c: &mut Criterion) {
+  let mut xs = EdnListView::default();
+  for i in 0..100_000 {
+    let mut m = EdnMapView::default();
+    m.insert_key("id", Edn::Number(i as f64));
+    m.insert_key("name", Edn::str(format!("item {}", i)));
+    m.insert_key("tags", Edn::from(vec![Edn::tag("a"), Edn::tag("b"), Edn::tag("c")]));
+    xs.push(Edn::Map(m));
+  }
+
+  c.bench_function("get_or_nil", |b| {
+    b.iter(|| {
+      let mut count = 0.0;
+      for i in 0..xs.len() {
+        if let Edn::Map(m) = xs.get_or_nil(i) {
+          count += m.get_or_nil("id").read_number().unwrap();
+        }
+      }
+      count
+    })
+  });
+
+  c.bench_function("get_ref_or_nil", |b| {
+    b.iter(|| {
+      let mut count = 0.0;
+      for i in 0..xs.len() {
+        if let Edn::Map(m) = xs.get_ref_or_nil(i) {
+          count += m.get_ref_or_nil("id").read_number().unwrap();
+        }
+      }
+      count
+    })
+  });
+
+  let m = xs.get_ref_or_nil(0).view_map().unwrap();
+  c.bench_function("tag_lookup_new", |b| {
+    b.iter(|| {
+      let mut count = 0;
+      for _ in 0..1_000_000 {
+        if m.get(&Edn::tag("id")).is_some() {
+          count += 1;
+        }
+      }
+      count
+    })
+  });
+
+  c.bench_function("tag_lookup_cached", |b| {
+    b.iter(|| {
+      let mut count = 0;
+      for _ in 0..1_000_000 {
+        if m.get(&Edn::Tag(edn_tag!("id"))).is_some() {
+          count += 1;
+        }
+      }
+      count
+    })
+  });
+
+  let key = EdnTag::new("id");
+  c.bench_function("tag_key_by_name", |b| {
+    b.iter(|| {
+      let mut count = 0.0;
+      for x in &xs {
+        if let Edn::Map(m) = x {
+          count += m.get_ref_or_nil(key.ref_str()).read_number().unwrap();
+        }
+      }
+      count
+    })
+  });
+
+  c.bench_function("tag_key_by_tag", |b| {
+    b.iter(|| {
+      let mut count = 0.0;
+      for x in &xs {
+        if let Edn::Map(m) = x {
+          count += m.get_by(&key).unwrap_or(&Edn::Nil).read_number().unwrap();
+        }
+      }
+      count
+    })
+  });
+
+  let mut wide = EdnMapView::default();
+  let names = (0..32).map(|i| format!("k{}", i)).collect::<Vec<_>>();
+  for (i, name) in names.iter().enumerate() {
+    wide.insert_key(name.as_str(), Edn::Number(i as f64));
+  }
+  let keys: [&str; 8] = std::array::from_fn(|i| names[i * 4].as_str());
+  c.bench_function("take_keys_separately", |b| {
+    b.iter(|| {
+      let mut count = 0.0;
+      for _ in 0..100_000 {
+        for key in keys {
+          count += wide.get_or_nil(key).read_number().unwrap();
+        }
+      }
+      count
+    })
+  });
+
+  c.bench_function("take_keys_in_one_pass", |b| {
+    b.iter(|| {
+      let mut count = 0.0;
+      for _ in 0..100_000 {
+        for v in wide.take_keys(keys) {
+          count += v.read_number().unwrap();
+        }
+      }
+      count
+    })
+  });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
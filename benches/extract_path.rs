@@ -0,0 +1,29 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use cirru_edn::{Edn, EdnListView, EdnMapView};
+
+fn criterion_benchmark(c: &mut Criterion) {
+  let mut xs = EdnListView::default();
+  for i in 0..20_000 {
+    let mut m = EdnMapView::default();
+    m.insert_key("id", Edn::Number(i as f64));
+    m.insert_key("name", Edn::str(format!("item {}", i)));
+    m.insert_key("tags", Edn::from(vec![Edn::tag("a"), Edn::tag("b"), Edn::tag("c")]));
+    xs.push(Edn::Map(m));
+  }
+  let mut doc = EdnMapView::default();
+  doc.insert_key("version", Edn::str("1.0.0"));
+  doc.insert_key("items", Edn::List(xs));
+  let content = cirru_edn::format(&Edn::Map(doc), true).unwrap();
+
+  c.bench_function("extract_path", |b| {
+    b.iter(|| cirru_edn::extract_path(&content, &["version"]).is_ok())
+  });
+
+  c.bench_function("parse and get", |b| {
+    b.iter(|| cirru_edn::parse(&content).unwrap().map_get("version").is_ok())
+  });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use cirru_edn::{Edn, EdnFormatOptions};
+
+fn record_list(n: usize) -> Edn {
+  Edn::list((0..n).map(|i| {
+    Edn::map([
+      (Edn::tag("id"), Edn::Number(i as f64)),
+      (Edn::tag("name"), Edn::str(format!("item {}", i))),
+      (
+        Edn::tag("tags"),
+        Edn::list([Edn::tag("a"), Edn::tag("b"), Edn::tag("c")]),
+      ),
+    ])
+  }))
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+  let data = record_list(200_000);
+
+  let sequential = EdnFormatOptions {
+    parallel_min_items: Some(usize::MAX),
+    ..Default::default()
+  };
+  c.bench_function("format_sequential", |b| {
+    b.iter(|| {
+      let _ = cirru_edn::format_with_options(&data, sequential);
+    })
+  });
+
+  for threads in [1, 2, 4] {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+    c.bench_function(&format!("format_parallel_{}_threads", threads), |b| {
+      b.iter(|| {
+        pool.install(|| {
+          let _ = cirru_edn::format(&data, false);
+        })
+      })
+    });
+  }
+}
+
+criterion_group! {
+  name = benches;
+  config = Criterion::default().sample_size(10);
+  targets = criterion_benchmark
+}
+criterion_main!(benches);
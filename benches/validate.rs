@@ -0,0 +1,26 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use cirru_edn::{Edn, EdnListView, EdnMapView};
+
+fn criterion_benchmark(c: &mut Criterion) {
+  let mut xs = EdnListView::default();
+  for i in 0..20_000 {
+    let mut m = EdnMapView::default();
+    m.insert_key("id", Edn::Number(i as f64));
+    m.insert_key("name", Edn::str(format!("item {}", i)));
+    m.insert_key("tags", Edn::from(vec![Edn::tag("a"), Edn::tag("b"), Edn::tag("c")]));
+    xs.push(Edn::Map(m));
+  }
+  let content = cirru_edn::format(&Edn::List(xs), true).unwrap();
+  let tree = cirru_parser::parse(&content).unwrap().remove(0);
+
+  c.bench_function("is_valid_edn", |b| b.iter(|| cirru_edn::is_valid_edn(&tree).is_ok()));
+
+  // extraction is not public, it takes the time of `parse` beyond reading the tree
+  c.bench_function("read tree", |b| b.iter(|| cirru_parser::parse(&content).is_ok()));
+
+  c.bench_function("parse", |b| b.iter(|| cirru_edn::parse(&content).is_ok()));
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
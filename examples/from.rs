@@ -19,8 +19,8 @@ impl TryFrom<Edn> for Cat {
   type Error = String;
   fn try_from(value: Edn) -> Result<Self, Self::Error> {
     let c = Cat {
-      name: value.view_map()?.str_get("name").unwrap_or(&Edn::Nil).try_into()?,
-      category: value.view_map()?.get_or_nil("category").try_into()?,
+      name: value.view_map()?.get_str_key("name").unwrap_or(&Edn::Nil).try_into()?,
+      category: EdnTag::try_from_lenient(&value.view_map()?.get_or_nil("category"))?,
       weight: value.view_map()?.get_or_nil("weight").try_into()?,
       skills: value.view_map()?.get_or_nil("skills").try_into()?,
       counts: value.view_map()?.get_or_nil("counts").try_into()?,
@@ -39,7 +39,7 @@ impl TryFrom<Edn> for Cat {
 
 impl From<Cat> for Edn {
   fn from(x: Cat) -> Edn {
-    Edn::Map(EdnMapView(HashMap::from_iter([
+    Edn::Map(EdnMapView::from(HashMap::from_iter([
       ("name".into(), x.name.into()),
       ("category".into(), x.category.into()),
       ("weight".into(), x.weight.into()),
@@ -51,21 +51,15 @@ impl From<Cat> for Edn {
 }
 
 fn main() -> Result<(), String> {
-  let data: Edn = Edn::Map(EdnMapView(HashMap::from_iter([
-    ("name".into(), Edn::str("Kii")),
-    ("category".into(), Edn::tag("ying")),
-    // ("weight".into(), Edn::Number(1.0)),
-    // (
-    //   "skills".into(),
-    //   Edn::List(vec![Edn::kwd("eating"), Edn::kwd("sleeping")]),
-    // ),
-    (
-      "counts".into(),
-      Edn::from(HashMap::from_iter([(Edn::from("a"), Edn::Number(1.))])),
-    ),
-    // ("owner".into(), Edn::str("Kii")),
-    ("owner".into(), Edn::Nil),
-  ])));
+  let data: Edn = Edn::map([
+    ("name", Edn::str("Kii")),
+    ("category", Edn::tag("ying")),
+    // ("weight", Edn::Number(1.0)),
+    // ("skills", Edn::list([Edn::tag("eating"), Edn::tag("sleeping")])),
+    ("counts", Edn::map([("a", 1)])),
+    // ("owner", Edn::str("Kii")),
+    ("owner", Edn::Nil),
+  ]);
   let cat: Cat = data.try_into()?;
   println!("new {:?}", cat);
   assert_eq!(cat.name, "Kii");
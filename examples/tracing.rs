@@ -0,0 +1,23 @@
+//! run with `cargo run --example tracing --features tracing` to see spans of parsing and formatting
+
+extern crate cirru_edn;
+
+use tracing_subscriber::fmt::format::FmtSpan;
+
+fn main() -> Result<(), String> {
+  tracing_subscriber::fmt()
+    .with_max_level(tracing::Level::DEBUG)
+    .with_span_events(FmtSpan::CLOSE)
+    .init();
+
+  let data = cirru_edn::parse(
+    r#"
+{}
+  :name |demo
+  :items $ [] 1 2 3
+  :meta $ {} (:version |0.1.0)
+"#,
+  )?;
+  println!("{}", cirru_edn::format(&data, true)?);
+  Ok(())
+}
@@ -0,0 +1,351 @@
+//! binary encoding with bincode 2.x, enabled with `bincode` feature.
+//!
+//! wire format: each value starts with a variant index byte, followed by payload,
+//! integers and lengths are written in the encoding of bincode config:
+//!
+//! | index | variant  | payload                                        |
+//! | ----- | -------- | ---------------------------------------------- |
+//! | 0     | `Nil`    |                                                |
+//! | 1     | `Bool`   | bool                                           |
+//! | 2     | `Number` | f64                                            |
+//! | 3     | `Symbol` | string                                         |
+//! | 4     | `Tag`    | string                                         |
+//! | 5     | `Str`    | string                                         |
+//! | 6     | `Quote`  | Cirru, leaf as `0` and string, list as `1` and length and items |
+//! | 7     | `Tuple`  | tag value, length, items                       |
+//! | 8     | `List`   | length, items                                  |
+//! | 9     | `Set`    | length, items                                  |
+//! | 10    | `Map`    | length, pairs of key and value                 |
+//! | 11    | `Record` | tag string, length, pairs of field string and value |
+//! | 12    | `Buffer` | length, bytes                                  |
+//! | 13    | `AnyRef` | not encodable                                  |
+//! | 14    | `Atom`   | optional label string, value                   |
+//!
+//! set items and map pairs are sorted by `Ord` of items and keys, the comparator `format` uses for them,
+//! so equal data always encodes to the same bytes. decoding rejects data nested deeper than 512 levels,
+//! which would otherwise overflow the stack.
+//!
+//! the format is stable within a minor version. indexes of existing variants are never reused,
+//! new variants get new indexes, so older data can always be decoded by newer versions.
+
+use std::sync::Arc;
+
+use bincode::{
+  de::Decoder,
+  enc::Encoder,
+  error::{DecodeError, EncodeError},
+  impl_borrow_decode, Decode, Encode,
+};
+use cirru_parser::Cirru;
+
+use crate::{Edn, EdnAtomView, EdnListView, EdnMapView, EdnRecordView, EdnSetView, EdnTag, EdnTupleView};
+
+/// levels of nesting allowed in decoding, counting quoted Cirru lists too
+const MAX_DECODE_DEPTH: usize = 512;
+
+fn decode_arc_str<D: Decoder>(decoder: &mut D) -> Result<Arc<str>, DecodeError> {
+  Ok(Arc::from(String::decode(decoder)?))
+}
+
+fn decode_len<T, D: Decoder>(decoder: &mut D) -> Result<usize, DecodeError> {
+  let len = usize::decode(decoder)?;
+  decoder.claim_container_read::<T>(len)?;
+  Ok(len)
+}
+
+fn encode_cirru<E: Encoder>(x: &Cirru, encoder: &mut E) -> Result<(), EncodeError> {
+  match x {
+    Cirru::Leaf(s) => {
+      0u8.encode(encoder)?;
+      (**s).encode(encoder)
+    }
+    Cirru::List(xs) => {
+      1u8.encode(encoder)?;
+      xs.len().encode(encoder)?;
+      for x in xs {
+        encode_cirru(x, encoder)?;
+      }
+      Ok(())
+    }
+  }
+}
+
+fn check_depth(depth: usize) -> Result<(), DecodeError> {
+  if depth > MAX_DECODE_DEPTH {
+    Err(DecodeError::OtherString(format!(
+      "data nested deeper than {} levels",
+      MAX_DECODE_DEPTH
+    )))
+  } else {
+    Ok(())
+  }
+}
+
+fn decode_cirru<D: Decoder>(decoder: &mut D, depth: usize) -> Result<Cirru, DecodeError> {
+  check_depth(depth)?;
+  match u8::decode(decoder)? {
+    0 => Ok(Cirru::Leaf(decode_arc_str(decoder)?)),
+    1 => {
+      let len = decode_len::<Cirru, D>(decoder)?;
+      let mut xs = Vec::with_capacity(len);
+      for _ in 0..len {
+        xs.push(decode_cirru(decoder, depth + 1)?);
+      }
+      Ok(Cirru::List(xs))
+    }
+    n => Err(DecodeError::OtherString(format!("unknown Cirru variant: {}", n))),
+  }
+}
+
+impl Encode for EdnTag {
+  fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+    self.ref_str().encode(encoder)
+  }
+}
+
+impl<Context> Decode<Context> for EdnTag {
+  fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+    Ok(EdnTag::new_raw(decode_arc_str(decoder)?))
+  }
+}
+impl_borrow_decode!(EdnTag);
+
+impl Encode for EdnTupleView {
+  fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+    self.tag.encode(encoder)?;
+    self.extra.encode(encoder)
+  }
+}
+
+/// items of a collection at `depth`
+fn decode_items<D: Decoder>(decoder: &mut D, depth: usize) -> Result<Vec<Edn>, DecodeError> {
+  let len = decode_len::<Edn, D>(decoder)?;
+  let mut xs = Vec::with_capacity(len);
+  for _ in 0..len {
+    xs.push(decode_edn(decoder, depth + 1)?);
+  }
+  Ok(xs)
+}
+
+fn decode_tuple<D: Decoder>(decoder: &mut D, depth: usize) -> Result<EdnTupleView, DecodeError> {
+  Ok(EdnTupleView {
+    tag: Arc::new(decode_edn(decoder, depth + 1)?),
+    extra: decode_items(decoder, depth)?,
+  })
+}
+
+impl<Context> Decode<Context> for EdnTupleView {
+  fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+    decode_tuple(decoder, 0)
+  }
+}
+impl_borrow_decode!(EdnTupleView);
+
+impl Encode for EdnListView {
+  fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+    self.0.encode(encoder)
+  }
+}
+
+impl<Context> Decode<Context> for EdnListView {
+  fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+    Ok(EdnListView(decode_items(decoder, 0)?))
+  }
+}
+impl_borrow_decode!(EdnListView);
+
+/// items sorted, not in the order of hashing
+impl Encode for EdnSetView {
+  fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+    let mut xs = self.0.iter().collect::<Vec<_>>();
+    xs.sort();
+    xs.len().encode(encoder)?;
+    for x in xs {
+      x.encode(encoder)?;
+    }
+    Ok(())
+  }
+}
+
+fn decode_set<D: Decoder>(decoder: &mut D, depth: usize) -> Result<EdnSetView, DecodeError> {
+  let len = decode_len::<Edn, D>(decoder)?;
+  let mut xs = EdnSetView::default();
+  for _ in 0..len {
+    xs.insert(decode_edn(decoder, depth + 1)?);
+  }
+  Ok(xs)
+}
+
+impl<Context> Decode<Context> for EdnSetView {
+  fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+    decode_set(decoder, 0)
+  }
+}
+impl_borrow_decode!(EdnSetView);
+
+/// pairs sorted by keys, not in the order of hashing
+impl Encode for EdnMapView {
+  fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+    let mut pairs = self.0.iter().collect::<Vec<_>>();
+    pairs.sort_by(|a, b| a.0.cmp(b.0));
+    pairs.len().encode(encoder)?;
+    for (k, v) in pairs {
+      k.encode(encoder)?;
+      v.encode(encoder)?;
+    }
+    Ok(())
+  }
+}
+
+fn decode_map<D: Decoder>(decoder: &mut D, depth: usize) -> Result<EdnMapView, DecodeError> {
+  let len = decode_len::<(Edn, Edn), D>(decoder)?;
+  let mut xs = EdnMapView::default();
+  for _ in 0..len {
+    let k = decode_edn(decoder, depth + 1)?;
+    xs.insert(k, decode_edn(decoder, depth + 1)?);
+  }
+  Ok(xs)
+}
+
+impl<Context> Decode<Context> for EdnMapView {
+  fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+    decode_map(decoder, 0)
+  }
+}
+impl_borrow_decode!(EdnMapView);
+
+impl Encode for EdnRecordView {
+  fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+    self.tag.encode(encoder)?;
+    self.pairs.encode(encoder)
+  }
+}
+
+fn decode_record<D: Decoder>(decoder: &mut D, depth: usize) -> Result<EdnRecordView, DecodeError> {
+  let tag = EdnTag::decode(decoder)?;
+  let len = decode_len::<(EdnTag, Edn), D>(decoder)?;
+  let mut pairs = Vec::with_capacity(len);
+  for _ in 0..len {
+    let k = EdnTag::decode(decoder)?;
+    pairs.push((k, decode_edn(decoder, depth + 1)?));
+  }
+  Ok(EdnRecordView { tag, pairs })
+}
+
+impl<Context> Decode<Context> for EdnRecordView {
+  fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+    decode_record(decoder, 0)
+  }
+}
+impl_borrow_decode!(EdnRecordView);
+
+impl Encode for EdnAtomView {
+  fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+    self.label.as_deref().encode(encoder)?;
+    self.value.encode(encoder)
+  }
+}
+
+fn decode_atom<D: Decoder>(decoder: &mut D, depth: usize) -> Result<EdnAtomView, DecodeError> {
+  Ok(EdnAtomView {
+    label: Option::<String>::decode(decoder)?.map(Arc::from),
+    value: Box::new(decode_edn(decoder, depth + 1)?),
+  })
+}
+
+impl<Context> Decode<Context> for EdnAtomView {
+  fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+    decode_atom(decoder, 0)
+  }
+}
+impl_borrow_decode!(EdnAtomView);
+
+impl Encode for Edn {
+  fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+    match self {
+      Edn::Nil => 0u8.encode(encoder),
+      Edn::Bool(b) => {
+        1u8.encode(encoder)?;
+        b.encode(encoder)
+      }
+      Edn::Number(n) => {
+        2u8.encode(encoder)?;
+        n.encode(encoder)
+      }
+      Edn::Symbol(s) => {
+        3u8.encode(encoder)?;
+        (**s).encode(encoder)
+      }
+      Edn::Tag(t) => {
+        4u8.encode(encoder)?;
+        t.encode(encoder)
+      }
+      Edn::Str(s) => {
+        5u8.encode(encoder)?;
+        (**s).encode(encoder)
+      }
+      Edn::Quote(c) => {
+        6u8.encode(encoder)?;
+        encode_cirru(c, encoder)
+      }
+      Edn::Tuple(t) => {
+        7u8.encode(encoder)?;
+        t.encode(encoder)
+      }
+      Edn::List(xs) => {
+        8u8.encode(encoder)?;
+        xs.encode(encoder)
+      }
+      Edn::Set(xs) => {
+        9u8.encode(encoder)?;
+        xs.encode(encoder)
+      }
+      Edn::Map(xs) => {
+        10u8.encode(encoder)?;
+        xs.encode(encoder)
+      }
+      Edn::Record(r) => {
+        11u8.encode(encoder)?;
+        r.encode(encoder)
+      }
+      Edn::Buffer(buf) => {
+        12u8.encode(encoder)?;
+        buf.encode(encoder)
+      }
+      Edn::AnyRef(_) => Err(EncodeError::Other("AnyRef is not encodable")),
+      Edn::Atom(a) => {
+        14u8.encode(encoder)?;
+        a.encode(encoder)
+      }
+    }
+  }
+}
+
+/// value at `depth`, counted from where decoding started
+fn decode_edn<D: Decoder>(decoder: &mut D, depth: usize) -> Result<Edn, DecodeError> {
+  check_depth(depth)?;
+  match u8::decode(decoder)? {
+    0 => Ok(Edn::Nil),
+    1 => Ok(Edn::Bool(bool::decode(decoder)?)),
+    2 => Ok(Edn::Number(f64::decode(decoder)?)),
+    3 => Ok(Edn::Symbol(decode_arc_str(decoder)?)),
+    4 => Ok(Edn::Tag(EdnTag::decode(decoder)?)),
+    5 => Ok(Edn::Str(decode_arc_str(decoder)?)),
+    6 => Ok(Edn::Quote(decode_cirru(decoder, depth + 1)?)),
+    7 => Ok(Edn::Tuple(decode_tuple(decoder, depth)?)),
+    8 => Ok(Edn::List(EdnListView(decode_items(decoder, depth)?))),
+    9 => Ok(Edn::Set(decode_set(decoder, depth)?)),
+    10 => Ok(Edn::Map(decode_map(decoder, depth)?)),
+    11 => Ok(Edn::Record(decode_record(decoder, depth)?)),
+    12 => Ok(Edn::Buffer(Vec::decode(decoder)?)),
+    14 => Ok(Edn::Atom(decode_atom(decoder, depth)?)),
+    n => Err(DecodeError::OtherString(format!("unknown Edn variant: {}", n))),
+  }
+}
+
+impl<Context> Decode<Context> for Edn {
+  fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+    decode_edn(decoder, 0)
+  }
+}
+impl_borrow_decode!(Edn);
@@ -1,11 +1,28 @@
 mod any_ref;
+mod atom;
+mod buffer;
+mod frozen;
+mod key;
 mod list;
 mod map;
+mod name;
+mod number;
+mod preview;
+mod quote;
+mod rebuild;
 mod record;
+mod redact;
+mod release;
+mod rename;
+mod sample;
+mod select;
 mod set;
+mod strict_map;
+mod strings;
 mod tuple;
 
 use std::{
+  borrow::Cow,
   cmp::{
     Eq,
     Ordering::{self, *},
@@ -13,27 +30,36 @@ use std::{
   collections::{HashMap, HashSet},
   convert::{TryFrom, TryInto},
   fmt::{self, Write},
-  hash::{Hash, Hasher},
-  iter::FromIterator,
-  ptr,
-  sync::Arc,
+  hash::{DefaultHasher, Hash, Hasher},
+  sync::{Arc, OnceLock},
 };
 
-use cirru_parser::Cirru;
+use cirru_parser::{Cirru, CirruWriterOptions};
 
 pub use self::tuple::EdnTupleView;
 pub use any_ref::{DynEq, EdnAnyRef};
-pub use list::EdnListView;
-pub use map::EdnMapView;
-pub use record::EdnRecordView;
-pub use set::EdnSetView;
+use atom::AtomLabels;
+pub use atom::EdnAtomView;
+pub use frozen::FrozenEdn;
+pub use key::EdnKey;
+pub use list::{EdnListView, EdnListViewIter};
+pub use map::{EdnMapView, EdnMapViewIntoIter, EdnMapViewIter, MapKeyLookup};
+pub use name::NameKind;
+pub use number::TryFromF64;
+pub(crate) use preview::ERROR_PREVIEW_LEN;
+pub use record::{EdnRecordView, EdnRecordViewIter};
+pub use set::{EdnSetView, EdnSetViewIntoIter, EdnSetViewIter};
+pub use strict_map::EdnStrictMapView;
+pub use strings::EdnStringStats;
 
 use crate::tag::EdnTag;
 
 /// Data format based on subset of EDN, but in Cirru syntax.
 /// different parts are quote and Record.
-#[derive(fmt::Debug, Clone)]
+/// dropping is recursive, use `drop_iteratively` for very deep trees
+#[derive(fmt::Debug, Clone, Default)]
 pub enum Edn {
+  #[default]
   Nil,
   Bool(bool),
   Number(f64),
@@ -49,7 +75,8 @@ pub enum Edn {
   Buffer(Vec<u8>),
   /// reference to Rust data, not interpretable in Calcit
   AnyRef(EdnAnyRef),
-  Atom(Box<Edn>),
+  /// atom with an optional label for identity
+  Atom(EdnAtomView),
 }
 
 impl fmt::Display for Edn {
@@ -65,16 +92,38 @@ impl fmt::Display for Edn {
           f.write_fmt(format_args!("|{}", s))
         } else {
           f.write_str("\"|")?;
-          for c in s.chars() {
-            if is_simple_char(c) {
-              f.write_char(c)?;
-            } else {
-              f.write_str(&c.escape_default().to_string())?;
+          for c in escape_str(s).chars() {
+            match c {
+              '\n' => f.write_str("\\n")?,
+              '\t' => f.write_str("\\t")?,
+              '"' => f.write_str("\\\"")?,
+              '\'' => f.write_str("\\'")?,
+              '\\' => f.write_str("\\\\")?,
+              _ => f.write_char(c)?,
             }
           }
           f.write_char('"')
         }
       }
+      // the writer prints nothing for these
+      Self::Quote(Cirru::Leaf(s)) if s.is_empty() => f.write_str("(quote \"\")"),
+      Self::Quote(Cirru::List(xs)) if xs.is_empty() => {
+        f.write_str(if f.alternate() { "quote ()" } else { "(quote ())" })
+      }
+      Self::Quote(v @ Cirru::List(_)) if f.alternate() => {
+        // indented code on following lines
+        let code =
+          cirru_parser::format(&[v.to_owned()], CirruWriterOptions { use_inline: false }).map_err(|_| fmt::Error)?;
+        f.write_str("quote")?;
+        for line in code.lines().filter(|l| !l.trim().is_empty()) {
+          f.write_fmt(format_args!("\n  {}", line))?;
+        }
+        Ok(())
+      }
+      Self::Quote(v @ Cirru::List(_)) => {
+        let code = cirru_parser::format_expr_one_liner(v).map_err(|_| fmt::Error)?;
+        f.write_fmt(format_args!("(quote ({}))", code))
+      }
       Self::Quote(v) => f.write_fmt(format_args!("(quote {})", v)),
       Self::Tuple(EdnTupleView { tag, extra }) => {
         let mut extra_str = String::new();
@@ -93,15 +142,20 @@ impl fmt::Display for Edn {
         f.write_str(")")
       }
       Self::Set(xs) => {
+        // sorted like `format`, so equal sets show the same text
+        let mut items = xs.0.iter().collect::<Vec<_>>();
+        items.sort();
         f.write_str("(#{}")?;
-        for x in &xs.0 {
+        for x in items {
           f.write_fmt(format_args!(" {}", x))?;
         }
         f.write_str(")")
       }
       Self::Map(xs) => {
+        let mut entries = xs.0.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(k, _)| *k);
         f.write_str("({}")?;
-        for (k, v) in &xs.0 {
+        for (k, v) in entries {
           f.write_fmt(format_args!(" ({} {})", k, v))?;
         }
         f.write_str(")")
@@ -127,25 +181,122 @@ impl fmt::Display for Edn {
         f.write_str(")")
       }
       Self::AnyRef(_r) => f.write_str("(any-ref ...)"),
-      Self::Atom(a) => f.write_fmt(format_args!("(atom {})", a)),
+      Self::Atom(EdnAtomView {
+        label: Some(label),
+        value,
+      }) => f.write_fmt(format_args!("(atom '{} {})", label, value)),
+      Self::Atom(EdnAtomView { label: None, value }) => f.write_fmt(format_args!("(atom {})", value)),
     }
   }
 }
 
-/// check if a char is simple enough to be printed without quotes
+/// check if a char is simple enough to be printed without quotes.
+/// CJK characters are simple with `cjk-detect` feature, otherwise all non-ASCII chars are quoted
 pub fn is_simple_char(c: char) -> bool {
-  matches!(c, '0'..='9' | 'A'..='Z' | 'a'..='z' | '-' | '?' | '.' | '$' | ',') || cjk::is_cjk_codepoint(c)
+  matches!(c, '0'..='9' | 'A'..='Z' | 'a'..='z' | '-' | '?' | '.' | '$' | ',') || is_cjk_char(c)
+}
+
+/// check if a char can be kept unquoted inside a `|string`, a wider set than `is_simple_char` for tags.
+/// these are the punctuations the Cirru writer leaves unquoted, except `\\` which starts escapes in EDN.
+/// whitespaces, parentheses and `"` always need quotes
+pub fn is_simple_str_char(c: char) -> bool {
+  is_simple_char(c)
+    || matches!(
+      c,
+      ':'
+        | '<'
+        | '>'
+        | '['
+        | ']'
+        | '{'
+        | '}'
+        | '*'
+        | '='
+        | '+'
+        | '/'
+        | '!'
+        | '~'
+        | '_'
+        | '@'
+        | '#'
+        | '&'
+        | '%'
+        | '^'
+        | '|'
+        | ';'
+        | '\''
+    )
+}
+
+#[cfg(feature = "cjk-detect")]
+fn is_cjk_char(c: char) -> bool {
+  cjk::is_cjk_codepoint(c)
+}
+
+#[cfg(not(feature = "cjk-detect"))]
+fn is_cjk_char(_c: char) -> bool {
+  false
 }
 
 fn is_simple_token(tok: &str) -> bool {
   for s in tok.chars() {
-    if !is_simple_char(s) {
+    if !is_simple_str_char(s) {
       return false;
     }
   }
   true
 }
 
+/// escapes in strings are handled in 2 layers. the Cirru tokenizer reads `\n`, `\t`, `\r`, `\\`, `\"` and `\'`,
+/// while `\u{XXXX}` is kept as it is and decoded by EDN. so control characters are written as `\u{XXXX}`,
+/// and a literal backslash followed by `u{` is written as `\u{5c}` to keep it from being decoded.
+pub(crate) fn escape_str(s: &str) -> Cow<'_, str> {
+  let needs_escape =
+    |(idx, c): (usize, char)| (c.is_control() && c != '\n' && c != '\t') || s[idx..].starts_with("\\u{");
+  if !s.char_indices().any(needs_escape) {
+    return Cow::Borrowed(s);
+  }
+  let mut buf = String::with_capacity(s.len() + 8);
+  for (idx, c) in s.char_indices() {
+    if needs_escape((idx, c)) {
+      buf.push_str(&format!("\\u{{{:x}}}", c as u32));
+    } else {
+      buf.push(c);
+    }
+  }
+  Cow::Owned(buf)
+}
+
+/// decodes `\u{XXXX}` escapes in string content, the rest of escapes are already handled by Cirru tokenizer
+pub(crate) fn unescape_str(s: &str) -> Result<Cow<'_, str>, String> {
+  if !s.contains("\\u{") {
+    return Ok(Cow::Borrowed(s));
+  }
+  let mut buf = String::with_capacity(s.len());
+  let mut rest = s;
+  while let Some(pos) = rest.find("\\u{") {
+    buf.push_str(&rest[..pos]);
+    let body = &rest[pos + 3..];
+    let end = body
+      .find('}')
+      .ok_or_else(|| format!("unclosed unicode escape in string: {:?}", s))?;
+    let c = u32::from_str_radix(&body[..end], 16)
+      .ok()
+      .and_then(char::from_u32)
+      .ok_or_else(|| format!("invalid unicode escape `\\u{{{}}}` in string: {:?}", &body[..end], s))?;
+    buf.push(c);
+    rest = &body[end + 1..];
+  }
+  buf.push_str(rest);
+  Ok(Cow::Owned(buf))
+}
+
+fn hash_one<T: Hash>(x: T) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  x.hash(&mut hasher);
+  hasher.finish()
+}
+
 impl Hash for Edn {
   fn hash<H>(&self, _state: &mut H)
   where
@@ -159,7 +310,15 @@ impl Hash for Edn {
       }
       Self::Number(n) => {
         "number:".hash(_state);
-        (*n as usize).hash(_state) // TODO inaccurate solution
+        // `-0.0 == 0.0` and all NaNs are equal, so they need to share bits
+        let bits = if *n == 0.0 {
+          0
+        } else if n.is_nan() {
+          f64::NAN.to_bits()
+        } else {
+          n.to_bits()
+        };
+        bits.hash(_state)
       }
       Self::Symbol(s) => {
         "symbol:".hash(_state);
@@ -188,17 +347,12 @@ impl Hash for Edn {
       }
       Self::Set(v) => {
         "set:".hash(_state);
-        // TODO order for set is stable
-        for x in &v.0 {
-          x.hash(_state)
-        }
+        // combined regardless of iteration order
+        v.iter().map(hash_one).fold(0u64, u64::wrapping_add).hash(_state);
       }
       Self::Map(v) => {
         "map:".hash(_state);
-        // TODO order for map is not stable
-        for x in &v.0 {
-          x.hash(_state)
-        }
+        v.iter().map(hash_one).fold(0u64, u64::wrapping_add).hash(_state);
       }
       Self::Record(EdnRecordView {
         tag: name,
@@ -214,9 +368,9 @@ impl Hash for Edn {
           b.hash(_state);
         }
       }
-      Self::AnyRef(h) => {
+      Self::AnyRef(_) => {
+        // refs with equal data are equal, while data is not hashable
         "any-ref:".hash(_state);
-        ptr::hash(h, _state);
       }
       Self::Atom(a) => {
         "atom:".hash(_state);
@@ -237,15 +391,8 @@ impl Ord for Edn {
       (Self::Bool(_), _) => Less,
       (_, Self::Bool(_)) => Greater,
 
-      (Self::Number(a), Self::Number(b)) => {
-        if a < b {
-          Less
-        } else if a > b {
-          Greater
-        } else {
-          Equal
-        }
-      }
+      // NaN goes after other numbers to keep the order total
+      (Self::Number(a), Self::Number(b)) => a.partial_cmp(b).unwrap_or_else(|| a.is_nan().cmp(&b.is_nan())),
       (Self::Number(_), _) => Less,
       (_, Self::Number(_)) => Greater,
 
@@ -277,19 +424,29 @@ impl Ord for Edn {
       (Self::Buffer(_), _) => Less,
       (_, Self::Buffer(_)) => Greater,
 
-      (Self::Set(a), Self::Set(b)) => match a.len().cmp(&b.len()) {
-        Equal => unreachable!("TODO sets are not cmp ed"), // TODO
-        a => a,
-      },
+      #[cfg(not(feature = "btree-map"))]
+      (Self::Set(a), Self::Set(b)) => a.len().cmp(&b.len()).then_with(|| {
+        let mut xs = a.iter().collect::<Vec<_>>();
+        let mut ys = b.iter().collect::<Vec<_>>();
+        xs.sort();
+        ys.sort();
+        xs.cmp(&ys)
+      }),
+      #[cfg(feature = "btree-map")]
+      (Self::Set(a), Self::Set(b)) => a.cmp(b),
       (Self::Set(_), _) => Less,
       (_, Self::Set(_)) => Greater,
 
-      (Self::Map(a), Self::Map(b)) => {
-        match a.len().cmp(&b.len()) {
-          Equal => unreachable!("TODO maps are not cmp ed {:?} {:?}", a, b), // TODO
-          a => a,
-        }
-      }
+      #[cfg(not(feature = "btree-map"))]
+      (Self::Map(a), Self::Map(b)) => a.len().cmp(&b.len()).then_with(|| {
+        let mut xs = a.iter().collect::<Vec<_>>();
+        let mut ys = b.iter().collect::<Vec<_>>();
+        xs.sort();
+        ys.sort();
+        xs.cmp(&ys)
+      }),
+      #[cfg(feature = "btree-map")]
+      (Self::Map(a), Self::Map(b)) => a.cmp(b),
       (Self::Map(_), _) => Less,
       (_, Self::Map(_)) => Greater,
 
@@ -311,13 +468,7 @@ impl Ord for Edn {
       (Self::Atom(_), _) => Less,
       (_, Self::Atom(_)) => Greater,
 
-      (Self::AnyRef(a), Self::AnyRef(b)) => {
-        if ptr::eq(a, b) {
-          Equal
-        } else {
-          unreachable!("anyref are not cmp ed {:?} {:?}", a, b)
-        }
-      }
+      (Self::AnyRef(a), Self::AnyRef(b)) => a.cmp(b),
     }
   }
 }
@@ -335,7 +486,8 @@ impl PartialEq for Edn {
     match (self, other) {
       (Self::Nil, Self::Nil) => true,
       (Self::Bool(a), Self::Bool(b)) => a == b,
-      (Self::Number(a), Self::Number(b)) => (a - b).abs() < f64::EPSILON,
+      // exact, consistent with `Ord` and `Hash`, use `structural_eq_within` for tolerance
+      (Self::Number(a), Self::Number(b)) => a == b || (a.is_nan() && b.is_nan()),
       (Self::Symbol(a), Self::Symbol(b)) => a == b,
       (Self::Tag(a), Self::Tag(b)) => a == b,
       (Self::Str(a), Self::Str(b)) => a == b,
@@ -353,20 +505,86 @@ impl PartialEq for Edn {
   }
 }
 
+fn all_eq_within(xs: &[Edn], ys: &[Edn], epsilon: f64) -> bool {
+  xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| x.structural_eq_within(y, epsilon))
+}
+
+/// pairs each item of `xs` with the first unused matching item of `ys`
+fn greedy_eq_within<T, I: Iterator<Item = T>>(xs: I, mut ys: Vec<T>, f: impl Fn(&T, &T) -> bool) -> bool {
+  for x in xs {
+    match ys.iter().position(|y| f(&x, y)) {
+      Some(idx) => {
+        ys.swap_remove(idx);
+      }
+      None => return false,
+    }
+  }
+  ys.is_empty()
+}
+
+/// `-0.0` as `0.0`, other values unchanged
+pub(crate) fn normalize_zero(n: f64) -> f64 {
+  if n == 0.0 {
+    0.0
+  } else {
+    n
+  }
+}
+
 /// Support reading from EDN
 impl Edn {
-  /// create new string
+  pub const NIL: Edn = Edn::Nil;
+  pub const TRUE: Edn = Edn::Bool(true);
+  pub const FALSE: Edn = Edn::Bool(false);
+  pub const ZERO: Edn = Edn::Number(0.0);
+  pub const ONE: Edn = Edn::Number(1.0);
+
+  /// empty list, a function since views are not const-constructible
+  pub fn empty_list() -> Self {
+    Edn::List(EdnListView::default())
+  }
+  /// empty map
+  pub fn empty_map() -> Self {
+    Edn::Map(EdnMapView::default())
+  }
+  /// empty set
+  pub fn empty_set() -> Self {
+    Edn::Set(EdnSetView::default())
+  }
+  /// checked number, rejecting NaN and infinities, with `-0.0` turned into `0.0` like the parser does
+  pub fn number(n: f64) -> Result<Self, String> {
+    if n.is_finite() {
+      Ok(Edn::Number(normalize_zero(n)))
+    } else {
+      Err(format!("expected a finite number, got {}", n))
+    }
+  }
+  /// create new string, text is kept as is, `|` or `"` at the start is part of the text,
+  /// as `Edn::str("a")` is what `|a` reads to
   pub fn str<T: Into<Arc<str>>>(s: T) -> Self {
     Edn::Str(s.into())
   }
-  /// create new tag
+  /// create new tag, a leading `:` is dropped like in code, so `Edn::tag(":a")` is the same as `Edn::tag("a")`
   pub fn tag<T: Into<Arc<str>>>(s: T) -> Self {
     Edn::Tag(EdnTag::new(s.into()))
   }
+  /// create new tag with the name as is, for names starting with `:`, written like `::a`
+  pub fn tag_raw<T: Into<Arc<str>>>(s: T) -> Self {
+    Edn::Tag(EdnTag::new_raw(s.into()))
+  }
   /// create new symbol
   pub fn sym<T: Into<Arc<str>>>(s: T) -> Self {
     Edn::Symbol(s.into())
   }
+  /// create new symbol, rejecting empty names which the parser doesn't read
+  pub fn try_sym<T: Into<Arc<str>>>(s: T) -> Result<Self, String> {
+    let s = s.into();
+    if s.is_empty() {
+      Err(String::from("symbol name can not be empty"))
+    } else {
+      Ok(Edn::Symbol(s))
+    }
+  }
   /// create new tuple
   pub fn tuple(tag: Self, extra: Vec<Self>) -> Self {
     Edn::Tuple(EdnTupleView {
@@ -374,7 +592,27 @@ impl Edn {
       extra,
     })
   }
+  /// create atom
+  pub fn atom(value: Edn) -> Self {
+    Edn::Atom(EdnAtomView::new(value))
+  }
+  /// create atom with a label, atoms of the same label share identity
+  pub fn atom_labeled<T: Into<Arc<str>>>(label: T, value: Edn) -> Self {
+    Edn::Atom(EdnAtomView::labeled(label, value))
+  }
+  /// create any-ref, data inside has to be `Send + Sync`
+  ///
+  /// ```compile_fail,E0277
+  /// use std::rc::Rc;
+  ///
+  /// let _ = cirru_edn::Edn::any_ref(Rc::new(1));
+  /// ```
+  #[cfg(not(feature = "unsafe-any-ref-send"))]
+  pub fn any_ref<T: ToOwned + DynEq + Send + Sync + 'static>(d: T) -> Self {
+    Edn::AnyRef(EdnAnyRef::new(d))
+  }
   /// create any-ref
+  #[cfg(feature = "unsafe-any-ref-send")]
   pub fn any_ref<T: ToOwned + DynEq + 'static>(d: T) -> Self {
     Edn::AnyRef(EdnAnyRef::new(d))
   }
@@ -384,75 +622,308 @@ impl Edn {
       Self::Nil | Self::Bool(_) | Self::Number(_) | Self::Symbol(_) | Self::Tag(_) | Self::Str(_)
     )
   }
+  /// check for `AnyRef` at any depth, such data can not be formatted or shared across threads
+  pub(crate) fn has_any_ref(&self) -> bool {
+    self.any_node(|x| matches!(x, Edn::AnyRef(_)))
+  }
+  /// count of values at any depth, map keys included
+  #[cfg(feature = "tracing")]
+  pub(crate) fn value_count(&self) -> usize {
+    let mut count = 0;
+    self.any_node(|_| {
+      count += 1;
+      false
+    });
+    count
+  }
+  /// checks before writing in one walk: no any-refs, which can't be written or ordered in sets and map keys,
+  /// and atoms sharing a label hold equal values.
+  /// maps and sets holding keys changed behind references are checked with `debug_validate` in debug builds
+  pub(crate) fn check_writable(&self) -> Result<(), String> {
+    let mut labels = AtomLabels::default();
+    let mut failure = None;
+    self.any_node(|x| {
+      let checked = match x {
+        Edn::AnyRef(_) => Err(format!(
+          "failed to format, any-ref is not serializable: {}",
+          x.preview(ERROR_PREVIEW_LEN)
+        )),
+        Edn::Atom(a) => labels.check(a).map_err(|e| format!("failed to format, {}", e)),
+        #[cfg(debug_assertions)]
+        Edn::Map(xs) => xs.debug_validate(),
+        #[cfg(debug_assertions)]
+        Edn::Set(xs) => xs.debug_validate(),
+        _ => Ok(()),
+      };
+      checked.map_err(|e| failure = Some(e)).is_err()
+    });
+    failure.map_or(Ok(()), Err)
+  }
+
+  /// walk the tree without recursion until `f` returns true, nodes are visited in the order they are written
+  fn any_node<'a>(&'a self, mut f: impl FnMut(&'a Edn) -> bool) -> bool {
+    let mut stack = vec![self];
+    while let Some(x) = stack.pop() {
+      if f(x) {
+        return true;
+      }
+      let start = stack.len();
+      match x {
+        Edn::Tuple(EdnTupleView { tag, extra }) => {
+          stack.push(tag);
+          stack.extend(extra);
+        }
+        Edn::List(xs) => stack.extend(&xs.0),
+        Edn::Set(xs) => stack.extend(xs),
+        Edn::Map(xs) => {
+          for (k, v) in xs {
+            stack.push(k);
+            stack.push(v);
+          }
+        }
+        Edn::Record(EdnRecordView { pairs, .. }) => stack.extend(pairs.iter().map(|(_, v)| v)),
+        Edn::Atom(a) => stack.push(&a.value),
+        _ => {}
+      }
+      // popped from the end, so children are reversed to come out in order
+      stack[start..].reverse();
+    }
+    false
+  }
+  /// names from `type_name` of all variants, in the order of variants
+  pub const TYPE_NAMES: [&'static str; 15] = [
+    "nil", "bool", "number", "symbol", "tag", "string", "quote", "tuple", "list", "set", "map", "record", "buffer",
+    "any-ref", "atom",
+  ];
+  fn type_index(&self) -> usize {
+    match self {
+      Self::Nil => 0,
+      Self::Bool(_) => 1,
+      Self::Number(_) => 2,
+      Self::Symbol(_) => 3,
+      Self::Tag(_) => 4,
+      Self::Str(_) => 5,
+      Self::Quote(_) => 6,
+      Self::Tuple(_) => 7,
+      Self::List(_) => 8,
+      Self::Set(_) => 9,
+      Self::Map(_) => 10,
+      Self::Record(_) => 11,
+      Self::Buffer(_) => 12,
+      Self::AnyRef(_) => 13,
+      Self::Atom(_) => 14,
+    }
+  }
+  /// name of the variant, used in error messages
+  pub fn type_name(&self) -> &'static str {
+    Self::TYPE_NAMES[self.type_index()]
+  }
+  /// `type_name` as a tag for dispatch tables keyed by tags, tags are created once and shared
+  pub fn type_tag(&self) -> &'static EdnTag {
+    static TYPE_TAGS: OnceLock<Vec<EdnTag>> = OnceLock::new();
+    let tags = TYPE_TAGS.get_or_init(|| Edn::TYPE_NAMES.iter().map(|s| EdnTag::from_static(s)).collect());
+    &tags[self.type_index()]
+  }
+  /// check variant by a name from `TYPE_NAMES`
+  pub fn is_type(&self, name: &str) -> bool {
+    self.type_name() == name
+  }
+  /// create list from values that convert into Edn, `Edn::list([1, 2, 3])`
+  pub fn list<T: Into<Edn>>(items: impl IntoIterator<Item = T>) -> Self {
+    Self::List(EdnListView(items.into_iter().map(Into::into).collect()))
+  }
+  /// create set from values that convert into Edn, duplicated items are merged
+  pub fn set<T: Into<Edn>>(items: impl IntoIterator<Item = T>) -> Self {
+    Self::Set(EdnSetView(items.into_iter().map(Into::into).collect()))
+  }
+  /// create map from pairs that convert into Edn, `Edn::map([("a", 1), ("b", 2)])` has string keys,
+  /// use `EdnTag` or `Edn::tag` for tag keys. later pairs overwrite earlier ones of the same key
+  pub fn map<K: Into<Edn>, V: Into<Edn>>(pairs: impl IntoIterator<Item = (K, V)>) -> Self {
+    Self::Map(EdnMapView(
+      pairs.into_iter().map(|(k, v)| (k.into(), v.into())).collect(),
+    ))
+  }
   pub fn map_from_iter<T: IntoIterator<Item = (Edn, Edn)>>(pairs: T) -> Self {
-    Self::Map(EdnMapView(HashMap::from_iter(pairs)))
+    Self::Map(EdnMapView(pairs.into_iter().collect()))
   }
+  /// build record from pairs as they are, callers need to keep field tags unique,
+  /// or use `record_from_pairs_checked`
   pub fn record_from_pairs(tag: EdnTag, pairs: &[(EdnTag, Edn)]) -> Self {
     Self::Record(EdnRecordView {
       tag,
       pairs: pairs.to_vec(),
     })
   }
+  /// like `record_from_pairs` but fails on duplicated field tags, order of fields is kept
+  pub fn record_from_pairs_checked(tag: EdnTag, pairs: &[(EdnTag, Edn)]) -> Result<Self, String> {
+    for (idx, (k, _)) in pairs.iter().enumerate() {
+      if pairs[..idx].iter().any(|(k2, _)| k2 == k) {
+        return Err(format!("duplicated field `{}` in record :{}", k, tag));
+      }
+    }
+    Ok(Self::record_from_pairs(tag, pairs))
+  }
   pub fn read_string(&self) -> Result<String, String> {
     match self {
       Edn::Str(s) => Ok((**s).to_owned()),
-      a => Err(format!("failed to convert to string: {}", a)),
+      a => Err(format!("failed to convert to string: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
   pub fn read_symbol_string(&self) -> Result<String, String> {
     match self {
       Edn::Symbol(s) => Ok((**s).to_owned()),
-      a => Err(format!("failed to convert to symbol: {}", a)),
+      a => Err(format!("failed to convert to symbol: {}", a.preview(ERROR_PREVIEW_LEN))),
+    }
+  }
+  /// text of a string, a tag or a symbol, for data that doesn't tell names apart.
+  /// `TryFrom<Edn> for String` accepts strings only, use `read_name` to also know which kind it was
+  pub fn read_string_lossy(&self) -> Result<String, String> {
+    match self {
+      Edn::Str(s) | Edn::Symbol(s) => Ok((**s).to_owned()),
+      Edn::Tag(t) => Ok(t.ref_str().to_owned()),
+      a => Err(format!("failed to convert to string: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
   pub fn read_str(&self) -> Result<Arc<str>, String> {
     match self {
       Edn::Str(s) => Ok(s.to_owned()),
-      a => Err(format!("failed to convert to string: {}", a)),
+      a => Err(format!("failed to convert to string: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
   pub fn read_symbol_str(&self) -> Result<Arc<str>, String> {
     match self {
       Edn::Symbol(s) => Ok(s.to_owned()),
-      a => Err(format!("failed to convert to symbol: {}", a)),
+      a => Err(format!("failed to convert to symbol: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
   pub fn read_tag_str(&self) -> Result<Arc<str>, String> {
     match self {
-      Edn::Tag(s) => Ok(s.arc_str()),
-      a => Err(format!("failed to convert to tag: {}", a)),
+      Edn::Tag(s) => Ok(s.0.to_owned()),
+      a => Err(format!("failed to convert to tag: {}", a.preview(ERROR_PREVIEW_LEN))),
+    }
+  }
+  /// borrow name of a tag, `None` for other values
+  pub fn tag_name(&self) -> Option<&str> {
+    match self {
+      Edn::Tag(t) => Some(t.ref_str()),
+      _ => None,
     }
   }
+  /// check for a tag of this name without allocating, false for other values
+  pub fn is_tag_named(&self, name: &str) -> bool {
+    self.tag_name() == Some(name)
+  }
+  /// borrow name of a symbol, `None` for other values
+  pub fn symbol_name(&self) -> Option<&str> {
+    match self {
+      Edn::Symbol(s) => Some(s),
+      _ => None,
+    }
+  }
+  /// check for a symbol of this name, false for other values
+  pub fn is_symbol_named(&self, name: &str) -> bool {
+    self.symbol_name() == Some(name)
+  }
+  /// check for a string equal to `s`, false for other values
+  pub fn is_str_eq(&self, s: &str) -> bool {
+    matches!(self, Edn::Str(x) if &**x == s)
+  }
 
   pub fn read_bool(&self) -> Result<bool, String> {
     match self {
       Edn::Bool(b) => Ok(*b),
-      a => Err(format!("failed to convert to bool: {}", a)),
+      a => Err(format!("failed to convert to bool: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 
   pub fn read_number(&self) -> Result<f64, String> {
     match self {
       Edn::Number(n) => Ok(*n),
-      a => Err(format!("failed to convert to number: {}", a)),
+      a => Err(format!("failed to convert to number: {}", a.preview(ERROR_PREVIEW_LEN))),
+    }
+  }
+
+  /// accepts bool, and `true`/`false` in strings or tags, errors on anything else
+  pub fn coerce_bool(&self) -> Result<bool, String> {
+    match self {
+      Edn::Bool(b) => Ok(*b),
+      Edn::Str(s) if &**s == "true" => Ok(true),
+      Edn::Str(s) if &**s == "false" => Ok(false),
+      Edn::Tag(t) if t.ref_str() == "true" => Ok(true),
+      Edn::Tag(t) if t.ref_str() == "false" => Ok(false),
+      a => Err(format!(
+        "failed to coerce {} to bool: {}",
+        a.type_name(),
+        a.preview(ERROR_PREVIEW_LEN)
+      )),
+    }
+  }
+
+  /// lenient reading with `coerce_bool`, falls back to `default` on nil or other values
+  pub fn read_bool_or(&self, default: bool) -> bool {
+    self.coerce_bool().unwrap_or(default)
+  }
+
+  /// accepts numbers, and numbers in strings read by `parse_number`, falls back to `default` on nil or other values
+  pub fn read_number_or(&self, default: f64) -> f64 {
+    match self {
+      Edn::Number(n) => *n,
+      Edn::Str(s) => crate::parse_number(s.trim()).unwrap_or(default),
+      _ => default,
+    }
+  }
+
+  /// accepts strings and tags, falls back to `default` on nil or other values
+  pub fn read_string_or(&self, default: &str) -> String {
+    match self {
+      Edn::Str(s) => (**s).to_owned(),
+      Edn::Tag(t) => t.to_string(),
+      _ => default.to_owned(),
     }
   }
 
   pub fn read_quoted_cirru(&self) -> Result<Cirru, String> {
     match self {
       Edn::Quote(c) => Ok(c.to_owned()),
-      a => Err(format!("failed to convert to cirru code: {}", a)),
+      a => Err(format!(
+        "failed to convert to cirru code: {}",
+        a.preview(ERROR_PREVIEW_LEN)
+      )),
+    }
+  }
+
+  /// create quoted code from Cirru text, a single expression is quoted directly,
+  /// multiple expressions are wrapped in one `Cirru::List` in order
+  pub fn quote_from_str(s: &str) -> Result<Self, String> {
+    let mut xs = cirru_parser::parse(s)?;
+    match xs.len() {
+      0 => Err(format!("expected code to quote, got: {:?}", s)),
+      1 => Ok(Edn::Quote(xs.remove(0))),
+      _ => Ok(Edn::Quote(Cirru::List(xs))),
+    }
+  }
+
+  /// format quoted code back to one-liner text, reverse of `quote_from_str`
+  pub fn read_quoted_string(&self) -> Result<String, String> {
+    match self {
+      Edn::Quote(c @ Cirru::List(_)) => cirru_parser::format_expr_one_liner(c),
+      Edn::Quote(c @ Cirru::Leaf(_)) => Ok(c.to_string()),
+      a => Err(format!(
+        "failed to convert to cirru code: {}",
+        a.preview(ERROR_PREVIEW_LEN)
+      )),
     }
   }
 
-  // viewers
+  // viewers, list/map/set treat nil as empty collections,
+  // while record and tuple require a tag, nil is only accepted by `view_record_or_nil` and `view_tuple_or_nil`
 
   /// get List variant in struct
   pub fn view_list(&self) -> Result<EdnListView, String> {
     match self {
       Edn::List(xs) => Ok((*xs).to_owned()),
       Edn::Nil => Ok(EdnListView::default()),
-      a => Err(format!("failed to convert to list: {}", a)),
+      a => Err(format!("failed to convert to list: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 
@@ -461,7 +932,7 @@ impl Edn {
     match self {
       Edn::Map(xs) => Ok(xs.to_owned()),
       Edn::Nil => Ok(EdnMapView::default()),
-      a => Err(format!("failed to convert to map: {}", a)),
+      a => Err(format!("failed to convert to map: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 
@@ -470,7 +941,7 @@ impl Edn {
     match self {
       Edn::Set(xs) => Ok(xs.to_owned()),
       Edn::Nil => Ok(EdnSetView::default()),
-      a => Err(format!("failed to convert to set: {}", a)),
+      a => Err(format!("failed to convert to set: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 
@@ -481,10 +952,54 @@ impl Edn {
         tag: tag.to_owned(),
         pairs: pairs.to_owned(),
       }),
-      a => Err(format!("failed to convert to record: {}", a)),
+      a => Err(format!("failed to convert to record: {}", a.preview(ERROR_PREVIEW_LEN))),
+    }
+  }
+
+  /// iterate over items of a list without cloning, nil is an empty list like in `view_list`
+  pub fn iter_list(&self) -> Result<impl Iterator<Item = &Edn>, String> {
+    match self {
+      Edn::List(xs) => Ok(Some(xs.iter()).into_iter().flatten()),
+      Edn::Nil => Ok(None.into_iter().flatten()),
+      a => Err(a.iter_error("list")),
+    }
+  }
+
+  /// iterate over entries of a map without cloning, nil is an empty map like in `view_map`
+  pub fn iter_map(&self) -> Result<impl Iterator<Item = (&Edn, &Edn)>, String> {
+    match self {
+      Edn::Map(xs) => Ok(Some(xs.iter()).into_iter().flatten()),
+      Edn::Nil => Ok(None.into_iter().flatten()),
+      a => Err(a.iter_error("map")),
     }
   }
 
+  /// iterate over items of a set without cloning, nil is an empty set like in `view_set`
+  pub fn iter_set(&self) -> Result<impl Iterator<Item = &Edn>, String> {
+    match self {
+      Edn::Set(xs) => Ok(Some(xs.iter()).into_iter().flatten()),
+      Edn::Nil => Ok(None.into_iter().flatten()),
+      a => Err(a.iter_error("set")),
+    }
+  }
+
+  /// iterate over fields of a record in order, nil is not a record
+  pub fn iter_record(&self) -> Result<impl Iterator<Item = (&EdnTag, &Edn)>, String> {
+    match self {
+      Edn::Record(xs) => Ok(xs.iter()),
+      a => Err(a.iter_error("record")),
+    }
+  }
+
+  fn iter_error(&self, expected: &str) -> String {
+    format!(
+      "failed to iterate {}, got {}: {}",
+      expected,
+      self.type_name(),
+      self.preview(ERROR_PREVIEW_LEN)
+    )
+  }
+
   /// get Tuple variant in struct
   pub fn view_tuple(&self) -> Result<EdnTupleView, String> {
     match self {
@@ -492,7 +1007,202 @@ impl Edn {
         tag: tag.to_owned(),
         extra: extra.to_owned(),
       }),
-      a => Err(format!("failed to convert to tuple: {}", a)),
+      a => Err(format!("failed to convert to tuple: {}", a.preview(ERROR_PREVIEW_LEN))),
+    }
+  }
+
+  /// get Record variant in struct, `None` for nil
+  pub fn view_record_or_nil(&self) -> Result<Option<EdnRecordView>, String> {
+    match self {
+      Edn::Nil => Ok(None),
+      a => a.view_record().map(Some),
+    }
+  }
+
+  /// get Tuple variant in struct, `None` for nil
+  pub fn view_tuple_or_nil(&self) -> Result<Option<EdnTupleView>, String> {
+    match self {
+      Edn::Nil => Ok(None),
+      a => a.view_tuple().map(Some),
+    }
+  }
+
+  /// borrow tag and extra values of a tuple without cloning, `None` for other values
+  ///
+  /// ```
+  /// use cirru_edn::Edn;
+  ///
+  /// let v = cirru_edn::parse(":: :point 1 2").unwrap();
+  /// if let Some((tag, [x, y])) = v.as_tuple() {
+  ///   assert!(tag.is_tag_named("point"));
+  ///   assert_eq!((x, y), (&Edn::Number(1.0), &Edn::Number(2.0)));
+  /// }
+  /// ```
+  pub fn as_tuple(&self) -> Option<(&Edn, &[Edn])> {
+    match self {
+      Edn::Tuple(EdnTupleView { tag, extra }) => Some((tag, extra)),
+      _ => None,
+    }
+  }
+
+  /// borrow a list in the shape of `[] :op arg1 arg2` as the tag and arguments, `None` for other values
+  ///
+  /// ```
+  /// use cirru_edn::Edn;
+  ///
+  /// let v = cirru_edn::parse("[] :move 1 2").unwrap();
+  /// if let Some((op, [x, y])) = v.as_op() {
+  ///   assert_eq!(op.ref_str(), "move");
+  ///   assert_eq!((x, y), (&Edn::Number(1.0), &Edn::Number(2.0)));
+  /// }
+  /// ```
+  pub fn as_op(&self) -> Option<(&EdnTag, &[Edn])> {
+    match self {
+      Edn::List(EdnListView(xs)) => match xs.split_first() {
+        Some((Edn::Tag(op), args)) => Some((op, args)),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
+  /// concatenate lists, nil is treated as empty list
+  pub fn concat(parts: &[Edn]) -> Result<Edn, String> {
+    let mut ys = EdnListView::default();
+    for (idx, part) in parts.iter().enumerate() {
+      match part {
+        Edn::List(xs) => ys.0.extend(xs.0.iter().cloned()),
+        Edn::Nil => {}
+        a => {
+          return Err(format!(
+            "failed to concat, part {} is not a list but {}: {}",
+            idx,
+            a.type_name(),
+            a.preview(ERROR_PREVIEW_LEN)
+          ))
+        }
+      }
+    }
+    Ok(Edn::List(ys))
+  }
+
+  /// flatten nested lists up to `depth` levels, other values in lists are kept. nil is treated as empty list
+  pub fn flatten(&self, depth: usize) -> Result<Edn, String> {
+    self.to_owned().into_flattened(depth)
+  }
+
+  /// like `flatten` but takes ownership to avoid cloning
+  pub fn into_flattened(self, depth: usize) -> Result<Edn, String> {
+    match self {
+      Edn::List(xs) => {
+        let mut ys = Vec::with_capacity(xs.len());
+        flatten_into(xs.0, depth, &mut ys);
+        Ok(Edn::List(EdnListView(ys)))
+      }
+      Edn::Nil => Ok(Edn::List(EdnListView::default())),
+      a => Err(format!(
+        "failed to flatten {}: {}",
+        a.type_name(),
+        a.preview(ERROR_PREVIEW_LEN)
+      )),
+    }
+  }
+
+  /// compare structurally with numbers allowed to differ by `epsilon`, other leaves compare exactly.
+  /// sets and maps are matched greedily, each item pairs with the first unused tolerant match,
+  /// so in rare cases with items close to several others a valid pairing may be missed
+  pub fn structural_eq_within(&self, other: &Edn, epsilon: f64) -> bool {
+    match (self, other) {
+      (Edn::Number(a), Edn::Number(b)) => a == b || (a - b).abs() <= epsilon || (a.is_nan() && b.is_nan()),
+      (Edn::Tuple(a), Edn::Tuple(b)) => {
+        a.tag.structural_eq_within(&b.tag, epsilon) && all_eq_within(&a.extra, &b.extra, epsilon)
+      }
+      (Edn::List(a), Edn::List(b)) => all_eq_within(&a.0, &b.0, epsilon),
+      (Edn::Set(a), Edn::Set(b)) => {
+        a.len() == b.len() && greedy_eq_within(a.iter(), b.iter().collect(), |x, y| x.structural_eq_within(y, epsilon))
+      }
+      (Edn::Map(a), Edn::Map(b)) => {
+        a.len() == b.len()
+          && greedy_eq_within(a.iter(), b.iter().collect(), |(k1, v1), (k2, v2)| {
+            k1.structural_eq_within(k2, epsilon) && v1.structural_eq_within(v2, epsilon)
+          })
+      }
+      (Edn::Record(a), Edn::Record(b)) => {
+        a.tag == b.tag
+          && a.pairs.len() == b.pairs.len()
+          && a
+            .pairs
+            .iter()
+            .zip(&b.pairs)
+            .all(|((k1, v1), (k2, v2))| k1 == k2 && v1.structural_eq_within(v2, epsilon))
+      }
+      (Edn::Atom(a), Edn::Atom(b)) => a.label == b.label && a.value.structural_eq_within(&b.value, epsilon),
+      (a, b) => a == b,
+    }
+  }
+
+  /// borrow value from map by string key, then by tag key like `EdnMapView::get_by`, nil if missing.
+  /// nil is treated as empty map
+  pub fn map_get(&self, key: &str) -> Result<&Edn, String> {
+    match self {
+      Edn::Map(xs) => Ok(xs.get_by(key).unwrap_or(&Edn::Nil)),
+      Edn::Nil => Ok(&Edn::Nil),
+      a => Err(format!(
+        "failed to get `{}` from {}: {}",
+        key,
+        a.type_name(),
+        a.preview(ERROR_PREVIEW_LEN)
+      )),
+    }
+  }
+
+  /// borrow item from list, nil if out of range. nil is treated as empty list
+  pub fn list_get(&self, idx: usize) -> Result<&Edn, String> {
+    match self {
+      Edn::List(xs) => Ok(xs.get_ref_or_nil(idx)),
+      Edn::Nil => Ok(&Edn::Nil),
+      a => Err(format!(
+        "failed to get index {} from {}: {}",
+        idx,
+        a.type_name(),
+        a.preview(ERROR_PREVIEW_LEN)
+      )),
+    }
+  }
+
+  /// borrow field from record, nil if missing
+  pub fn record_get(&self, key: &str) -> Result<&Edn, String> {
+    match self {
+      Edn::Record(EdnRecordView { pairs, .. }) => Ok(
+        pairs
+          .iter()
+          .find(|(k, _)| k.ref_str() == key)
+          .map(|(_, v)| v)
+          .unwrap_or(&Edn::Nil),
+      ),
+      a => Err(format!(
+        "failed to get field `{}` from {}: {}",
+        key,
+        a.type_name(),
+        a.preview(ERROR_PREVIEW_LEN)
+      )),
+    }
+  }
+}
+
+/// with a stack of open lists, so deeply nested lists do not overflow the stack
+fn flatten_into(xs: Vec<Edn>, depth: usize, ys: &mut Vec<Edn>) {
+  let mut stack = vec![(xs.into_iter(), depth)];
+  while let Some((items, depth)) = stack.last_mut() {
+    match items.next() {
+      Some(Edn::List(zs)) if *depth > 0 => {
+        let depth = *depth - 1;
+        stack.push((zs.0.into_iter(), depth));
+      }
+      Some(a) => ys.push(a),
+      None => {
+        stack.pop();
+      }
     }
   }
 }
@@ -502,7 +1212,7 @@ impl TryFrom<Edn> for EdnTag {
   fn try_from(x: Edn) -> Result<EdnTag, String> {
     match x {
       Edn::Tag(k) => Ok(k),
-      _ => Err(format!("failed to convert to tag: {}", x)),
+      _ => Err(format!("failed to convert to tag: {}", x.preview(ERROR_PREVIEW_LEN))),
     }
   }
 }
@@ -519,27 +1229,20 @@ impl From<&EdnTag> for Edn {
   }
 }
 
+/// strings only, tags and symbols are rejected.
+/// use `Edn::read_string_lossy` to accept all three, or `Edn::read_name` to also know the kind
 impl TryFrom<Edn> for String {
   type Error = String;
   fn try_from(x: Edn) -> Result<String, Self::Error> {
-    match x {
-      Edn::Str(s) => Ok((*s).to_owned()),
-      Edn::Symbol(s) => Err(format!("cannot convert symbol {} into string", s)),
-      Edn::Tag(s) => Ok(s.to_string()),
-      a => Err(format!("failed to convert to string: {}", a)),
-    }
+    x.read_string()
   }
 }
 
+/// strings only, same as `TryFrom<Edn> for String`
 impl TryFrom<&Edn> for String {
   type Error = String;
   fn try_from(x: &Edn) -> Result<String, Self::Error> {
-    match x {
-      Edn::Str(s) => Ok((**s).to_owned()),
-      Edn::Symbol(s) => Err(format!("cannot convert symbol {} into string", s)),
-      Edn::Tag(s) => Ok(s.to_string()),
-      a => Err(format!("failed to convert to string: {}", a)),
-    }
+    x.read_string()
   }
 }
 
@@ -567,26 +1270,30 @@ impl From<&Box<str>> for Edn {
   }
 }
 
+/// strings only, like `TryFrom<Edn> for String`
 impl TryFrom<Edn> for Arc<str> {
   type Error = String;
   fn try_from(x: Edn) -> Result<Self, Self::Error> {
     match x {
-      Edn::Str(s) => Ok((*s).into()),
-      Edn::Tag(s) => Ok(s.arc_str()),
-      a => Err(format!("failed to convert to arc str: {}", a)),
+      Edn::Str(s) => Ok(s),
+      a => Err(format!(
+        "failed to convert to arc str: {}",
+        a.preview(ERROR_PREVIEW_LEN)
+      )),
     }
   }
 }
 
+/// shares the allocation, no copying of the string
 impl From<Arc<str>> for Edn {
   fn from(x: Arc<str>) -> Self {
-    Edn::Str((*x).into())
+    Edn::Str(x)
   }
 }
 
 impl From<&Arc<str>> for Edn {
   fn from(x: &Arc<str>) -> Self {
-    Edn::Str((**x).into())
+    Edn::Str(x.to_owned())
   }
 }
 
@@ -595,7 +1302,7 @@ impl TryFrom<Edn> for bool {
   fn try_from(x: Edn) -> Result<Self, Self::Error> {
     match x {
       Edn::Bool(s) => Ok(s),
-      a => Err(format!("failed to convert to bool: {}", a)),
+      a => Err(format!("failed to convert to bool: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 }
@@ -612,16 +1319,7 @@ impl From<&bool> for Edn {
   }
 }
 
-impl TryFrom<Edn> for f64 {
-  type Error = String;
-  fn try_from(x: Edn) -> Result<Self, Self::Error> {
-    match x {
-      Edn::Number(s) => Ok(s),
-      a => Err(format!("failed to convert to number: {}", a)),
-    }
-  }
-}
-
+/// raw constructor keeping `-0.0`, NaN and infinities as they are, see `Edn::number` for a checked one
 impl From<f64> for Edn {
   fn from(x: f64) -> Self {
     Edn::Number(x)
@@ -634,16 +1332,6 @@ impl From<&f64> for Edn {
   }
 }
 
-impl TryFrom<Edn> for f32 {
-  type Error = String;
-  fn try_from(x: Edn) -> Result<Self, Self::Error> {
-    match x {
-      Edn::Number(s) => Ok(s as f32),
-      a => Err(format!("failed to convert to number: {}", a)),
-    }
-  }
-}
-
 impl From<f32> for Edn {
   fn from(x: f32) -> Self {
     Edn::Number(x as f64)
@@ -656,16 +1344,6 @@ impl From<&f32> for Edn {
   }
 }
 
-impl TryFrom<Edn> for i64 {
-  type Error = String;
-  fn try_from(x: Edn) -> Result<Self, Self::Error> {
-    match x {
-      Edn::Number(s) => Ok(s as i64),
-      a => Err(format!("failed to convert to number: {}", a)),
-    }
-  }
-}
-
 impl From<i64> for Edn {
   fn from(x: i64) -> Self {
     Edn::Number(x as f64)
@@ -678,6 +1356,18 @@ impl From<&i64> for Edn {
   }
 }
 
+impl From<i32> for Edn {
+  fn from(x: i32) -> Self {
+    Edn::Number(x as f64)
+  }
+}
+
+impl From<&i32> for Edn {
+  fn from(x: &i32) -> Self {
+    Edn::Number(*x as f64)
+  }
+}
+
 impl From<u8> for Edn {
   fn from(x: u8) -> Self {
     Edn::Number(x as f64)
@@ -696,22 +1386,6 @@ impl From<usize> for Edn {
   }
 }
 
-impl TryFrom<Edn> for u8 {
-  type Error = String;
-  fn try_from(x: Edn) -> Result<Self, Self::Error> {
-    match x {
-      Edn::Number(s) => {
-        if s >= u8::MIN as f64 && s <= u8::MAX as f64 && s.fract().abs() <= f64::EPSILON {
-          Ok(s as u8)
-        } else {
-          Err(format!("invalid u8 value: {}", s))
-        }
-      }
-      a => Err(format!("failed to convert to u8: {}", a)),
-    }
-  }
-}
-
 impl From<i8> for Edn {
   fn from(x: i8) -> Self {
     Edn::Number(x as f64)
@@ -730,22 +1404,6 @@ impl From<&[Edn]> for Edn {
   }
 }
 
-impl TryFrom<Edn> for i8 {
-  type Error = String;
-  fn try_from(x: Edn) -> Result<Self, Self::Error> {
-    match x {
-      Edn::Number(s) => {
-        if s >= i8::MIN as f64 && s <= i8::MAX as f64 && s.fract().abs() <= f64::EPSILON {
-          Ok(s as i8)
-        } else {
-          Err(format!("invalid i8 value: {}", s))
-        }
-      }
-      a => Err(format!("failed to convert to i8: {}", a)),
-    }
-  }
-}
-
 impl From<Cirru> for Edn {
   fn from(x: Cirru) -> Self {
     Edn::Quote(x)
@@ -763,11 +1421,15 @@ impl TryFrom<Edn> for Cirru {
   fn try_from(x: Edn) -> Result<Self, Self::Error> {
     match x {
       Edn::Quote(s) => Ok(s),
-      a => Err(format!("failed to convert to cirru code: {}", a)),
+      a => Err(format!(
+        "failed to convert to cirru code: {}",
+        a.preview(ERROR_PREVIEW_LEN)
+      )),
     }
   }
 }
 
+/// items of a list, or bytes of a buffer as numbers, so `Vec<u8>` can be read from `buf ...`
 impl<T> TryFrom<Edn> for Vec<T>
 where
   T: TryFrom<Edn, Error = String>,
@@ -783,12 +1445,30 @@ where
         }
         Ok(ys)
       }
+      Edn::Buffer(buf) => buf.into_iter().map(|b| Edn::Number(b as f64).try_into()).collect(),
       Edn::Nil => Ok(vec![]),
-      a => Err(format!("failed to convert to vec: {}", a)),
+      a => Err(format!("failed to convert to vec: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 }
 
+/// like `Vec<T>`, with a check of length
+impl<T, const N: usize> TryFrom<Edn> for [T; N]
+where
+  T: TryFrom<Edn, Error = String>,
+{
+  type Error = String;
+  fn try_from(x: Edn) -> Result<Self, Self::Error> {
+    let ys: Vec<T> = match x {
+      Edn::List(_) | Edn::Buffer(_) => x.try_into()?,
+      a => return Err(format!("failed to convert to array: {}", a.preview(ERROR_PREVIEW_LEN))),
+    };
+    let len = ys.len();
+    ys.try_into()
+      .map_err(|_| format!("failed to convert to array of {} items, got {}", N, len))
+  }
+}
+
 /// `Option<T>` is a special case to convert since it has it's own implementation in core.
 /// To handle `Edn::Nil` which is dynamically typed, some code like this is required:
 /// ```ignore
@@ -852,6 +1532,35 @@ where
   }
 }
 
+/// collects values into a list
+///
+/// ```
+/// use cirru_edn::Edn;
+///
+/// let xs = (1..4).map(|i| Edn::Number(i as f64 * 2.0)).collect::<Edn>();
+/// assert_eq!(xs, cirru_edn::parse("[] 2 4 6").unwrap());
+/// ```
+impl FromIterator<Edn> for Edn {
+  fn from_iter<T: IntoIterator<Item = Edn>>(iter: T) -> Self {
+    Edn::List(iter.into_iter().collect())
+  }
+}
+
+/// collects pairs into a map, later values replace earlier ones of the same key.
+/// records need a tag, collect into `EdnRecordView` instead
+///
+/// ```
+/// use cirru_edn::Edn;
+///
+/// let m = ["a", "b"].into_iter().map(|k| (Edn::tag(k), Edn::str(k.to_uppercase()))).collect::<Edn>();
+/// assert_eq!(m, cirru_edn::parse("{} (:a |A) (:b |B)").unwrap());
+/// ```
+impl FromIterator<(Edn, Edn)> for Edn {
+  fn from_iter<T: IntoIterator<Item = (Edn, Edn)>>(iter: T) -> Self {
+    Edn::Map(iter.into_iter().collect())
+  }
+}
+
 impl<T> TryFrom<Edn> for HashSet<T>
 where
   T: TryFrom<Edn, Error = String> + Eq + Hash,
@@ -861,14 +1570,14 @@ where
     match x {
       Edn::Set(xs) => {
         let mut ys = HashSet::new();
-        for x in xs.0 {
+        for x in xs {
           let y = x.try_into()?;
           ys.insert(y);
         }
         Ok(ys)
       }
       Edn::Nil => Ok(HashSet::new()),
-      a => Err(format!("failed to convert to vec: {}", a)),
+      a => Err(format!("failed to convert to vec: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 }
@@ -901,15 +1610,15 @@ where
     match x {
       Edn::Map(xs) => {
         let mut ys = HashMap::new();
-        for (k, v) in &xs.0 {
-          let k = k.to_owned().try_into()?;
-          let v = v.to_owned().try_into()?;
+        for (k, v) in xs {
+          let k = k.try_into()?;
+          let v = v.try_into()?;
           ys.insert(k, v);
         }
         Ok(ys)
       }
       Edn::Nil => Ok(HashMap::new()),
-      a => Err(format!("failed to convert to vec: {}", a)),
+      a => Err(format!("failed to convert to vec: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 }
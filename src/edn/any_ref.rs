@@ -1,8 +1,10 @@
 //! AnyRef is designed to hold any Rust data, which is used in Clacit FFIs.
 
 use std::{
-  any::Any,
-  fmt::Debug,
+  any::{self, Any},
+  cmp::Ordering,
+  fmt::{self, Debug, Write},
+  hash::{DefaultHasher, Hasher},
   sync::{Arc, RwLock},
 };
 
@@ -10,6 +12,7 @@ use std::{
 pub trait DynEq: Debug {
   fn as_any(&self) -> &dyn Any;
   fn do_eq(&self, rhs: &dyn DynEq) -> bool;
+  fn type_name(&self) -> &'static str;
 }
 
 impl<T> DynEq for T
@@ -27,6 +30,10 @@ where
       false
     }
   }
+
+  fn type_name(&self) -> &'static str {
+    any::type_name::<T>()
+  }
 }
 
 impl PartialEq for dyn DynEq {
@@ -35,18 +42,27 @@ impl PartialEq for dyn DynEq {
   }
 }
 
-/// data inside any-ref is allowed to be mutable
+#[cfg(not(feature = "unsafe-any-ref-send"))]
+type AnyRefData = dyn DynEq + Send + Sync;
+#[cfg(feature = "unsafe-any-ref-send")]
+type AnyRefData = dyn DynEq;
+
+/// data inside any-ref is allowed to be mutable, and has to be `Send + Sync`
+/// unless the `unsafe-any-ref-send` feature is enabled
 #[derive(Debug, Clone)]
-pub struct EdnAnyRef(pub Arc<RwLock<dyn DynEq>>);
+pub struct EdnAnyRef(pub Arc<RwLock<AnyRefData>>);
 
-/// cannot predict behavior yet, but to bypass type checking
+// SAFETY: none, the old behavior kept for payloads like `Rc` that are never shared across threads,
+// sending such an any-ref to another thread is undefined behavior
+#[cfg(feature = "unsafe-any-ref-send")]
 unsafe impl Send for EdnAnyRef {}
-/// cannot predict behavior yet, but to bypass type checking
+#[cfg(feature = "unsafe-any-ref-send")]
 unsafe impl Sync for EdnAnyRef {}
 
 impl PartialEq for EdnAnyRef {
   fn eq(&self, other: &Self) -> bool {
-    if std::ptr::addr_eq(&self, &other) {
+    // clones share the allocation, and reading the same lock twice might deadlock
+    if Arc::ptr_eq(&self.0, &other.0) {
       true
     } else {
       let a = self.0.read().expect("read any-ref");
@@ -58,7 +74,60 @@ impl PartialEq for EdnAnyRef {
 
 impl Eq for EdnAnyRef {}
 
+/// refs with equal data are `Equal`, consistent with `PartialEq`, other refs are ordered by type names of data,
+/// then by hashes of their `Debug` output and at last by addresses of allocations.
+/// the order is only for sorting without panics, it holds no meaning of the data
+impl Ord for EdnAnyRef {
+  fn cmp(&self, other: &Self) -> Ordering {
+    // reading the same lock twice might deadlock
+    if Arc::ptr_eq(&self.0, &other.0) {
+      return Ordering::Equal;
+    }
+    let a = self.0.read().expect("read any-ref");
+    let b = other.0.read().expect("read any-ref");
+    if a.do_eq(&*b) {
+      return Ordering::Equal;
+    }
+    a.type_name()
+      .cmp(b.type_name())
+      .then_with(|| debug_hash(&*a).cmp(&debug_hash(&*b)))
+      .then_with(|| {
+        Arc::as_ptr(&self.0)
+          .cast::<()>()
+          .cmp(&Arc::as_ptr(&other.0).cast::<()>())
+      })
+  }
+}
+
+/// hashes `Debug` output as it is written, without building a string
+fn debug_hash(x: &dyn DynEq) -> u64 {
+  struct HashWriter(DefaultHasher);
+
+  impl fmt::Write for HashWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+      self.0.write(s.as_bytes());
+      Ok(())
+    }
+  }
+
+  let mut w = HashWriter(DefaultHasher::new());
+  let _ = write!(w, "{:?}", x);
+  w.0.finish()
+}
+
+impl PartialOrd for EdnAnyRef {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
 impl EdnAnyRef {
+  #[cfg(not(feature = "unsafe-any-ref-send"))]
+  pub fn new<T: ToOwned + DynEq + Send + Sync + 'static>(d: T) -> Self {
+    EdnAnyRef(Arc::new(RwLock::new(d)))
+  }
+
+  #[cfg(feature = "unsafe-any-ref-send")]
   pub fn new<T: ToOwned + DynEq + 'static>(d: T) -> Self {
     EdnAnyRef(Arc::new(RwLock::new(d)))
   }
@@ -0,0 +1,90 @@
+use std::{
+  collections::{hash_map::Entry, HashMap},
+  sync::Arc,
+};
+
+use crate::edn::ERROR_PREVIEW_LEN;
+use crate::Edn;
+
+// Atom
+
+/// Atom, a reference to a value. atoms with the same label share identity, so they hold equal values,
+/// see `Edn::check_atom_labels`. written in `atom 'name value`
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EdnAtomView {
+  pub label: Option<Arc<str>>,
+  pub value: Box<Edn>,
+}
+
+impl EdnAtomView {
+  pub fn new(value: Edn) -> Self {
+    EdnAtomView {
+      label: None,
+      value: Box::new(value),
+    }
+  }
+
+  pub fn labeled<T: Into<Arc<str>>>(label: T, value: Edn) -> Self {
+    EdnAtomView {
+      label: Some(label.into()),
+      value: Box::new(value),
+    }
+  }
+}
+
+impl TryFrom<Edn> for EdnAtomView {
+  type Error = String;
+
+  fn try_from(data: Edn) -> Result<Self, Self::Error> {
+    match data {
+      Edn::Atom(a) => Ok(a),
+      a => Err(format!("data is not atom: {}", a.preview(ERROR_PREVIEW_LEN))),
+    }
+  }
+}
+
+impl From<EdnAtomView> for Edn {
+  fn from(x: EdnAtomView) -> Edn {
+    Edn::Atom(x)
+  }
+}
+
+/// values of labeled atoms seen so far, for checking atoms sharing a label one by one in a walk
+#[derive(Default)]
+pub(crate) struct AtomLabels<'a>(HashMap<&'a str, &'a Edn>);
+
+impl<'a> AtomLabels<'a> {
+  /// check an atom against earlier atoms with the same label
+  pub(crate) fn check(&mut self, atom: &'a EdnAtomView) -> Result<(), String> {
+    let Some(label) = &atom.label else {
+      return Ok(());
+    };
+    match self.0.entry(label) {
+      Entry::Occupied(e) if *e.get() != &*atom.value => Err(format!(
+        "atoms labeled '{} hold different values: {} and {}",
+        label,
+        e.get().preview(ERROR_PREVIEW_LEN),
+        atom.value.preview(ERROR_PREVIEW_LEN)
+      )),
+      Entry::Occupied(_) => Ok(()),
+      Entry::Vacant(e) => {
+        e.insert(&atom.value);
+        Ok(())
+      }
+    }
+  }
+}
+
+impl Edn {
+  /// check that atoms with the same label hold equal values, since they refer to the same value.
+  /// `parse` and `format` reject data failing this check
+  pub fn check_atom_labels(&self) -> Result<(), String> {
+    let mut labels = AtomLabels::default();
+    let mut failure = None;
+    self.any_node(|x| match x {
+      Edn::Atom(a) => labels.check(a).map_err(|e| failure = Some(e)).is_err(),
+      _ => false,
+    });
+    failure.map_or(Ok(()), Err)
+  }
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use super::rebuild::rebuild_with;
+use crate::edn::ERROR_PREVIEW_LEN;
+use crate::{Edn, EdnAtomView, EdnRecordView, EdnTupleView};
+
+// Buffer migration
+
+impl Edn {
+  /// convert a list of integers in `0..=255` into a buffer, for data written before `buf` existed
+  pub fn list_to_buffer(data: &Edn) -> Result<Edn, String> {
+    match data {
+      Edn::List(xs) => {
+        let mut buf = Vec::with_capacity(xs.len());
+        for (idx, x) in xs.iter().enumerate() {
+          match x {
+            Edn::Number(n) if n.fract() == 0.0 && (0.0..=255.0).contains(n) => buf.push(*n as u8),
+            a => {
+              return Err(format!(
+                "failed to convert to buffer, item {} is not a byte: {}",
+                idx,
+                a.preview(ERROR_PREVIEW_LEN)
+              ))
+            }
+          }
+        }
+        Ok(Edn::Buffer(buf))
+      }
+      a => Err(format!(
+        "failed to convert {} to buffer: {}",
+        a.type_name(),
+        a.preview(ERROR_PREVIEW_LEN)
+      )),
+    }
+  }
+
+  /// convert lists of bytes into buffers, only for values in maps and records whose keys match `is_buffer_key`,
+  /// so genuine lists of numbers elsewhere are kept. record fields are passed as tags.
+  /// returns the count of converted lists
+  pub fn normalize_buffers(&mut self, is_buffer_key: impl Fn(&Edn) -> bool) -> usize {
+    let mut count = 0;
+    let mut stack: Vec<&mut Edn> = vec![self];
+    while let Some(x) = stack.pop() {
+      // converted buffers are pushed as well, they have no children
+      count += normalize_node(x, &is_buffer_key);
+      match x {
+        Edn::Tuple(EdnTupleView { tag, extra }) => {
+          stack.push(Arc::make_mut(tag));
+          stack.extend(extra.iter_mut());
+        }
+        Edn::List(xs) => stack.extend(xs.0.iter_mut()),
+        // set items can't be changed in place, so sets are rebuilt
+        Edn::Set(xs) => {
+          for item in std::mem::take(&mut xs.0) {
+            xs.0
+              .insert(rebuild_with(item, &mut |y| count += normalize_node(y, &is_buffer_key)));
+          }
+        }
+        Edn::Map(xs) => stack.extend(xs.0.values_mut()),
+        Edn::Record(EdnRecordView { pairs, .. }) => stack.extend(pairs.iter_mut().map(|(_, v)| v)),
+        Edn::Atom(EdnAtomView { value, .. }) => stack.push(value),
+        _ => {}
+      }
+    }
+    count
+  }
+}
+
+/// convert values of one map or record under matching keys, without visiting children
+fn normalize_node(x: &mut Edn, is_buffer_key: &dyn Fn(&Edn) -> bool) -> usize {
+  let mut count = 0;
+  let mut convert = |v: &mut Edn| {
+    if let (Edn::List(_), Ok(buf)) = (&*v, Edn::list_to_buffer(v)) {
+      *v = buf;
+      count += 1;
+    }
+  };
+  match x {
+    Edn::Map(xs) => {
+      for (k, v) in xs.0.iter_mut() {
+        if is_buffer_key(k) {
+          convert(v);
+        }
+      }
+    }
+    Edn::Record(EdnRecordView { pairs, .. }) => {
+      for (k, v) in pairs.iter_mut() {
+        if is_buffer_key(&Edn::Tag(k.to_owned())) {
+          convert(v);
+        }
+      }
+    }
+    _ => {}
+  }
+  count
+}
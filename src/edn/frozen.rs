@@ -0,0 +1,68 @@
+use std::{fmt, ops::Deref, sync::Arc};
+
+use crate::Edn;
+
+// Frozen
+
+/// deeply immutable Edn for sharing across threads, created by `Edn::freeze`.
+/// cloning only increases a reference count, read it with methods of `Edn` through `Deref`
+#[derive(Clone, PartialEq, Eq)]
+pub struct FrozenEdn(Arc<Edn>);
+
+fn check_frozen(data: &Edn) -> Result<(), String> {
+  if data.has_any_ref() {
+    return Err(String::from("failed to freeze, any-ref is not allowed"));
+  }
+  Ok(())
+}
+
+impl FrozenEdn {
+  /// get the inner value
+  pub fn as_edn(&self) -> &Edn {
+    &self.0
+  }
+
+  /// a mutable deep copy
+  pub fn thaw(&self) -> Edn {
+    (*self.0).to_owned()
+  }
+
+  /// check if two frozen values share the same data
+  pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+    Arc::ptr_eq(&a.0, &b.0)
+  }
+}
+
+impl Deref for FrozenEdn {
+  type Target = Edn;
+  fn deref(&self) -> &Edn {
+    &self.0
+  }
+}
+
+impl fmt::Debug for FrozenEdn {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("FrozenEdn").field(&self.0).finish()
+  }
+}
+
+impl fmt::Display for FrozenEdn {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.0.fmt(f)
+  }
+}
+
+impl TryFrom<Edn> for FrozenEdn {
+  type Error = String;
+  fn try_from(data: Edn) -> Result<Self, Self::Error> {
+    data.freeze()
+  }
+}
+
+impl Edn {
+  /// wrap in `FrozenEdn` for sharing across threads, fails when containing `AnyRef`
+  pub fn freeze(self) -> Result<FrozenEdn, String> {
+    check_frozen(&self)?;
+    Ok(FrozenEdn(Arc::new(self)))
+  }
+}
@@ -0,0 +1,112 @@
+//! Edn values used as map keys, rejecting values that may change while being hashed.
+
+use std::{
+  borrow::Borrow,
+  cmp::Ordering,
+  fmt,
+  hash::{Hash, Hasher},
+};
+
+use crate::edn::ERROR_PREVIEW_LEN;
+use crate::Edn;
+
+/// a wrapper of Edn for map keys, `AnyRef` and `Atom` are rejected at any depth
+/// since they are references to mutable data
+#[derive(fmt::Debug, Clone, PartialEq, Eq)]
+pub struct EdnKey(Edn);
+
+impl EdnKey {
+  pub fn new(x: Edn) -> Result<Self, String> {
+    let mut stack: Vec<&Edn> = vec![&x];
+    while let Some(y) = stack.pop() {
+      match y {
+        Edn::AnyRef(_) => {
+          return Err(format!(
+            "any-ref is not allowed in map key: {}",
+            x.preview(ERROR_PREVIEW_LEN)
+          ))
+        }
+        Edn::Atom(_) => {
+          return Err(format!(
+            "atom is not allowed in map key: {}",
+            x.preview(ERROR_PREVIEW_LEN)
+          ))
+        }
+        Edn::Tuple(t) => {
+          stack.push(&t.tag);
+          stack.extend(&t.extra);
+        }
+        Edn::List(xs) => stack.extend(&xs.0),
+        Edn::Set(xs) => stack.extend(xs),
+        Edn::Map(xs) => {
+          for (k, v) in xs {
+            stack.push(k);
+            stack.push(v);
+          }
+        }
+        Edn::Record(r) => stack.extend(r.pairs.iter().map(|(_, v)| v)),
+        _ => {}
+      }
+    }
+    Ok(EdnKey(x))
+  }
+
+  /// get the inner value
+  pub fn as_edn(&self) -> &Edn {
+    &self.0
+  }
+
+  pub fn into_inner(self) -> Edn {
+    self.0
+  }
+}
+
+impl fmt::Display for EdnKey {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.0.fmt(f)
+  }
+}
+
+impl Hash for EdnKey {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.0.hash(state)
+  }
+}
+
+impl Ord for EdnKey {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.0.cmp(&other.0)
+  }
+}
+
+impl PartialOrd for EdnKey {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+/// lookup with `&Edn` in maps with `EdnKey` keys
+impl Borrow<Edn> for EdnKey {
+  fn borrow(&self) -> &Edn {
+    &self.0
+  }
+}
+
+impl AsRef<Edn> for EdnKey {
+  fn as_ref(&self) -> &Edn {
+    &self.0
+  }
+}
+
+impl TryFrom<Edn> for EdnKey {
+  type Error = String;
+  fn try_from(x: Edn) -> Result<Self, Self::Error> {
+    EdnKey::new(x)
+  }
+}
+
+impl From<EdnKey> for Edn {
+  fn from(x: EdnKey) -> Edn {
+    x.0
+  }
+}
@@ -1,4 +1,7 @@
+use crate::edn::ERROR_PREVIEW_LEN;
 use crate::Edn;
+use std::collections::HashSet;
+use std::ops::Index;
 
 // List
 
@@ -35,7 +38,32 @@ impl TryFrom<Edn> for EdnListView {
   fn try_from(value: Edn) -> Result<Self, Self::Error> {
     match value {
       Edn::List(xs) => Ok(xs),
-      _ => Err(format!("expecting list, got: {}", value)),
+      Edn::Nil => Ok(EdnListView::default()),
+      _ => Err(format!("expecting list, got: {}", value.preview(ERROR_PREVIEW_LEN))),
+    }
+  }
+}
+
+impl FromIterator<Edn> for EdnListView {
+  fn from_iter<T: IntoIterator<Item = Edn>>(iter: T) -> Self {
+    EdnListView(iter.into_iter().collect())
+  }
+}
+
+impl Extend<Edn> for EdnListView {
+  fn extend<T: IntoIterator<Item = Edn>>(&mut self, iter: T) {
+    self.0.extend(iter)
+  }
+}
+
+/// panics when out of bounds, use `get` or `get_or_nil` to handle missing items
+impl Index<usize> for EdnListView {
+  type Output = Edn;
+
+  fn index(&self, idx: usize) -> &Self::Output {
+    match self.0.get(idx) {
+      Some(x) => x,
+      None => panic!("index {} out of bounds for list of length {}", idx, self.0.len()),
     }
   }
 }
@@ -75,10 +103,12 @@ impl EdnListView {
   }
 
   pub fn get_or_nil(&self, index: usize) -> Edn {
-    if index >= self.0.len() {
-      return Edn::Nil;
-    }
-    self.0[index].to_owned()
+    self.get_ref_or_nil(index).to_owned()
+  }
+
+  /// like `get_or_nil` but without cloning, the reference borrows from the view
+  pub fn get_ref_or_nil(&self, index: usize) -> &Edn {
+    self.0.get(index).unwrap_or(&Edn::Nil)
   }
 
   pub fn len(&self) -> usize {
@@ -93,7 +123,61 @@ impl EdnListView {
     self.0.push(x)
   }
 
-  pub fn iter(&self) -> EdnListViewIter {
+  /// append items of another list
+  pub fn concat(mut self, other: EdnListView) -> EdnListView {
+    self.0.extend(other.0);
+    self
+  }
+
+  /// the first item and the rest, `None` for an empty list
+  pub fn split_first(&self) -> Option<(&Edn, &[Edn])> {
+    self.0.split_first()
+  }
+
+  /// items after the first one, borrowed, empty for an empty list
+  pub fn rest(&self) -> &[Edn] {
+    self.0.get(1..).unwrap_or_default()
+  }
+
+  pub fn iter(&self) -> EdnListViewIter<'_> {
     EdnListViewIter { xs: &self.0, idx: 0 }
   }
+
+  pub fn contains(&self, x: &Edn) -> bool {
+    self.0.contains(x)
+  }
+
+  /// index of the first item equal to `x`
+  pub fn position(&self, x: &Edn) -> Option<usize> {
+    self.0.iter().position(|y| y == x)
+  }
+
+  /// remove later duplicates, the first occurrence of each item is kept in place.
+  /// numbers are compared exactly, while `0.0` and `-0.0` are equal
+  pub fn dedup(&mut self) {
+    let keep = self.first_occurrences();
+    let mut flags = keep.iter();
+    self.0.retain(|_| *flags.next().unwrap_or(&true));
+  }
+
+  /// new list with later duplicates removed, like `dedup`
+  pub fn unique(&self) -> EdnListView {
+    let keep = self.first_occurrences();
+    EdnListView(
+      self
+        .0
+        .iter()
+        .zip(keep)
+        .filter(|(_, k)| *k)
+        .map(|(x, _)| x.to_owned())
+        .collect(),
+    )
+  }
+
+  fn first_occurrences(&self) -> Vec<bool> {
+    // hashing does not touch interior mutability of any-ref
+    #[allow(clippy::mutable_key_type)]
+    let mut seen: HashSet<&Edn> = HashSet::with_capacity(self.0.len());
+    self.0.iter().map(|x| seen.insert(x)).collect()
+  }
 }
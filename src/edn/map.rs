@@ -1,70 +1,273 @@
 // Map
 
+#[cfg(not(feature = "btree-map"))]
+use std::collections::hash_map;
 use std::collections::HashMap;
+#[cfg(feature = "btree-map")]
+use std::collections::{btree_map, BTreeMap};
+use std::ops::Index;
+use std::sync::Arc;
 
+use crate::edn::ERROR_PREVIEW_LEN;
 use crate::{Edn, EdnTag};
 
-/// Map interface for Edn::Map
+/// Map interface for Edn::Map. prefer `iter` and other methods over `.0`,
+/// the inner collection depends on features and may change
+#[cfg(not(feature = "btree-map"))]
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct EdnMapView(pub HashMap<Edn, Edn>);
 
+/// Map interface for Edn::Map, entries are sorted by keys with `btree-map` feature
+#[cfg(feature = "btree-map")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EdnMapView(pub BTreeMap<Edn, Edn>);
+
 impl TryFrom<Edn> for EdnMapView {
   type Error = String;
 
   fn try_from(data: Edn) -> Result<Self, Self::Error> {
     match data {
       Edn::Map(xs) => Ok(xs),
-      Edn::Nil => Ok(EdnMapView(HashMap::new())),
-      a => Err(format!("data is not map: {}", a)),
+      Edn::Nil => Ok(EdnMapView::default()),
+      a => Err(format!("data is not map: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 }
 
+#[cfg(not(feature = "btree-map"))]
+impl From<HashMap<Edn, Edn>> for EdnMapView {
+  fn from(xs: HashMap<Edn, Edn>) -> EdnMapView {
+    EdnMapView(xs)
+  }
+}
+
+#[cfg(feature = "btree-map")]
 impl From<HashMap<Edn, Edn>> for EdnMapView {
   fn from(xs: HashMap<Edn, Edn>) -> EdnMapView {
+    EdnMapView(xs.into_iter().collect())
+  }
+}
+
+#[cfg(feature = "btree-map")]
+impl From<BTreeMap<Edn, Edn>> for EdnMapView {
+  fn from(xs: BTreeMap<Edn, Edn>) -> EdnMapView {
     EdnMapView(xs)
   }
 }
 
+#[cfg(not(feature = "btree-map"))]
 impl From<EdnMapView> for HashMap<Edn, Edn> {
   fn from(x: EdnMapView) -> HashMap<Edn, Edn> {
     x.0
   }
 }
 
+#[cfg(feature = "btree-map")]
+impl From<EdnMapView> for HashMap<Edn, Edn> {
+  fn from(x: EdnMapView) -> HashMap<Edn, Edn> {
+    x.0.into_iter().collect()
+  }
+}
+
+/// later values replace earlier ones of the same key
+impl FromIterator<(Edn, Edn)> for EdnMapView {
+  fn from_iter<T: IntoIterator<Item = (Edn, Edn)>>(iter: T) -> Self {
+    EdnMapView(iter.into_iter().collect())
+  }
+}
+
+impl Extend<(Edn, Edn)> for EdnMapView {
+  fn extend<T: IntoIterator<Item = (Edn, Edn)>>(&mut self, iter: T) {
+    self.0.extend(iter)
+  }
+}
+
 impl From<EdnMapView> for Edn {
   fn from(x: EdnMapView) -> Edn {
     Edn::Map(EdnMapView(x.0))
   }
 }
 
+/// get by string or tag key like `get_by_name`, for code that knows the key is there.
+///
+/// # Panics
+///
+/// panics on a missing key, with similar keys as hints. data read from documents may miss any key,
+/// use `get_by_name` or `get_or_nil`, or `try_get_by_name` for an error with the same hints
+impl Index<&str> for EdnMapView {
+  type Output = Edn;
+
+  fn index(&self, key: &str) -> &Self::Output {
+    self.try_get_by_name(key).unwrap_or_else(|e| panic!("{}", e))
+  }
+}
+
 impl EdnMapView {
-  /// get by tag
+  /// get by tag key only, `key` is the name of the tag as it is, so `":x"` finds the tag written as `::x`
+  pub fn get_tag_key(&self, key: &str) -> Option<&Edn> {
+    self.0.get(&Edn::Tag(EdnTag::new_raw(key)))
+  }
+  /// get by string key only
+  pub fn get_str_key(&self, key: &str) -> Option<&Edn> {
+    self.0.get(&Edn::str(key))
+  }
+  #[deprecated(since = "0.6.14", note = "use `get_tag_key`")]
   pub fn tag_get(&self, key: &str) -> Option<&Edn> {
-    self.0.get(&Edn::Tag(EdnTag::from(key)))
+    self.get_tag_key(key)
   }
-  /// get by str
+  #[deprecated(since = "0.6.14", note = "use `get_str_key`")]
   pub fn str_get(&self, key: &str) -> Option<&Edn> {
-    self.0.get(&Edn::str(key))
+    self.get_str_key(key)
+  }
+
+  /// get by string key, then by tag key, like `get_or_nil` and `contains_key`.
+  /// the string key wins when a map has both `|k` and `:k`
+  pub fn get_by_name(&self, key: &str) -> Option<&Edn> {
+    self.get_str_key(key).or_else(|| self.get_tag_key(key))
+  }
+
+  /// like `get_by_name`, but errors on a missing key with similar keys as hints
+  pub fn try_get_by_name(&self, key: &str) -> Result<&Edn, String> {
+    if let Some(v) = self.get_by_name(key) {
+      return Ok(v);
+    }
+    let hints = self.similar_keys(key);
+    if hints.is_empty() {
+      return Err(format!("no key `{}` in map of {} entries", key, self.len()));
+    }
+    let hints = hints.iter().map(|k| k.to_string()).collect::<Vec<_>>().join(" or ");
+    Err(format!(
+      "no key `{}` in map of {} entries, did you mean {}?",
+      key,
+      self.len(),
+      hints
+    ))
+  }
+
+  /// string and tag keys with names close to `key`, for hints on typos, like `:skill-level` for `skil-level`.
+  /// names match when one starts with the other, or when shared prefix and suffix cover 2/3 of the longer one.
+  /// at most 3, closest first
+  pub fn similar_keys(&self, key: &str) -> Vec<&Edn> {
+    let key_chars: Vec<char> = key.chars().collect();
+    let mut found: Vec<(usize, &Edn)> = self
+      .0
+      .keys()
+      .filter_map(|k| {
+        let name = match k {
+          Edn::Str(s) => &**s,
+          Edn::Tag(t) => t.ref_str(),
+          _ => return None,
+        };
+        if name == key {
+          return None;
+        }
+        let name_chars: Vec<char> = name.chars().collect();
+        let shorter = key_chars.len().min(name_chars.len());
+        let longer = key_chars.len().max(name_chars.len());
+        let prefix = key_chars.iter().zip(&name_chars).take_while(|(a, b)| a == b).count();
+        let suffix = key_chars
+          .iter()
+          .rev()
+          .zip(name_chars.iter().rev())
+          .take_while(|(a, b)| a == b)
+          .count();
+        let score = (prefix + suffix).min(shorter);
+        (prefix == shorter || score * 3 >= longer * 2).then_some((score, k))
+      })
+      .collect();
+    found.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    found.into_iter().take(3).map(|(_, k)| k).collect()
   }
 
-  /// get reference of element
+  /// get reference of element by exact key, use `get_by_name` for string or tag keys
   pub fn get(&self, key: &Edn) -> Option<&Edn> {
     self.0.get(key)
   }
 
   /// regardless of key in string or tag
   pub fn get_or_nil(&self, key: &str) -> Edn {
-    self
-      .0
-      .get(&Edn::str(key))
-      .cloned()
-      .or_else(|| self.0.get(&Edn::tag(key)).cloned())
-      .unwrap_or(Edn::Nil)
+    self.get_ref_or_nil(key).to_owned()
+  }
+
+  /// like `get_or_nil` but without cloning, the reference borrows from the view
+  pub fn get_ref_or_nil(&self, key: &str) -> &Edn {
+    self.get_by(key).unwrap_or(&Edn::Nil)
+  }
+
+  /// values of several names in one pass over the map, missing keys are nil.
+  /// names are resolved like `get_by_name`, and a name requested twice gets the value twice
+  pub fn take_keys<const N: usize>(&self, keys: [&str; N]) -> [Edn; N] {
+    let mut found: [Option<(&Edn, bool)>; N] = [None; N];
+    for (k, v) in &self.0 {
+      let Some((name, is_str)) = key_name(k) else {
+        continue;
+      };
+      for (slot, key) in found.iter_mut().zip(keys) {
+        if key == name && !matches!(slot, Some((_, true))) {
+          *slot = Some((v, is_str));
+        }
+      }
+    }
+    found.map(|x| x.map_or(Edn::Nil, |(v, _)| v.to_owned()))
+  }
+
+  /// like `take_keys`, but moves values out of the map, only names requested twice are cloned
+  pub fn into_take_keys<const N: usize>(self, keys: [&str; N]) -> [Edn; N] {
+    let mut found: [Option<(Edn, bool)>; N] = std::array::from_fn(|_| None);
+    for (k, v) in self.0 {
+      let Some((name, is_str)) = key_name(&k) else {
+        continue;
+      };
+      let mut last: Option<usize> = None;
+      for (idx, key) in keys.iter().enumerate() {
+        if *key == name && !matches!(found[idx], Some((_, true))) {
+          if let Some(prev) = last {
+            found[prev] = Some((v.to_owned(), is_str));
+          }
+          last = Some(idx);
+        }
+      }
+      if let Some(idx) = last {
+        found[idx] = Some((v, is_str));
+      }
+    }
+    found.map(|x| x.map_or(Edn::Nil, |(v, _)| v))
+  }
+
+  /// values of names in `spec` order, resolved like `get_by_name`, with defaults from `spec` for missing keys.
+  /// with `strict`, keys not in `spec` are errors, for reading maps into fixed structs
+  pub fn project(&self, spec: &[(&str, Edn)], strict: bool) -> Result<Vec<Edn>, String> {
+    if strict {
+      let mut unknown = self
+        .keys()
+        .filter(|k| !key_name(k).is_some_and(|(name, _)| spec.iter().any(|(s, _)| *s == name)))
+        .collect::<Vec<_>>();
+      if !unknown.is_empty() {
+        unknown.sort();
+        let keys = unknown.iter().map(|k| k.preview(ERROR_PREVIEW_LEN)).collect::<Vec<_>>();
+        return Err(format!("unexpected keys in map: {}", keys.join(" ")));
+      }
+    }
+    Ok(
+      spec
+        .iter()
+        .map(|(name, default)| self.get_by_name(name).unwrap_or(default).to_owned())
+        .collect(),
+    )
+  }
+
+  /// get by a name, a tag or an exact key, see `MapKeyLookup`
+  pub fn get_by<K: MapKeyLookup>(&self, key: K) -> Option<&Edn> {
+    key.lookup(self)
+  }
+
+  /// like `get_or_nil` but takes a name, a tag or an exact key
+  pub fn get_or_nil_by<K: MapKeyLookup>(&self, key: K) -> Edn {
+    self.get_by(key).cloned().unwrap_or(Edn::Nil)
   }
 
   pub fn contains_key(&self, key: &str) -> bool {
-    self.0.contains_key(&Edn::str(key)) || self.0.contains_key(&Edn::tag(key))
+    self.0.contains_key(&Edn::str(key)) || self.0.contains_key(&Edn::tag_raw(key))
   }
 
   pub fn insert(&mut self, k: Edn, v: Edn) {
@@ -72,8 +275,25 @@ impl EdnMapView {
   }
 
   /// takes k that impl Into<EdnTag>
-  pub fn insert_key(&mut self, k: impl Into<EdnTag>, v: Edn) {
-    self.0.insert(k.into().into(), v);
+  pub fn insert_key(&mut self, k: impl Into<EdnTag>, v: impl Into<Edn>) {
+    self.0.insert(k.into().into(), v.into());
+  }
+
+  /// insert with a string key, like `insert_key` for tags
+  pub fn insert_str_key(&mut self, k: impl Into<Arc<str>>, v: impl Into<Edn>) {
+    self.0.insert(Edn::Str(k.into()), v.into());
+  }
+
+  /// `insert_key` in builder style, `EdnMapView::default().with_key("a", 1).with_str_key("b", "x")`
+  pub fn with_key(mut self, k: impl Into<EdnTag>, v: impl Into<Edn>) -> Self {
+    self.insert_key(k, v);
+    self
+  }
+
+  /// `insert_str_key` in builder style
+  pub fn with_str_key(mut self, k: impl Into<Arc<str>>, v: impl Into<Edn>) -> Self {
+    self.insert_str_key(k, v);
+    self
   }
 
   pub fn len(&self) -> usize {
@@ -83,4 +303,121 @@ impl EdnMapView {
   pub fn is_empty(&self) -> bool {
     self.0.is_empty()
   }
+
+  /// iterate over entries, sorted by keys with `btree-map` feature, otherwise in arbitrary order
+  pub fn iter(&self) -> EdnMapViewIter<'_> {
+    EdnMapViewIter { xs: self.0.iter() }
+  }
+
+  pub fn keys(&self) -> impl ExactSizeIterator<Item = &Edn> {
+    self.iter().map(|(k, _)| k)
+  }
+
+  pub fn values(&self) -> impl ExactSizeIterator<Item = &Edn> {
+    self.iter().map(|(_, v)| v)
+  }
+
+  /// look up every key again, reporting entries that are not found by their own keys,
+  /// like `EdnSetView::debug_validate`
+  pub fn debug_validate(&self) -> Result<(), String> {
+    let lost = self
+      .0
+      .keys()
+      .filter(|k| !self.0.get_key_value(k).is_some_and(|(k2, _)| std::ptr::eq(*k, k2)))
+      .map(|k| k.preview(ERROR_PREVIEW_LEN))
+      .collect::<Vec<_>>();
+    if lost.is_empty() {
+      Ok(())
+    } else {
+      Err(format!("map keys no longer found by lookup: {}", lost.join(" ")))
+    }
+  }
+}
+
+/// name of a string or tag key, and whether it's a string
+fn key_name(k: &Edn) -> Option<(&str, bool)> {
+  match k {
+    Edn::Str(s) => Some((s, true)),
+    Edn::Tag(t) => Some((t.ref_str(), false)),
+    _ => None,
+  }
+}
+
+/// keys for `EdnMapView::get_by`, also used by `Edn::map_get` and `EdnFields`.
+/// a `&str` is looked up as a string key then as a tag key,
+/// while `EdnTag` and `Edn` keys take exactly one lookup without allocating strings
+pub trait MapKeyLookup {
+  fn lookup(self, map: &EdnMapView) -> Option<&Edn>;
+}
+
+impl MapKeyLookup for &str {
+  fn lookup(self, map: &EdnMapView) -> Option<&Edn> {
+    map.get_by_name(self)
+  }
+}
+
+impl MapKeyLookup for &EdnTag {
+  fn lookup(self, map: &EdnMapView) -> Option<&Edn> {
+    map.0.get(&Edn::Tag(self.to_owned()))
+  }
+}
+
+impl MapKeyLookup for &Edn {
+  fn lookup(self, map: &EdnMapView) -> Option<&Edn> {
+    map.0.get(self)
+  }
+}
+
+pub struct EdnMapViewIter<'a> {
+  #[cfg(not(feature = "btree-map"))]
+  xs: hash_map::Iter<'a, Edn, Edn>,
+  #[cfg(feature = "btree-map")]
+  xs: btree_map::Iter<'a, Edn, Edn>,
+}
+
+impl<'a> Iterator for EdnMapViewIter<'a> {
+  type Item = (&'a Edn, &'a Edn);
+  fn next(&mut self) -> Option<Self::Item> {
+    self.xs.next()
+  }
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.xs.size_hint()
+  }
+}
+
+impl ExactSizeIterator for EdnMapViewIter<'_> {}
+
+impl<'a> IntoIterator for &'a EdnMapView {
+  type Item = (&'a Edn, &'a Edn);
+  type IntoIter = EdnMapViewIter<'a>;
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+pub struct EdnMapViewIntoIter {
+  #[cfg(not(feature = "btree-map"))]
+  xs: hash_map::IntoIter<Edn, Edn>,
+  #[cfg(feature = "btree-map")]
+  xs: btree_map::IntoIter<Edn, Edn>,
+}
+
+impl Iterator for EdnMapViewIntoIter {
+  type Item = (Edn, Edn);
+  fn next(&mut self) -> Option<Self::Item> {
+    self.xs.next()
+  }
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.xs.size_hint()
+  }
+}
+
+impl ExactSizeIterator for EdnMapViewIntoIter {}
+
+impl IntoIterator for EdnMapView {
+  type Item = (Edn, Edn);
+  type IntoIter = EdnMapViewIntoIter;
+  fn into_iter(self) -> Self::IntoIter {
+    EdnMapViewIntoIter { xs: self.0.into_iter() }
+  }
 }
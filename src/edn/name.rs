@@ -0,0 +1,47 @@
+//! names stored as strings, tags or symbols, read without losing which one it was.
+
+use crate::{Edn, EdnTag};
+
+use super::ERROR_PREVIEW_LEN;
+
+/// kind of value a name is read from, see `Edn::read_name`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NameKind {
+  Str,
+  Tag,
+  Symbol,
+}
+
+impl NameKind {
+  /// build a value of this kind, reverse of `Edn::read_name`
+  pub fn to_edn(self, name: &str) -> Edn {
+    match self {
+      NameKind::Str => Edn::str(name),
+      NameKind::Tag => Edn::Tag(EdnTag::new_raw(name)),
+      NameKind::Symbol => Edn::sym(name),
+    }
+  }
+}
+
+impl Edn {
+  /// borrow text of a string, a tag or a symbol along with its kind,
+  /// so that names can be written back as the same kind of value
+  ///
+  /// ```
+  /// use cirru_edn::{Edn, NameKind};
+  ///
+  /// let key = Edn::tag("a");
+  /// let (kind, name) = key.read_name()?;
+  /// assert_eq!((kind, name), (NameKind::Tag, "a"));
+  /// assert_eq!(kind.to_edn(&name.to_uppercase()), Edn::tag("A"));
+  /// # Ok::<(), String>(())
+  /// ```
+  pub fn read_name(&self) -> Result<(NameKind, &str), String> {
+    match self {
+      Edn::Str(s) => Ok((NameKind::Str, s)),
+      Edn::Tag(t) => Ok((NameKind::Tag, t.ref_str())),
+      Edn::Symbol(s) => Ok((NameKind::Symbol, s)),
+      a => Err(format!("failed to read name from: {}", a.preview(ERROR_PREVIEW_LEN))),
+    }
+  }
+}
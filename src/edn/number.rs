@@ -0,0 +1,90 @@
+use crate::Edn;
+
+// Number
+
+mod sealed {
+  pub trait Sealed {}
+}
+
+/// numeric types a number in Edn can be read as, with `Edn::read_number_as`.
+/// integers need whole numbers in range, `f32` needs numbers not overflowing it, rounding is allowed
+pub trait TryFromF64: sealed::Sealed + Sized {
+  /// name of the type in errors
+  const NAME: &'static str;
+  /// `None` when `n` is not representable
+  fn try_from_f64(n: f64) -> Option<Self>;
+}
+
+macro_rules! impl_signed {
+  ($($t:ident),*) => {
+    $(
+      impl sealed::Sealed for $t {}
+      impl TryFromF64 for $t {
+        const NAME: &'static str = stringify!($t);
+        fn try_from_f64(n: f64) -> Option<Self> {
+          // `-MIN` is a power of 2, exact in f64 unlike `MAX`
+          (n.fract() == 0.0 && n >= $t::MIN as f64 && n < -($t::MIN as f64)).then_some(n as $t)
+        }
+      }
+    )*
+  };
+}
+
+macro_rules! impl_unsigned {
+  ($($t:ident),*) => {
+    $(
+      impl sealed::Sealed for $t {}
+      impl TryFromF64 for $t {
+        const NAME: &'static str = stringify!($t);
+        fn try_from_f64(n: f64) -> Option<Self> {
+          // `MAX + 1` is a power of 2, exact in f64 unlike `MAX`
+          (n.fract() == 0.0 && n >= 0.0 && n < $t::MAX as f64 + 1.0).then_some(n as $t)
+        }
+      }
+    )*
+  };
+}
+
+impl_signed!(i8, i16, i32, i64, i128, isize);
+impl_unsigned!(u8, u16, u32, u64, u128, usize);
+
+impl sealed::Sealed for f64 {}
+impl TryFromF64 for f64 {
+  const NAME: &'static str = "f64";
+  fn try_from_f64(n: f64) -> Option<Self> {
+    Some(n)
+  }
+}
+
+impl sealed::Sealed for f32 {}
+impl TryFromF64 for f32 {
+  const NAME: &'static str = "f32";
+  fn try_from_f64(n: f64) -> Option<Self> {
+    let x = n as f32;
+    (x.is_finite() || !n.is_finite()).then_some(x)
+  }
+}
+
+impl Edn {
+  /// read a number as `T`, errors like `expected u16-compatible number, got 70000`
+  /// when the number is out of range or not whole for integers
+  pub fn read_number_as<T: TryFromF64>(&self) -> Result<T, String> {
+    let n = self.read_number()?;
+    T::try_from_f64(n).ok_or_else(|| format!("expected {}-compatible number, got {}", T::NAME, n))
+  }
+}
+
+macro_rules! impl_try_from_edn {
+  ($($t:ident),*) => {
+    $(
+      impl TryFrom<Edn> for $t {
+        type Error = String;
+        fn try_from(x: Edn) -> Result<Self, Self::Error> {
+          x.read_number_as()
+        }
+      }
+    )*
+  };
+}
+
+impl_try_from_edn!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
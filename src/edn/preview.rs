@@ -0,0 +1,176 @@
+use std::fmt::{self, Write};
+use std::iter;
+
+use crate::{Edn, EdnAtomView, EdnListView, EdnRecordView, EdnTag, EdnTupleView};
+
+/// length of values previewed in error messages
+pub(crate) const ERROR_PREVIEW_LEN: usize = 80;
+
+/// a piece of output inside an open bracket, separated by spaces
+enum PreviewPiece<'a> {
+  Value(&'a Edn),
+  Text(&'static str),
+  Label(&'a str),
+  Field(&'a EdnTag),
+  Pair(&'a Edn, &'a Edn),
+  Entry(&'a EdnTag, &'a Edn),
+}
+
+/// an open bracket with pieces left to write
+struct PreviewFrame<'a> {
+  pieces: Box<dyn Iterator<Item = PreviewPiece<'a>> + 'a>,
+  started: bool,
+}
+
+/// counts chars and refuses to write beyond `cap`
+struct PreviewBuf {
+  buf: String,
+  len: usize,
+  cap: usize,
+}
+
+impl Write for PreviewBuf {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    for c in s.chars() {
+      if self.len >= self.cap {
+        return Err(fmt::Error);
+      }
+      self.buf.push(c);
+      self.len += 1;
+    }
+    Ok(())
+  }
+}
+
+impl Edn {
+  /// compact one-line form like `Display`, bounded to `max_len` chars.
+  /// values that don't fit are replaced with `…` and open brackets are still closed.
+  /// work is proportional to `max_len` rather than to the size of data
+  pub fn preview(&self, max_len: usize) -> String {
+    let mut out = PreviewBuf {
+      buf: String::new(),
+      len: 0,
+      cap: 0,
+    };
+    let mut stack: Vec<PreviewFrame> = vec![];
+    let mut next = Some(PreviewPiece::Value(self));
+    loop {
+      let mut separated = false;
+      let piece = match next.take() {
+        Some(piece) => piece,
+        None => match stack.last_mut() {
+          None => break,
+          Some(frame) => match frame.pieces.next() {
+            Some(piece) => {
+              separated = frame.started;
+              frame.started = true;
+              piece
+            }
+            None => {
+              // reserved when the bracket was opened
+              out.buf.push(')');
+              out.len += 1;
+              stack.pop();
+              continue;
+            }
+          },
+        },
+      };
+
+      // room for closing brackets and a trailing ` …`
+      out.cap = max_len.saturating_sub(stack.len() + 2);
+      let mark = (out.buf.len(), out.len);
+      let written = if separated { out.write_char(' ') } else { Ok(()) }.and_then(|_| match piece {
+        PreviewPiece::Value(x) => match preview_pieces(x, max_len) {
+          Some(pieces) => open_frame(&mut out, &mut stack, pieces),
+          None => write!(out, "{}", x),
+        },
+        PreviewPiece::Text(s) => out.write_str(s),
+        PreviewPiece::Label(s) => write!(out, "'{}", s),
+        PreviewPiece::Field(t) => write!(out, ":{}", t),
+        PreviewPiece::Pair(k, v) => open_frame(
+          &mut out,
+          &mut stack,
+          Box::new([PreviewPiece::Value(k), PreviewPiece::Value(v)].into_iter()),
+        ),
+        PreviewPiece::Entry(k, v) => open_frame(
+          &mut out,
+          &mut stack,
+          Box::new([PreviewPiece::Field(k), PreviewPiece::Value(v)].into_iter()),
+        ),
+      });
+
+      if written.is_err() {
+        out.buf.truncate(mark.0);
+        out.buf.push_str(if separated { " …" } else { "…" });
+        for _ in 0..stack.len() {
+          out.buf.push(')');
+        }
+        break;
+      }
+    }
+    out.buf
+  }
+}
+
+fn open_frame<'a>(
+  out: &mut PreviewBuf,
+  stack: &mut Vec<PreviewFrame<'a>>,
+  pieces: Box<dyn Iterator<Item = PreviewPiece<'a>> + 'a>,
+) -> fmt::Result {
+  // keep one more char for the closing bracket
+  out.cap = out.cap.saturating_sub(1);
+  out.write_char('(')?;
+  stack.push(PreviewFrame { pieces, started: false });
+  Ok(())
+}
+
+/// pieces inside brackets of collections, `None` for values written as a whole.
+/// sets and maps are sorted like `Display` when they could fit in `max_len` and items or keys are short leaves,
+/// so sorting takes bounded work. others are written in arbitrary order, they are cut off anyway
+fn preview_pieces(x: &Edn, max_len: usize) -> Option<Box<dyn Iterator<Item = PreviewPiece<'_>> + '_>> {
+  use PreviewPiece::*;
+  match x {
+    Edn::Tuple(EdnTupleView { tag, extra }) => Some(Box::new(
+      [Text("::"), Value(tag)].into_iter().chain(extra.iter().map(Value)),
+    )),
+    Edn::List(EdnListView(xs)) => Some(Box::new(iter::once(Text("[]")).chain(xs.iter().map(Value)))),
+    Edn::Set(xs) if xs.len() <= max_len && xs.0.iter().all(|y| is_short_leaf(y, max_len)) => {
+      let mut items = xs.0.iter().collect::<Vec<_>>();
+      items.sort();
+      Some(Box::new(iter::once(Text("#{}")).chain(items.into_iter().map(Value))))
+    }
+    Edn::Set(xs) => Some(Box::new(iter::once(Text("#{}")).chain(xs.iter().map(Value)))),
+    Edn::Map(xs) if xs.len() <= max_len && xs.0.keys().all(|k| is_short_leaf(k, max_len)) => {
+      let mut entries = xs.0.iter().collect::<Vec<_>>();
+      entries.sort_by_key(|(k, _)| *k);
+      Some(Box::new(
+        iter::once(Text("{}")).chain(entries.into_iter().map(|(k, v)| Pair(k, v))),
+      ))
+    }
+    Edn::Map(xs) => Some(Box::new(
+      iter::once(Text("{}")).chain(xs.iter().map(|(k, v)| Pair(k, v))),
+    )),
+    Edn::Record(EdnRecordView { tag, pairs }) => Some(Box::new(
+      [Text("%{}"), Field(tag)]
+        .into_iter()
+        .chain(pairs.iter().map(|(k, v)| Entry(k, v))),
+    )),
+    Edn::Atom(EdnAtomView { label, value }) => Some(Box::new(
+      iter::once(Text("atom"))
+        .chain(label.as_deref().map(Label))
+        .chain(iter::once(Value(value))),
+    )),
+    _ => None,
+  }
+}
+
+/// values compared without recursion, with texts no longer than `max_len`
+fn is_short_leaf(x: &Edn, max_len: usize) -> bool {
+  match x {
+    Edn::Nil | Edn::Bool(_) | Edn::Number(_) => true,
+    Edn::Str(s) | Edn::Symbol(s) => s.len() <= max_len,
+    Edn::Tag(t) => t.ref_str().len() <= max_len,
+    _ => false,
+  }
+}
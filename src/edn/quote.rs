@@ -0,0 +1,120 @@
+use std::{collections::HashSet, sync::Arc};
+
+use cirru_parser::Cirru;
+
+use super::rebuild::try_rebuild_with;
+use super::ERROR_PREVIEW_LEN;
+use crate::{Edn, EdnAtomView, EdnRecordView, EdnTupleView};
+
+// Quote
+
+impl Edn {
+  /// visit quoted code at any depth, including quotes in set items and map keys.
+  /// fails when changed set items or map keys would become equal to others,
+  /// quotes visited before are changed already, while that set or map is kept as it was
+  pub fn map_quotes(&mut self, mut f: impl FnMut(&mut Cirru)) -> Result<(), String> {
+    map_quotes_in(self, &mut f).map_err(|e| format!("failed to map quotes, {}", e))
+  }
+
+  /// replace leaves equal to `from` with `to` in all quoted code, returns the count of replaced leaves.
+  /// fails like `map_quotes`
+  pub fn replace_quote_leaf(&mut self, from: &str, to: &str) -> Result<usize, String> {
+    let to: Arc<str> = Arc::from(to);
+    let mut count = 0;
+    self.map_quotes(|code| count += replace_leaf(code, from, &to))?;
+    Ok(count)
+  }
+}
+
+fn map_quotes_in(data: &mut Edn, f: &mut dyn FnMut(&mut Cirru)) -> Result<(), String> {
+  let mut stack: Vec<&mut Edn> = vec![data];
+  while let Some(x) = stack.pop() {
+    let changed = changed_members(x, f)?;
+    match x {
+      Edn::Quote(code) => f(code),
+      Edn::Tuple(EdnTupleView { tag, extra }) => {
+        stack.push(Arc::make_mut(tag));
+        stack.extend(extra.iter_mut());
+      }
+      Edn::List(xs) => stack.extend(xs.0.iter_mut()),
+      // items and keys can not be mutated in place, so they are replaced with changed copies
+      Edn::Set(xs) => {
+        for (old, _) in &changed {
+          xs.0.remove(old);
+        }
+        xs.0.extend(changed.into_iter().map(|(_, new)| new));
+      }
+      Edn::Map(xs) => {
+        let values = changed.iter().map(|(old, _)| xs.0.remove(old)).collect::<Vec<_>>();
+        for ((_, new), v) in changed.into_iter().zip(values) {
+          xs.0.insert(new, v.unwrap_or_default());
+        }
+        stack.extend(xs.0.values_mut());
+      }
+      Edn::Record(EdnRecordView { pairs, .. }) => stack.extend(pairs.iter_mut().map(|(_, v)| v)),
+      Edn::Atom(EdnAtomView { value, .. }) => stack.push(value),
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+/// set items or map keys holding quotes, with their changed copies.
+/// fails when they would become equal to other items or keys
+fn changed_members(x: &Edn, f: &mut dyn FnMut(&mut Cirru)) -> Result<Vec<(Edn, Edn)>, String> {
+  let members: Box<dyn Iterator<Item = &Edn>> = match x {
+    Edn::Set(xs) => Box::new(xs.0.iter()),
+    Edn::Map(xs) => Box::new(xs.0.keys()),
+    _ => return Ok(vec![]),
+  };
+  let mut changed = vec![];
+  let mut kept = vec![];
+  for item in members {
+    if has_quote(item) {
+      let copy = try_rebuild_with(item.to_owned(), &mut |y| {
+        quote_node(y, f);
+        Ok(())
+      })?;
+      changed.push((item.to_owned(), copy));
+    } else {
+      kept.push(item);
+    }
+  }
+  // hashing does not touch interior mutability of any-ref
+  #[allow(clippy::mutable_key_type)]
+  let mut seen: HashSet<&Edn> = HashSet::new();
+  if let Some(dup) = kept
+    .into_iter()
+    .chain(changed.iter().map(|(_, y)| y))
+    .find(|y| !seen.insert(y))
+  {
+    return Err(format!(
+      "`{}` would appear twice in {}: {}",
+      dup.preview(ERROR_PREVIEW_LEN),
+      x.type_name(),
+      x.preview(ERROR_PREVIEW_LEN)
+    ));
+  }
+  Ok(changed)
+}
+
+fn has_quote(x: &Edn) -> bool {
+  x.any_node(|y| matches!(y, Edn::Quote(_)))
+}
+
+fn quote_node(x: &mut Edn, f: &mut dyn FnMut(&mut Cirru)) {
+  if let Edn::Quote(code) = x {
+    f(code)
+  }
+}
+
+fn replace_leaf(code: &mut Cirru, from: &str, to: &Arc<str>) -> usize {
+  match code {
+    Cirru::Leaf(s) if &**s == from => {
+      *s = to.to_owned();
+      1
+    }
+    Cirru::Leaf(_) => 0,
+    Cirru::List(xs) => xs.iter_mut().map(|x| replace_leaf(x, from, to)).sum(),
+  }
+}
@@ -0,0 +1,120 @@
+//! rebuilding values bottom-up with a work list, for items of sets and keys of maps,
+//! which can not be changed in place by passes walking `&mut Edn`
+
+use std::{iter, mem, sync::Arc};
+
+use super::ERROR_PREVIEW_LEN;
+use crate::{Edn, EdnAtomView, EdnRecordView, EdnTupleView};
+
+/// a step of rebuilding, children are visited before their parent is built
+enum RebuildFrame {
+  Visit(Edn),
+  /// parent emptied of its children, with count of children on the result stack
+  Build(Edn, usize),
+}
+
+/// children taken out of a collection, in order. map entries come as key and value
+fn take_children(x: &mut Edn) -> Vec<Edn> {
+  match x {
+    Edn::List(xs) => mem::take(&mut xs.0),
+    Edn::Set(xs) => mem::take(&mut xs.0).into_iter().collect(),
+    Edn::Map(xs) => mem::take(&mut xs.0).into_iter().flat_map(|(k, v)| [k, v]).collect(),
+    Edn::Tuple(EdnTupleView { tag, extra }) => {
+      let tag = Arc::unwrap_or_clone(mem::replace(tag, Arc::new(Edn::Nil)));
+      iter::once(tag).chain(mem::take(extra)).collect()
+    }
+    Edn::Record(EdnRecordView { pairs, .. }) => pairs.iter_mut().map(|(_, v)| mem::take(v)).collect(),
+    Edn::Atom(EdnAtomView { value, .. }) => vec![mem::take(&mut **value)],
+    _ => vec![],
+  }
+}
+
+/// put children back, `Err` with the item or key appearing twice in `strict` mode, otherwise they are merged
+fn put_children(x: &mut Edn, children: Vec<Edn>, strict: bool) -> Result<(), Edn> {
+  let mut ys = children.into_iter();
+  match x {
+    Edn::List(xs) => xs.0 = ys.collect(),
+    Edn::Set(xs) => {
+      for y in ys {
+        if let Some(dup) = xs.0.replace(y) {
+          if strict {
+            return Err(dup);
+          }
+        }
+      }
+    }
+    Edn::Map(xs) => {
+      while let (Some(k), Some(v)) = (ys.next(), ys.next()) {
+        if strict && xs.0.contains_key(&k) {
+          return Err(k);
+        }
+        xs.0.insert(k, v);
+      }
+    }
+    Edn::Tuple(EdnTupleView { tag, extra }) => {
+      *tag = Arc::new(ys.next().unwrap_or_default());
+      *extra = ys.collect();
+    }
+    Edn::Record(EdnRecordView { pairs, .. }) => {
+      for ((_, v), y) in pairs.iter_mut().zip(ys) {
+        *v = y;
+      }
+    }
+    Edn::Atom(EdnAtomView { value, .. }) => **value = ys.next().unwrap_or_default(),
+    _ => {}
+  }
+  Ok(())
+}
+
+fn rebuild(x: Edn, f: &mut dyn FnMut(&mut Edn) -> Result<(), String>, strict: bool) -> Result<Edn, String> {
+  let mut results: Vec<Edn> = vec![];
+  let mut stack = vec![RebuildFrame::Visit(x)];
+  while let Some(frame) = stack.pop() {
+    match frame {
+      RebuildFrame::Visit(mut x) => {
+        let children = take_children(&mut x);
+        if children.is_empty() {
+          f(&mut x)?;
+          results.push(x);
+          continue;
+        }
+        stack.push(RebuildFrame::Build(x, children.len()));
+        stack.extend(children.into_iter().rev().map(RebuildFrame::Visit));
+      }
+      RebuildFrame::Build(mut x, n) => {
+        let children = results.split_off(results.len() - n);
+        if let Err(dup) = put_children(&mut x, children, strict) {
+          return Err(format!(
+            "`{}` would appear twice in {}",
+            dup.preview(ERROR_PREVIEW_LEN),
+            x.type_name()
+          ));
+        }
+        f(&mut x)?;
+        results.push(x);
+      }
+    }
+  }
+  Ok(results.pop().unwrap_or_default())
+}
+
+/// call `f` on every node of `x` at any depth, children before their parents.
+/// sets and maps are rebuilt, so items and keys changed by `f` are hashed again, and merged when they become equal
+pub(crate) fn rebuild_with(x: Edn, f: &mut dyn FnMut(&mut Edn)) -> Edn {
+  match rebuild(
+    x,
+    &mut |y| {
+      f(y);
+      Ok(())
+    },
+    false,
+  ) {
+    Ok(y) => y,
+    Err(e) => unreachable!("merged items are not reported: {}", e),
+  }
+}
+
+/// like `rebuild_with`, stops at errors from `f`, and when items of a set or keys of a map become equal
+pub(crate) fn try_rebuild_with(x: Edn, f: &mut dyn FnMut(&mut Edn) -> Result<(), String>) -> Result<Edn, String> {
+  rebuild(x, f, true)
+}
@@ -1,6 +1,8 @@
 // Record
 
-/// Record interface for Edn::Record
+/// Record interface for Edn::Record.
+/// no `Default` or `FromIterator` since a record always has a tag,
+/// start from `EdnRecordView::new` or collect fields with `EdnRecordView::from_fields`
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EdnRecordView {
   pub tag: EdnTag,
@@ -31,7 +33,7 @@ impl TryFrom<Edn> for EdnRecordView {
         }
         Ok(EdnRecordView { tag: t, pairs: buf })
       }
-      a => Err(format!("data is not record: {}", a)),
+      a => Err(format!("data is not record: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 }
@@ -47,6 +49,7 @@ impl From<EdnRecordView> for Edn {
 
 use std::ops::Index;
 
+use crate::edn::ERROR_PREVIEW_LEN;
 use crate::{Edn, EdnTag};
 impl Index<&str> for EdnRecordView {
   type Output = Edn;
@@ -66,6 +69,31 @@ impl EdnRecordView {
     EdnRecordView { tag, pairs: vec![] }
   }
 
+  /// build record from fields in order, like from a filtered iterator of another record.
+  /// fails on an empty tag and on duplicated fields
+  pub fn from_fields(tag: EdnTag, fields: impl IntoIterator<Item = (EdnTag, Edn)>) -> Result<EdnRecordView, String> {
+    if tag.ref_str().is_empty() {
+      return Err(String::from("record needs a tag"));
+    }
+    let mut pairs: Vec<(EdnTag, Edn)> = vec![];
+    for (k, v) in fields {
+      if pairs.iter().any(|(k2, _)| *k2 == k) {
+        return Err(format!("duplicated field `{}` in record :{}", k, tag));
+      }
+      pairs.push((k, v));
+    }
+    Ok(EdnRecordView { tag, pairs })
+  }
+
+  /// build record with fields sorted by tags, fails on duplicated tags
+  pub fn from_sorted_pairs(tag: EdnTag, mut pairs: Vec<(EdnTag, Edn)>) -> Result<EdnRecordView, String> {
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    if let Some(w) = pairs.windows(2).find(|w| w[0].0 == w[1].0) {
+      return Err(format!("duplicated field `{}` in record :{}", w[0].0, tag));
+    }
+    Ok(EdnRecordView { tag, pairs })
+  }
+
   pub fn has_key(&self, key: &str) -> bool {
     for pair in self.pairs.iter() {
       if key == &*pair.0.arc_str() {
@@ -79,4 +107,94 @@ impl EdnRecordView {
   pub fn insert(&mut self, k: impl Into<EdnTag>, v: Edn) {
     self.pairs.push((k.into(), v))
   }
+
+  pub fn len(&self) -> usize {
+    self.pairs.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.pairs.is_empty()
+  }
+
+  /// iterate over fields in order
+  pub fn iter(&self) -> EdnRecordViewIter<'_> {
+    EdnRecordViewIter { xs: self.pairs.iter() }
+  }
+
+  pub fn iter_mut(&mut self) -> impl Iterator<Item = (&EdnTag, &mut Edn)> {
+    self.pairs.iter_mut().map(|(k, v)| (&*k, v))
+  }
+
+  pub fn keys(&self) -> impl Iterator<Item = &EdnTag> {
+    self.pairs.iter().map(|(k, _)| k)
+  }
+
+  pub fn values(&self) -> impl Iterator<Item = &Edn> {
+    self.pairs.iter().map(|(_, v)| v)
+  }
+
+  /// values of fields in `spec` order, like `EdnMapView::project`
+  pub fn project(&self, spec: &[(&str, Edn)], strict: bool) -> Result<Vec<Edn>, String> {
+    if strict {
+      let unknown = self
+        .keys()
+        .filter(|k| !spec.iter().any(|(name, _)| k.ref_str() == *name))
+        .map(|k| format!(":{}", k))
+        .collect::<Vec<_>>();
+      if !unknown.is_empty() {
+        return Err(format!(
+          "unexpected fields in record :{}: {}",
+          self.tag,
+          unknown.join(" ")
+        ));
+      }
+    }
+    Ok(
+      spec
+        .iter()
+        .map(|(name, default)| {
+          self
+            .pairs
+            .iter()
+            .find(|(k, _)| k.ref_str() == *name)
+            .map_or(default, |(_, v)| v)
+            .to_owned()
+        })
+        .collect(),
+    )
+  }
+}
+
+pub struct EdnRecordViewIter<'a> {
+  xs: std::slice::Iter<'a, (EdnTag, Edn)>,
+}
+
+impl<'a> Iterator for EdnRecordViewIter<'a> {
+  type Item = (&'a EdnTag, &'a Edn);
+  fn next(&mut self) -> Option<Self::Item> {
+    self.xs.next().map(|(k, v)| (k, v))
+  }
+}
+
+impl<'a> IntoIterator for &'a EdnRecordView {
+  type Item = (&'a EdnTag, &'a Edn);
+  type IntoIter = EdnRecordViewIter<'a>;
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl IntoIterator for EdnRecordView {
+  type Item = (EdnTag, Edn);
+  type IntoIter = std::vec::IntoIter<(EdnTag, Edn)>;
+  fn into_iter(self) -> Self::IntoIter {
+    self.pairs.into_iter()
+  }
+}
+
+/// fields are appended as they are
+impl Extend<(EdnTag, Edn)> for EdnRecordView {
+  fn extend<T: IntoIterator<Item = (EdnTag, Edn)>>(&mut self, iter: T) {
+    self.pairs.extend(iter)
+  }
 }
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use crate::{Edn, EdnAtomView, EdnRecordView, EdnTupleView};
+
+// Redact
+
+impl Edn {
+  /// copy of data with values replaced at map keys and record fields named in `keys`,
+  /// string and tag keys are matched by name, case-sensitive
+  pub fn redact(&self, keys: &[&str], replacement: Edn) -> Edn {
+    let mut data = self.to_owned();
+    data.redact_in_place(keys, &replacement);
+    data
+  }
+
+  /// like `redact` but modifies data in place, returns the count of replaced values.
+  /// replaced values are not visited, lists, sets, tuples and atoms are searched too
+  pub fn redact_in_place(&mut self, keys: &[&str], replacement: &Edn) -> usize {
+    let mut count = 0;
+    let mut stack: Vec<&mut Edn> = vec![self];
+    while let Some(x) = stack.pop() {
+      match x {
+        Edn::Tuple(EdnTupleView { tag, extra }) => {
+          stack.push(Arc::make_mut(tag));
+          stack.extend(extra.iter_mut());
+        }
+        Edn::List(xs) => stack.extend(xs.0.iter_mut()),
+        // items can not be mutated in place, so sets are rebuilt
+        Edn::Set(xs) => {
+          for mut item in std::mem::take(&mut xs.0) {
+            count += item.redact_in_place(keys, replacement);
+            xs.0.insert(item);
+          }
+        }
+        Edn::Map(xs) => {
+          for (k, v) in xs.0.iter_mut() {
+            let name = match k {
+              Edn::Str(s) => Some(&**s),
+              Edn::Tag(t) => Some(t.ref_str()),
+              _ => None,
+            };
+            if name.is_some_and(|name| keys.contains(&name)) {
+              *v = replacement.to_owned();
+              count += 1;
+            } else {
+              stack.push(v);
+            }
+          }
+        }
+        Edn::Record(EdnRecordView { pairs, .. }) => {
+          for (k, v) in pairs.iter_mut() {
+            if keys.contains(&k.ref_str()) {
+              *v = replacement.to_owned();
+              count += 1;
+            } else {
+              stack.push(v);
+            }
+          }
+        }
+        Edn::Atom(EdnAtomView { value, .. }) => stack.push(value),
+        _ => {}
+      }
+    }
+    count
+  }
+}
@@ -0,0 +1,42 @@
+use std::{mem, sync::Arc, thread};
+
+use crate::{Edn, EdnTupleView};
+
+// Release
+
+impl Edn {
+  /// drop with a work list instead of recursion, for trees too deep for the stack,
+  /// like a list nested 200k times. children are moved out before each node is dropped.
+  /// quoted code is dropped as usual
+  pub fn drop_iteratively(self) {
+    let mut stack = vec![self];
+    while let Some(mut x) = stack.pop() {
+      match &mut x {
+        Edn::List(xs) => stack.append(&mut xs.0),
+        Edn::Set(xs) => stack.extend(mem::take(&mut xs.0)),
+        Edn::Map(xs) => {
+          for (k, v) in mem::take(&mut xs.0) {
+            stack.push(k);
+            stack.push(v);
+          }
+        }
+        Edn::Record(record) => stack.extend(record.pairs.drain(..).map(|(_, v)| v)),
+        Edn::Tuple(EdnTupleView { tag, extra }) => {
+          stack.append(extra);
+          // a shared tag is only released by its last owner
+          if let Some(tag) = Arc::get_mut(tag) {
+            stack.push(mem::take(tag));
+          }
+        }
+        Edn::Atom(atom) => stack.push(mem::take(&mut *atom.value)),
+        _ => {}
+      }
+    }
+  }
+
+  /// move the value to a new thread and drop it there with `drop_iteratively`,
+  /// so a large document doesn't hold the current thread. join the handle to wait for it
+  pub fn drop_in_background(self) -> thread::JoinHandle<()> {
+    thread::spawn(move || self.drop_iteratively())
+  }
+}
@@ -0,0 +1,208 @@
+use std::{
+  borrow::Cow,
+  collections::{HashMap, HashSet},
+  sync::Arc,
+};
+
+use super::rebuild::{rebuild_with, try_rebuild_with};
+use crate::edn::ERROR_PREVIEW_LEN;
+use crate::{Edn, EdnAtomView, EdnRecordView, EdnTag, EdnTupleView};
+
+// Rename
+
+/// the key after renaming, tags stay tags and strings stay strings
+fn renamed_key(k: &Edn, renames: &HashMap<&str, &str>) -> Option<Edn> {
+  match k {
+    Edn::Str(s) => renames.get(&**s).map(|name| Edn::str(*name)),
+    Edn::Tag(t) => renames.get(t.ref_str()).map(|name| Edn::tag(*name)),
+    _ => None,
+  }
+}
+
+/// whether keys of a map or fields of a record in `x` would be renamed, at any depth
+fn has_renames(x: &Edn, renames: &HashMap<&str, &str>) -> bool {
+  x.any_node(|y| match y {
+    Edn::Map(xs) => xs.0.keys().any(|k| renamed_key(k, renames).is_some()),
+    Edn::Record(EdnRecordView { pairs, .. }) => pairs.iter().any(|(k, _)| renames.contains_key(k.ref_str())),
+    _ => false,
+  })
+}
+
+/// rename keys of a map or fields of a record, without visiting children
+fn rename_node(x: &mut Edn, renames: &HashMap<&str, &str>) -> usize {
+  let mut count = 0;
+  match x {
+    Edn::Map(xs) if xs.0.keys().any(|k| renamed_key(k, renames).is_some()) => {
+      for (k, v) in std::mem::take(&mut xs.0) {
+        match renamed_key(&k, renames) {
+          Some(key) => {
+            count += 1;
+            xs.0.insert(key, v);
+          }
+          None => {
+            xs.0.insert(k, v);
+          }
+        }
+      }
+    }
+    Edn::Record(EdnRecordView { pairs, .. }) => {
+      for (k, _) in pairs.iter_mut() {
+        if let Some(name) = renames.get(k.ref_str()) {
+          *k = EdnTag::new(*name);
+          count += 1;
+        }
+      }
+    }
+    _ => {}
+  }
+  count
+}
+
+/// a set item or a map key after renaming at any depth, with the count of renamed keys in it
+fn renamed_nested(x: Edn, renames: &HashMap<&str, &str>) -> (Edn, usize) {
+  let mut count = 0;
+  let y = rebuild_with(x, &mut |y| count += rename_node(y, renames));
+  (y, count)
+}
+
+/// check that items of a set, or keys of a map, stay distinct after renaming.
+/// a renamed copy is built for items and keys holding renamed keys, which checks collisions inside them as well
+fn check_members<'a>(
+  x: &Edn,
+  members: impl Iterator<Item = &'a Edn>,
+  is_key: bool,
+  renames: &HashMap<&str, &str>,
+) -> Result<(), String> {
+  // hashing does not touch interior mutability of any-ref
+  #[allow(clippy::mutable_key_type)]
+  let mut seen: HashSet<Cow<Edn>> = HashSet::new();
+  for item in members {
+    let renamed = match renamed_key(item, renames) {
+      Some(key) if is_key => Some(key),
+      _ if has_renames(item, renames) => Some(try_rebuild_with(item.to_owned(), &mut |y| {
+        check_node(y, renames)?;
+        rename_node(y, renames);
+        Ok(())
+      })?),
+      _ => None,
+    };
+    let item = renamed.map_or(Cow::Borrowed(item), Cow::Owned);
+    if let Some(item) = seen.replace(item) {
+      return Err(format!(
+        "`{}` would appear twice in {}: {}",
+        item.preview(ERROR_PREVIEW_LEN),
+        x.type_name(),
+        x.preview(ERROR_PREVIEW_LEN)
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// check keys of a map or fields of a record, keys holding other collections are not renamed here
+fn check_node(x: &Edn, renames: &HashMap<&str, &str>) -> Result<(), String> {
+  match x {
+    Edn::Map(xs) if xs.0.keys().any(|k| renamed_key(k, renames).is_some()) => check_members(
+      x,
+      xs.0.keys().filter(|k| matches!(k, Edn::Str(_) | Edn::Tag(_))),
+      true,
+      renames,
+    ),
+    Edn::Record(EdnRecordView { tag, pairs }) => {
+      let mut seen: HashSet<&str> = HashSet::with_capacity(pairs.len());
+      for (k, _) in pairs {
+        let name = renames.get(k.ref_str()).copied().unwrap_or(k.ref_str());
+        if !seen.insert(name.strip_prefix(':').unwrap_or(name)) {
+          return Err(format!("field `{}` would appear twice in record :{}", name, tag));
+        }
+      }
+      Ok(())
+    }
+    _ => Ok(()),
+  }
+}
+
+/// check all collisions before renaming, set items and map keys are checked on renamed copies
+fn check_renames(data: &Edn, renames: &HashMap<&str, &str>) -> Result<(), String> {
+  let mut stack = vec![data];
+  while let Some(x) = stack.pop() {
+    match x {
+      Edn::Tuple(EdnTupleView { tag, extra }) => {
+        stack.push(tag);
+        stack.extend(extra);
+      }
+      Edn::List(xs) => stack.extend(&xs.0),
+      Edn::Set(xs) => check_members(x, xs.0.iter(), false, renames)?,
+      Edn::Map(xs) => {
+        check_members(x, xs.0.keys(), true, renames)?;
+        stack.extend(xs.0.values());
+      }
+      Edn::Record(EdnRecordView { pairs, .. }) => {
+        check_node(x, renames)?;
+        stack.extend(pairs.iter().map(|(_, v)| v));
+      }
+      Edn::Atom(EdnAtomView { value, .. }) => stack.push(value),
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+impl Edn {
+  /// rename keys of maps and fields of records at any depth, `renames` maps old names to new names.
+  /// string and tag keys are matched by name and keep their kinds, so `|a` and `:a` may both be renamed.
+  /// nothing is changed when a renamed key would collide with another key, or a set item with another item,
+  /// returns the count of renamed keys. values are searched through lists, sets, tuples, atoms and map keys too
+  pub fn rename_keys(&mut self, renames: &HashMap<&str, &str>) -> Result<usize, String> {
+    check_renames(self, renames).map_err(|e| format!("failed to rename keys, {}", e))?;
+
+    let mut count = 0;
+    let mut stack: Vec<&mut Edn> = vec![self];
+    while let Some(x) = stack.pop() {
+      match x {
+        Edn::Tuple(EdnTupleView { tag, extra }) => {
+          stack.push(Arc::make_mut(tag));
+          stack.extend(extra.iter_mut());
+        }
+        Edn::List(xs) => stack.extend(xs.0.iter_mut()),
+        // items and keys can not be mutated in place, so sets and maps are rebuilt
+        Edn::Set(xs) if xs.0.iter().any(|item| has_renames(item, renames)) => {
+          for item in std::mem::take(&mut xs.0) {
+            let (item, n) = renamed_nested(item, renames);
+            count += n;
+            xs.0.insert(item);
+          }
+        }
+        Edn::Map(xs) => {
+          if xs
+            .0
+            .keys()
+            .any(|k| renamed_key(k, renames).is_some() || has_renames(k, renames))
+          {
+            for (k, v) in std::mem::take(&mut xs.0) {
+              let (k, n) = match renamed_key(&k, renames) {
+                Some(key) => (key, 1),
+                None => renamed_nested(k, renames),
+              };
+              count += n;
+              xs.0.insert(k, v);
+            }
+          }
+          stack.extend(xs.0.values_mut());
+        }
+        Edn::Record(EdnRecordView { pairs, .. }) => {
+          for (k, v) in pairs.iter_mut() {
+            if let Some(name) = renames.get(k.ref_str()) {
+              *k = EdnTag::new(*name);
+              count += 1;
+            }
+            stack.push(v);
+          }
+        }
+        Edn::Atom(EdnAtomView { value, .. }) => stack.push(value),
+        _ => {}
+      }
+    }
+    Ok(count)
+  }
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use crate::{Edn, EdnAtomView, EdnListView, EdnMapView, EdnRecordView, EdnSetView, EdnTupleView};
+
+// Sample
+
+/// a step of building a sample, children are visited before their parent is built
+enum SampleFrame<'a> {
+  Visit(&'a Edn),
+  /// parent with count of children on the result stack, and count of elided items
+  Build(&'a Edn, usize, usize),
+}
+
+/// children of a collection to keep, in order. map entries come as key and value
+fn sampled_children(x: &Edn, max_items: usize) -> (Vec<&Edn>, usize) {
+  match x {
+    Edn::List(xs) => (
+      xs.0.iter().take(max_items).collect(),
+      xs.len().saturating_sub(max_items),
+    ),
+    Edn::Set(xs) => (xs.iter().take(max_items).collect(), xs.len().saturating_sub(max_items)),
+    Edn::Map(xs) => (
+      xs.iter().take(max_items).flat_map(|(k, v)| [k, v]).collect(),
+      xs.len().saturating_sub(max_items),
+    ),
+    Edn::Tuple(EdnTupleView { tag, extra }) => (Some(&**tag).into_iter().chain(extra).collect(), 0),
+    Edn::Record(EdnRecordView { pairs, .. }) => (pairs.iter().map(|(_, v)| v).collect(), 0),
+    Edn::Atom(EdnAtomView { value, .. }) => (vec![&**value], 0),
+    _ => (vec![], 0),
+  }
+}
+
+/// marker of `elided` items, with an index appended while it's taken by a kept set item or map key,
+/// so that it is not merged into them
+fn elided_marker(elided: usize, taken: impl Fn(&Edn) -> bool) -> Option<Edn> {
+  if elided == 0 {
+    return None;
+  }
+  let mut marker = Edn::elided(elided);
+  let mut idx = 0;
+  while taken(&marker) {
+    idx += 1;
+    marker = Edn::tuple(
+      Edn::tag("elided"),
+      vec![Edn::Number(elided as f64), Edn::Number(idx as f64)],
+    );
+  }
+  Some(marker)
+}
+
+impl Edn {
+  /// marker of items left out by `sample`, written as `:: :elided 995`
+  pub fn elided(count: usize) -> Edn {
+    Edn::tuple(Edn::tag("elided"), vec![Edn::Number(count as f64)])
+  }
+
+  /// a copy with only the first `max_items` items of each list, set and map, at any depth,
+  /// for printing the shape of huge data. a marker from `Edn::elided` is added after kept items,
+  /// as a set item, or as a map key with nil. set items and map keys that become equal once sampled are merged,
+  /// and counted in the marker too. when a kept item or key equals the marker, an index is appended
+  /// to the marker, like `:: :elided 2 1`. records, tuples and scalars are kept whole.
+  /// built with a work list, so deep data does not overflow the stack
+  pub fn sample(&self, max_items: usize) -> Edn {
+    let mut results: Vec<Edn> = vec![];
+    let mut stack = vec![SampleFrame::Visit(self)];
+    while let Some(frame) = stack.pop() {
+      match frame {
+        SampleFrame::Visit(x) => {
+          let (children, elided) = sampled_children(x, max_items);
+          if children.is_empty() && elided == 0 {
+            results.push(match x {
+              // empty collections, without allocating their capacity
+              Edn::List(_) => Edn::List(EdnListView::default()),
+              Edn::Set(_) => Edn::Set(EdnSetView::default()),
+              Edn::Map(_) => Edn::Map(EdnMapView::default()),
+              _ => x.to_owned(),
+            });
+            continue;
+          }
+          stack.push(SampleFrame::Build(x, children.len(), elided));
+          stack.extend(children.into_iter().rev().map(SampleFrame::Visit));
+        }
+        SampleFrame::Build(x, n, elided) => {
+          let mut children = results.split_off(results.len() - n);
+          results.push(match x {
+            Edn::List(_) => {
+              children.extend(elided_marker(elided, |_| false));
+              Edn::List(EdnListView(children))
+            }
+            // sampled items may become equal, merged ones are counted as elided
+            Edn::Set(_) => {
+              let mut set = EdnSetView::default();
+              set.0.extend(children);
+              let merged = n - set.len();
+              let marker = elided_marker(elided + merged, |x| set.0.contains(x));
+              set.0.extend(marker);
+              Edn::Set(set)
+            }
+            Edn::Map(_) => {
+              let mut ys = children.into_iter();
+              let mut m = EdnMapView::default();
+              while let (Some(k), Some(v)) = (ys.next(), ys.next()) {
+                m.0.insert(k, v);
+              }
+              let merged = n / 2 - m.len();
+              if let Some(marker) = elided_marker(elided + merged, |x| m.0.contains_key(x)) {
+                m.0.insert(marker, Edn::Nil);
+              }
+              Edn::Map(m)
+            }
+            Edn::Tuple(_) => {
+              let extra = children.split_off(1);
+              Edn::Tuple(EdnTupleView {
+                tag: Arc::new(children.remove(0)),
+                extra,
+              })
+            }
+            Edn::Record(EdnRecordView { tag, pairs }) => Edn::Record(EdnRecordView {
+              tag: tag.to_owned(),
+              pairs: pairs.iter().map(|(k, _)| k.to_owned()).zip(children).collect(),
+            }),
+            Edn::Atom(EdnAtomView { label, .. }) => Edn::Atom(EdnAtomView {
+              label: label.to_owned(),
+              value: Box::new(children.remove(0)),
+            }),
+            a => unreachable!("unexpected parent in sample: {}", a.type_name()),
+          });
+        }
+      }
+    }
+    results.pop().unwrap_or_default()
+  }
+}
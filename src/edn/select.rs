@@ -0,0 +1,223 @@
+//! path queries like `items[2].meta.:version`
+
+use crate::edn::ERROR_PREVIEW_LEN;
+use crate::{Edn, EdnRecordView, EdnTupleView};
+
+/// a step in a query path
+#[derive(Debug, Clone, PartialEq)]
+enum PathSeg {
+  /// `:name`, tag key in maps, or field in records
+  Tag(String),
+  /// `name` or `"name"`, string key in maps, or field in records
+  Str(String),
+  /// `[2]`, index in lists, or in tuples where 0 is the tag
+  Index(usize),
+  /// `#:a`, `#|a`, `#1`, membership in sets
+  Member(Edn),
+}
+
+fn is_delimiter(c: char) -> bool {
+  matches!(c, '.' | '[' | '#')
+}
+
+struct QueryParser<'a> {
+  query: &'a str,
+  chars: Vec<char>,
+  pos: usize,
+}
+
+impl QueryParser<'_> {
+  fn err(&self, msg: &str) -> String {
+    format!("invalid query {:?} at {}: {}", self.query, self.pos, msg)
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.pos).copied()
+  }
+
+  fn read_bare(&mut self) -> String {
+    let start = self.pos;
+    while let Some(c) = self.peek() {
+      if is_delimiter(c) || c == ']' || c == '"' {
+        break;
+      }
+      self.pos += 1;
+    }
+    self.chars[start..self.pos].iter().collect()
+  }
+
+  fn read_quoted(&mut self) -> Result<String, String> {
+    // skips opening quote
+    self.pos += 1;
+    let mut buf = String::new();
+    loop {
+      match self.peek() {
+        None => return Err(self.err("unclosed quote")),
+        Some('"') => {
+          self.pos += 1;
+          return Ok(buf);
+        }
+        Some('\\') => {
+          self.pos += 1;
+          match self.peek() {
+            Some(c) => buf.push(c),
+            None => return Err(self.err("unclosed quote")),
+          }
+          self.pos += 1;
+        }
+        Some(c) => {
+          buf.push(c);
+          self.pos += 1;
+        }
+      }
+    }
+  }
+
+  /// key in `:tag`, `"string"` or bare form, bare keys are returned with `false`
+  fn read_key(&mut self) -> Result<(PathSeg, bool), String> {
+    match self.peek() {
+      Some('"') => Ok((PathSeg::Str(self.read_quoted()?), false)),
+      Some(':') => {
+        self.pos += 1;
+        let name = self.read_bare();
+        if name.is_empty() {
+          Err(self.err("empty tag"))
+        } else {
+          Ok((PathSeg::Tag(name), false))
+        }
+      }
+      _ => {
+        let name = self.read_bare();
+        if name.is_empty() {
+          Err(self.err("expected key"))
+        } else {
+          Ok((PathSeg::Str(name), true))
+        }
+      }
+    }
+  }
+
+  fn read_index(&mut self) -> Result<PathSeg, String> {
+    // skips `[`
+    self.pos += 1;
+    let start = self.pos;
+    while let Some(c) = self.peek() {
+      if !c.is_ascii_digit() {
+        break;
+      }
+      self.pos += 1;
+    }
+    let digits: String = self.chars[start..self.pos].iter().collect();
+    if digits.is_empty() {
+      return Err(self.err("expected index"));
+    }
+    if self.peek() != Some(']') {
+      return Err(self.err("expected `]`"));
+    }
+    self.pos += 1;
+    digits.parse().map(PathSeg::Index).map_err(|e| self.err(&e.to_string()))
+  }
+
+  fn parse(mut self) -> Result<Vec<PathSeg>, String> {
+    let mut segs = vec![];
+    if self.chars.is_empty() {
+      return Ok(segs);
+    }
+    if !matches!(self.peek(), Some('[' | '#')) {
+      segs.push(self.read_key()?.0);
+    }
+    while let Some(c) = self.peek() {
+      match c {
+        '.' => {
+          self.pos += 1;
+          segs.push(self.read_key()?.0);
+        }
+        '[' => segs.push(self.read_index()?),
+        '#' => {
+          self.pos += 1;
+          let member = match self.read_key()? {
+            (PathSeg::Tag(t), _) => Edn::tag_raw(t),
+            (PathSeg::Str(s), true) => match s.parse::<f64>() {
+              Ok(n) => Edn::Number(n),
+              Err(_) => Edn::str(s),
+            },
+            (PathSeg::Str(s), false) => Edn::str(s),
+            (seg, _) => unreachable!("unexpected key: {:?}", seg),
+          };
+          segs.push(PathSeg::Member(member));
+        }
+        _ => return Err(self.err(&format!("unexpected {:?}", c))),
+      }
+    }
+    Ok(segs)
+  }
+}
+
+fn parse_query(query: &str) -> Result<Vec<PathSeg>, String> {
+  QueryParser {
+    query,
+    chars: query.chars().collect(),
+    pos: 0,
+  }
+  .parse()
+}
+
+fn select_seg<'a>(data: &'a Edn, seg: &PathSeg) -> Result<Option<&'a Edn>, String> {
+  match (data, seg) {
+    (Edn::Nil, _) => Ok(None),
+    (Edn::Map(xs), PathSeg::Tag(k)) => Ok(xs.0.get(&Edn::tag_raw(k.as_str()))),
+    (Edn::Map(xs), PathSeg::Str(k)) => Ok(xs.0.get(&Edn::str(k.as_str()))),
+    (Edn::Record(EdnRecordView { pairs, .. }), PathSeg::Tag(k) | PathSeg::Str(k)) => {
+      Ok(pairs.iter().find(|(name, _)| name.ref_str() == k).map(|(_, v)| v))
+    }
+    (Edn::List(xs), PathSeg::Index(i)) => Ok(xs.get(*i)),
+    (Edn::Tuple(EdnTupleView { tag, extra }), PathSeg::Index(i)) => {
+      Ok(if *i == 0 { Some(&**tag) } else { extra.get(i - 1) })
+    }
+    (Edn::Set(xs), PathSeg::Member(x)) => Ok(xs.0.get(x)),
+    (a, PathSeg::Tag(k)) => Err(format!(
+      "cannot select `:{}` from {}: {}",
+      k,
+      a.type_name(),
+      a.preview(ERROR_PREVIEW_LEN)
+    )),
+    (a, PathSeg::Str(k)) => Err(format!(
+      "cannot select `{}` from {}: {}",
+      k,
+      a.type_name(),
+      a.preview(ERROR_PREVIEW_LEN)
+    )),
+    (a, PathSeg::Index(i)) => Err(format!(
+      "cannot select `[{}]` from {}: {}",
+      i,
+      a.type_name(),
+      a.preview(ERROR_PREVIEW_LEN)
+    )),
+    (a, PathSeg::Member(x)) => Err(format!(
+      "cannot select `#{}` from {}: {}",
+      x.preview(ERROR_PREVIEW_LEN),
+      a.type_name(),
+      a.preview(ERROR_PREVIEW_LEN)
+    )),
+  }
+}
+
+impl Edn {
+  /// query nested data with a path, for example `items[2].meta.:version`:
+  ///
+  /// - `:name` for tag keys, `name` or `"name"` for string keys, both forms work for record fields,
+  /// - `[2]` for index in lists, or in tuples where 0 is the tag,
+  /// - `#:a`, `#"a"`, `#a` or `#1` to check membership in sets, returns the item in set.
+  ///
+  /// returns `None` when missing or reaching nil, errors on malformed queries or mismatched types.
+  pub fn select(&self, query: &str) -> Result<Option<&Edn>, String> {
+    let mut current = self;
+    for seg in parse_query(query)? {
+      match select_seg(current, &seg)? {
+        Some(x) => current = x,
+        None => return Ok(None),
+      }
+    }
+    Ok(Some(current))
+  }
+}
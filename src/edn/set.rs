@@ -1,30 +1,69 @@
-use crate::edn::Edn;
+use crate::edn::{Edn, ERROR_PREVIEW_LEN};
 
+#[cfg(not(feature = "btree-map"))]
+use std::collections::hash_set;
+#[cfg(feature = "btree-map")]
+use std::collections::{btree_set, BTreeSet};
 use std::{collections::HashSet, fmt};
 
 // Set
 
+/// Set interface for Edn::Set. prefer `iter` and other methods over `.0`,
+/// the inner collection depends on features and may change
+#[cfg(not(feature = "btree-map"))]
 #[derive(fmt::Debug, Clone, Default, PartialEq, Eq)]
 pub struct EdnSetView(pub HashSet<Edn>);
 
+/// items are sorted with `btree-map` feature
+#[cfg(feature = "btree-map")]
+#[derive(fmt::Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EdnSetView(pub BTreeSet<Edn>);
+
 impl TryFrom<Edn> for EdnSetView {
   type Error = String;
 
   fn try_from(data: Edn) -> Result<Self, Self::Error> {
     match data {
       Edn::Set(xs) => Ok(xs),
-      Edn::Nil => Ok(EdnSetView(HashSet::new())),
-      a => Err(format!("data is not set: {}", a)),
+      Edn::Nil => Ok(EdnSetView::default()),
+      a => Err(format!("data is not set: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 }
 
+#[cfg(not(feature = "btree-map"))]
 impl From<HashSet<Edn>> for EdnSetView {
   fn from(xs: HashSet<Edn>) -> EdnSetView {
     EdnSetView(xs)
   }
 }
 
+#[cfg(feature = "btree-map")]
+impl From<HashSet<Edn>> for EdnSetView {
+  fn from(xs: HashSet<Edn>) -> EdnSetView {
+    EdnSetView(xs.into_iter().collect())
+  }
+}
+
+#[cfg(feature = "btree-map")]
+impl From<BTreeSet<Edn>> for EdnSetView {
+  fn from(xs: BTreeSet<Edn>) -> EdnSetView {
+    EdnSetView(xs)
+  }
+}
+
+impl FromIterator<Edn> for EdnSetView {
+  fn from_iter<T: IntoIterator<Item = Edn>>(iter: T) -> Self {
+    EdnSetView(iter.into_iter().collect())
+  }
+}
+
+impl Extend<Edn> for EdnSetView {
+  fn extend<T: IntoIterator<Item = Edn>>(&mut self, iter: T) {
+    self.0.extend(iter)
+  }
+}
+
 impl From<EdnSetView> for Edn {
   fn from(x: EdnSetView) -> Edn {
     Edn::Set(EdnSetView(x.0))
@@ -47,4 +86,79 @@ impl EdnSetView {
   pub fn is_empty(&self) -> bool {
     self.0.is_empty()
   }
+
+  /// iterate over items, sorted with `btree-map` feature, otherwise in arbitrary order
+  pub fn iter(&self) -> EdnSetViewIter<'_> {
+    EdnSetViewIter { xs: self.0.iter() }
+  }
+
+  /// look up every item again, reporting items that are not found as themselves.
+  /// only data changed behind references, like inside any-refs, breaks a set this way
+  pub fn debug_validate(&self) -> Result<(), String> {
+    let lost = self
+      .0
+      .iter()
+      .filter(|x| !self.0.get(x).is_some_and(|y| std::ptr::eq(*x, y)))
+      .map(|x| x.preview(ERROR_PREVIEW_LEN))
+      .collect::<Vec<_>>();
+    if lost.is_empty() {
+      Ok(())
+    } else {
+      Err(format!("set items no longer found by lookup: {}", lost.join(" ")))
+    }
+  }
+}
+
+pub struct EdnSetViewIter<'a> {
+  #[cfg(not(feature = "btree-map"))]
+  xs: hash_set::Iter<'a, Edn>,
+  #[cfg(feature = "btree-map")]
+  xs: btree_set::Iter<'a, Edn>,
+}
+
+impl<'a> Iterator for EdnSetViewIter<'a> {
+  type Item = &'a Edn;
+  fn next(&mut self) -> Option<Self::Item> {
+    self.xs.next()
+  }
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.xs.size_hint()
+  }
+}
+
+impl ExactSizeIterator for EdnSetViewIter<'_> {}
+
+impl<'a> IntoIterator for &'a EdnSetView {
+  type Item = &'a Edn;
+  type IntoIter = EdnSetViewIter<'a>;
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+pub struct EdnSetViewIntoIter {
+  #[cfg(not(feature = "btree-map"))]
+  xs: hash_set::IntoIter<Edn>,
+  #[cfg(feature = "btree-map")]
+  xs: btree_set::IntoIter<Edn>,
+}
+
+impl Iterator for EdnSetViewIntoIter {
+  type Item = Edn;
+  fn next(&mut self) -> Option<Self::Item> {
+    self.xs.next()
+  }
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.xs.size_hint()
+  }
+}
+
+impl ExactSizeIterator for EdnSetViewIntoIter {}
+
+impl IntoIterator for EdnSetView {
+  type Item = Edn;
+  type IntoIter = EdnSetViewIntoIter;
+  fn into_iter(self) -> Self::IntoIter {
+    EdnSetViewIntoIter { xs: self.0.into_iter() }
+  }
 }
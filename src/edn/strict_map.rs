@@ -0,0 +1,91 @@
+// Strict Map
+
+use std::collections::HashMap;
+
+use crate::edn::ERROR_PREVIEW_LEN;
+use crate::{Edn, EdnKey, EdnMapView};
+
+/// Map with keys checked by `EdnKey`, so keys can not be mutated after insertion
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EdnStrictMapView(pub HashMap<EdnKey, Edn>);
+
+impl TryFrom<EdnMapView> for EdnStrictMapView {
+  type Error = String;
+
+  fn try_from(xs: EdnMapView) -> Result<Self, Self::Error> {
+    // keys are checked by `EdnKey::new`, clippy could not tell
+    #[allow(clippy::mutable_key_type)]
+    let mut ys = HashMap::with_capacity(xs.len());
+    for (k, v) in xs.0 {
+      ys.insert(EdnKey::new(k)?, v);
+    }
+    Ok(EdnStrictMapView(ys))
+  }
+}
+
+impl TryFrom<Edn> for EdnStrictMapView {
+  type Error = String;
+
+  fn try_from(data: Edn) -> Result<Self, Self::Error> {
+    match data {
+      Edn::Map(xs) => xs.try_into(),
+      Edn::Nil => Ok(EdnStrictMapView::default()),
+      a => Err(format!("data is not map: {}", a.preview(ERROR_PREVIEW_LEN))),
+    }
+  }
+}
+
+impl From<EdnStrictMapView> for EdnMapView {
+  fn from(xs: EdnStrictMapView) -> EdnMapView {
+    EdnMapView(xs.0.into_iter().map(|(k, v)| (k.into_inner(), v)).collect())
+  }
+}
+
+impl From<EdnStrictMapView> for Edn {
+  fn from(xs: EdnStrictMapView) -> Edn {
+    Edn::Map(xs.into())
+  }
+}
+
+impl EdnStrictMapView {
+  /// get reference of element
+  pub fn get(&self, key: &Edn) -> Option<&Edn> {
+    self.0.get(key)
+  }
+
+  /// regardless of key in string or tag
+  pub fn get_or_nil(&self, key: &str) -> Edn {
+    self.get_ref_or_nil(key).to_owned()
+  }
+
+  /// like `get_or_nil` but without cloning, the reference borrows from the view
+  pub fn get_ref_or_nil(&self, key: &str) -> &Edn {
+    self
+      .0
+      .get(&Edn::str(key))
+      .or_else(|| self.0.get(&Edn::tag_raw(key)))
+      .unwrap_or(&Edn::Nil)
+  }
+
+  pub fn contains_key(&self, key: &Edn) -> bool {
+    self.0.contains_key(key)
+  }
+
+  pub fn insert(&mut self, k: EdnKey, v: Edn) {
+    self.0.insert(k, v);
+  }
+
+  /// insert with key checked
+  pub fn try_insert(&mut self, k: Edn, v: Edn) -> Result<(), String> {
+    self.0.insert(EdnKey::new(k)?, v);
+    Ok(())
+  }
+
+  pub fn len(&self) -> usize {
+    self.0.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+}
@@ -0,0 +1,147 @@
+//! statistics and deduplication of strings in data, for debugging memory usage
+
+use std::{
+  collections::{HashMap, HashSet},
+  sync::Arc,
+};
+
+use super::rebuild::rebuild_with;
+use crate::{Edn, EdnAtomView, EdnRecordView, EdnTag, EdnTupleView};
+
+/// count of most frequent strings in `EdnStringStats::top`
+const STATS_TOP_COUNT: usize = 10;
+
+/// statistics of strings, tags and symbols in data, including record names and fields
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EdnStringStats {
+  pub strings: usize,
+  pub tags: usize,
+  pub symbols: usize,
+  /// unique contents across strings, tags and symbols
+  pub unique: usize,
+  pub total_bytes: usize,
+  /// bytes when each unique content is stored only once
+  pub unique_bytes: usize,
+  /// most frequent contents with counts, in descending order
+  pub top: Vec<(Arc<str>, usize)>,
+}
+
+impl Edn {
+  /// collect statistics of strings, tags and symbols
+  pub fn string_stats(&self) -> EdnStringStats {
+    let mut stats = EdnStringStats::default();
+    let mut counts: HashMap<&Arc<str>, usize> = HashMap::new();
+    let mut stack: Vec<&Edn> = vec![self];
+    while let Some(x) = stack.pop() {
+      match x {
+        Edn::Str(s) => {
+          stats.strings += 1;
+          *counts.entry(s).or_default() += 1;
+        }
+        Edn::Tag(t) => {
+          stats.tags += 1;
+          *counts.entry(&t.0).or_default() += 1;
+        }
+        Edn::Symbol(s) => {
+          stats.symbols += 1;
+          *counts.entry(s).or_default() += 1;
+        }
+        Edn::Tuple(EdnTupleView { tag, extra }) => {
+          stack.push(tag);
+          stack.extend(extra);
+        }
+        Edn::List(xs) => stack.extend(&xs.0),
+        Edn::Set(xs) => stack.extend(xs),
+        Edn::Map(xs) => {
+          for (k, v) in xs {
+            stack.push(k);
+            stack.push(v);
+          }
+        }
+        Edn::Record(EdnRecordView { tag, pairs }) => {
+          stats.tags += 1 + pairs.len();
+          *counts.entry(&tag.0).or_default() += 1;
+          for (k, v) in pairs {
+            *counts.entry(&k.0).or_default() += 1;
+            stack.push(v);
+          }
+        }
+        Edn::Atom(a) => stack.push(&a.value),
+        _ => {}
+      }
+    }
+
+    // counts are merged by contents since `Arc<str>` is hashed by contents
+    stats.unique = counts.len();
+    stats.total_bytes = counts.iter().map(|(s, n)| s.len() * n).sum();
+    stats.unique_bytes = counts.keys().map(|s| s.len()).sum();
+    let mut top = counts.into_iter().map(|(s, n)| (s.to_owned(), n)).collect::<Vec<_>>();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    top.truncate(STATS_TOP_COUNT);
+    stats.top = top;
+    stats
+  }
+
+  /// make strings, tags and symbols with equal contents share one allocation
+  pub fn dedup_strings(&mut self) {
+    let mut table: HashSet<Arc<str>> = HashSet::new();
+    dedup_in(self, &mut table);
+  }
+}
+
+fn dedup_arc(s: &mut Arc<str>, table: &mut HashSet<Arc<str>>) {
+  match table.get(&**s) {
+    Some(shared) => *s = shared.to_owned(),
+    None => {
+      table.insert(s.to_owned());
+    }
+  }
+}
+
+fn dedup_tag(t: &mut EdnTag, table: &mut HashSet<Arc<str>>) {
+  dedup_arc(&mut t.0, table)
+}
+
+/// share strings of one node, without visiting children
+fn dedup_node(x: &mut Edn, table: &mut HashSet<Arc<str>>) {
+  match x {
+    Edn::Str(s) | Edn::Symbol(s) => dedup_arc(s, table),
+    Edn::Tag(t) => dedup_tag(t, table),
+    Edn::Record(EdnRecordView { tag, pairs }) => {
+      dedup_tag(tag, table);
+      for (k, _) in pairs.iter_mut() {
+        dedup_tag(k, table);
+      }
+    }
+    _ => {}
+  }
+}
+
+fn dedup_in(data: &mut Edn, table: &mut HashSet<Arc<str>>) {
+  let mut stack: Vec<&mut Edn> = vec![data];
+  while let Some(x) = stack.pop() {
+    dedup_node(x, table);
+    match x {
+      Edn::Tuple(EdnTupleView { tag, extra }) => {
+        stack.push(Arc::make_mut(tag));
+        stack.extend(extra.iter_mut());
+      }
+      Edn::List(xs) => stack.extend(xs.0.iter_mut()),
+      // keys can not be mutated in place, so sets and maps are rebuilt
+      Edn::Set(xs) => {
+        for item in std::mem::take(&mut xs.0) {
+          xs.0.insert(rebuild_with(item, &mut |y| dedup_node(y, table)));
+        }
+      }
+      Edn::Map(xs) => {
+        for (k, v) in std::mem::take(&mut xs.0) {
+          xs.0.insert(rebuild_with(k, &mut |y| dedup_node(y, table)), v);
+        }
+        stack.extend(xs.0.values_mut());
+      }
+      Edn::Record(EdnRecordView { pairs, .. }) => stack.extend(pairs.iter_mut().map(|(_, v)| v)),
+      Edn::Atom(EdnAtomView { value, .. }) => stack.push(value),
+      _ => {}
+    }
+  }
+}
@@ -1,7 +1,11 @@
 use std::sync::Arc;
 
+use crate::edn::ERROR_PREVIEW_LEN;
 use crate::Edn;
 
+/// tuple of a tag and extra values, equality, ordering and hashing all cover exactly these two fields,
+/// so tuples from `Edn::tuple` and from parsing `:: ...` are interchangeable, including as map keys.
+/// like records, there's no `Default` as a tuple needs its tag
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EdnTupleView {
   pub tag: Arc<Edn>,
@@ -26,7 +30,7 @@ impl TryFrom<Edn> for EdnTupleView {
   fn try_from(data: Edn) -> Result<Self, Self::Error> {
     match data {
       Edn::Tuple(EdnTupleView { tag, extra }) => Ok(EdnTupleView { tag, extra }),
-      a => Err(format!("data is not tuple: {}", a)),
+      a => Err(format!("data is not tuple: {}", a.preview(ERROR_PREVIEW_LEN))),
     }
   }
 }
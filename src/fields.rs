@@ -0,0 +1,118 @@
+//! reading fields from maps and records while collecting all errors, instead of returning at the first one.
+
+use crate::edn::ERROR_PREVIEW_LEN;
+use crate::{Edn, EdnRecordView};
+
+/// reads fields from a map or a record, errors are collected with paths and returned in `finish()`
+///
+/// ```rust
+/// use cirru_edn::{Edn, EdnFields};
+///
+/// let data = cirru_edn::parse("{} (:name |demo) (:size |large)").unwrap();
+/// let mut fields = EdnFields::new(&data);
+/// let name: Option<String> = fields.field("name");
+/// let size: Option<f64> = fields.field("size");
+/// let verbose: Option<bool> = fields.optional("verbose");
+/// assert_eq!(name.as_deref(), Some("demo"));
+/// assert_eq!(size, None);
+/// assert_eq!(verbose, None);
+/// assert_eq!(fields.finish().unwrap_err().len(), 1);
+/// ```
+pub struct EdnFields<'a> {
+  data: &'a Edn,
+  prefix: String,
+  errors: Vec<(String, String)>,
+}
+
+impl<'a> EdnFields<'a> {
+  pub fn new(data: &'a Edn) -> Self {
+    Self::with_prefix(data, String::new())
+  }
+
+  fn with_prefix(data: &'a Edn, prefix: String) -> Self {
+    let mut errors = vec![];
+    if !matches!(data, Edn::Map(_) | Edn::Record(_)) {
+      errors.push((
+        prefix.to_owned(),
+        format!(
+          "expected map or record, got {}: {}",
+          data.type_name(),
+          data.preview(ERROR_PREVIEW_LEN)
+        ),
+      ));
+    }
+    EdnFields { data, prefix, errors }
+  }
+
+  fn path(&self, key: &str) -> String {
+    if self.prefix.is_empty() {
+      key.to_owned()
+    } else {
+      format!("{}.{}", self.prefix, key)
+    }
+  }
+
+  /// value of key, string keys are looked up before tag keys in maps like `EdnMapView::get_by`
+  fn lookup(&self, key: &str) -> &'a Edn {
+    match self.data {
+      Edn::Map(xs) => xs.get_by(key).unwrap_or(&Edn::Nil),
+      Edn::Record(EdnRecordView { pairs, .. }) => pairs
+        .iter()
+        .find(|(k, _)| k.ref_str() == key)
+        .map(|(_, v)| v)
+        .unwrap_or(&Edn::Nil),
+      _ => &Edn::Nil,
+    }
+  }
+
+  /// read a required field, missing field is recorded as an error
+  pub fn field<T: TryFrom<Edn, Error = String>>(&mut self, key: &str) -> Option<T> {
+    match self.lookup(key) {
+      Edn::Nil => {
+        self.errors.push((self.path(key), String::from("missing field")));
+        None
+      }
+      v => self.convert(key, v),
+    }
+  }
+
+  /// read an optional field, nil gives `None` without an error
+  pub fn optional<T: TryFrom<Edn, Error = String>>(&mut self, key: &str) -> Option<T> {
+    match self.lookup(key) {
+      Edn::Nil => None,
+      v => self.convert(key, v),
+    }
+  }
+
+  /// read fields of a nested map or record, errors are collected with the path of `key`
+  pub fn nested<T>(&mut self, key: &str, f: impl FnOnce(&mut EdnFields<'a>) -> Option<T>) -> Option<T> {
+    let mut child = EdnFields::with_prefix(self.lookup(key), self.path(key));
+    let ret = f(&mut child);
+    self.errors.append(&mut child.errors);
+    ret
+  }
+
+  fn convert<T: TryFrom<Edn, Error = String>>(&mut self, key: &str, v: &Edn) -> Option<T> {
+    match T::try_from(v.to_owned()) {
+      Ok(x) => Some(x),
+      Err(e) => {
+        self.errors.push((self.path(key), e));
+        None
+      }
+    }
+  }
+
+  /// errors collected so far, in pairs of path and message
+  pub fn errors(&self) -> &[(String, String)] {
+    &self.errors
+  }
+
+  /// returns all collected errors
+  pub fn finish(self) -> Result<(), Vec<(String, String)>> {
+    if self.errors.is_empty() {
+      Ok(())
+    } else {
+      Err(self.errors)
+    }
+  }
+}
@@ -0,0 +1,156 @@
+//! converting Edn from and to JSON values, enabled with `json` feature.
+//!
+//! conventions:
+//!
+//! - objects become maps with string keys, not tags, arrays become lists, null becomes nil,
+//! - numbers without fractions that fit in `i64` are written as JSON integers, others as floats,
+//! - records are written as objects of their fields, sets as arrays, tags as `:name` strings.
+//!
+//! JSON has no tuples, buffers, symbols, quoted code, atoms or any-refs, converting them is an error
+//! with the path to the value, and so are NaN and infinities. map keys need to be strings or tags,
+//! and a string key like `|:a` colliding with the tag `:a` is an error too.
+//!
+//! ```
+//! use cirru_edn::Edn;
+//! use serde_json::json;
+//!
+//! let e: Edn = json!({"a": 1}).into();
+//! assert_eq!(e, cirru_edn::parse("{} (|a 1)").unwrap());
+//! assert_eq!(serde_json::Value::try_from(e), Ok(json!({"a": 1})));
+//! ```
+
+use serde_json::{Map, Number, Value};
+
+use crate::edn::ERROR_PREVIEW_LEN;
+use crate::{Edn, EdnListView, EdnMapView, EdnRecordView};
+
+/// read a JSON value into Edn
+pub fn json_to_edn(v: &Value) -> Edn {
+  match v {
+    Value::Null => Edn::Nil,
+    Value::Bool(b) => Edn::Bool(*b),
+    // always some without `arbitrary_precision` of serde_json
+    Value::Number(n) => Edn::Number(n.as_f64().unwrap_or(f64::NAN)),
+    Value::String(s) => Edn::str(s.as_str()),
+    Value::Array(xs) => Edn::List(EdnListView(xs.iter().map(json_to_edn).collect())),
+    Value::Object(m) => Edn::Map(EdnMapView(
+      m.iter().map(|(k, v)| (Edn::str(k.as_str()), json_to_edn(v))).collect(),
+    )),
+  }
+}
+
+/// generate a JSON value from Edn, errors on data JSON can not express
+pub fn edn_to_json(data: &Edn) -> Result<Value, String> {
+  edn_to_json_at(data, &mut String::new())
+}
+
+fn edn_to_json_at(data: &Edn, path: &mut String) -> Result<Value, String> {
+  let failed = |path: &str| {
+    format!(
+      "failed to convert {} to json at {}",
+      data.type_name(),
+      if path.is_empty() { "top level" } else { path }
+    )
+  };
+  match data {
+    Edn::Nil => Ok(Value::Null),
+    Edn::Bool(b) => Ok(Value::Bool(*b)),
+    Edn::Number(n) => {
+      if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Ok(Value::Number(Number::from(*n as i64)))
+      } else {
+        Number::from_f64(*n)
+          .map(Value::Number)
+          .ok_or_else(|| format!("{}, got {}", failed(path), n))
+      }
+    }
+    Edn::Str(s) => Ok(Value::String((**s).to_owned())),
+    Edn::Tag(t) => Ok(Value::String(format!(":{}", t))),
+    Edn::List(EdnListView(xs)) => {
+      let mut ys = Vec::with_capacity(xs.len());
+      for (idx, x) in xs.iter().enumerate() {
+        let len = path.len();
+        path.push_str(&format!("[{}]", idx));
+        ys.push(edn_to_json_at(x, path)?);
+        path.truncate(len);
+      }
+      Ok(Value::Array(ys))
+    }
+    Edn::Set(xs) => {
+      let mut ys = Vec::with_capacity(xs.len());
+      for x in xs {
+        let len = path.len();
+        path.push_str(&format!("#{}", x.preview(ERROR_PREVIEW_LEN)));
+        ys.push(edn_to_json_at(x, path)?);
+        path.truncate(len);
+      }
+      Ok(Value::Array(ys))
+    }
+    Edn::Map(xs) => {
+      let mut m = Map::new();
+      for (k, v) in xs {
+        let key = match k {
+          Edn::Str(s) => (**s).to_owned(),
+          Edn::Tag(s) => format!(":{}", s),
+          _ => {
+            return Err(format!(
+              "{}, key is not a string: {}",
+              failed(path),
+              k.preview(ERROR_PREVIEW_LEN)
+            ))
+          }
+        };
+        if m.contains_key(&key) {
+          return Err(format!(
+            "{}, key {} collides with another key as {:?}",
+            failed(path),
+            k,
+            key
+          ));
+        }
+        let len = path.len();
+        path.push_str(&format!(".{}", k));
+        m.insert(key, edn_to_json_at(v, path)?);
+        path.truncate(len);
+      }
+      Ok(Value::Object(m))
+    }
+    Edn::Record(EdnRecordView { pairs, .. }) => {
+      let mut m = Map::new();
+      for (k, v) in pairs {
+        let len = path.len();
+        path.push_str(&format!(".{}", k));
+        m.insert(k.to_string(), edn_to_json_at(v, path)?);
+        path.truncate(len);
+      }
+      Ok(Value::Object(m))
+    }
+    _ => Err(failed(path)),
+  }
+}
+
+impl From<Value> for Edn {
+  fn from(v: Value) -> Edn {
+    json_to_edn(&v)
+  }
+}
+
+impl From<&Value> for Edn {
+  fn from(v: &Value) -> Edn {
+    json_to_edn(v)
+  }
+}
+
+impl TryFrom<Edn> for Value {
+  type Error = String;
+  fn try_from(data: Edn) -> Result<Self, Self::Error> {
+    edn_to_json(&data)
+  }
+}
+
+impl TryFrom<&Edn> for Value {
+  type Error = String;
+  fn try_from(data: &Edn) -> Result<Self, Self::Error> {
+    edn_to_json(data)
+  }
+}
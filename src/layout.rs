@@ -0,0 +1,686 @@
+//! keeping the layout of parsed text, so writing edited data back changes as few lines as possible.
+//!
+//! lines and leaves are kept as they were written, including comments, blank lines and spaces at ends of lines,
+//! while spaces between items are normalized to one. values that changed are written like `format` at the
+//! same places, entries missing in the text are added in new lines.
+
+use std::sync::Arc;
+
+use cirru_parser::{Cirru, CirruWriterOptions};
+
+use crate::{
+  assemble_cirru_node, extract_cirru_edn, format, is_comment, one_liner, parse, record_tag_name, Edn, EdnMapView,
+  EdnSetView, EdnTupleView,
+};
+
+/// layout of text from `parse_with_layout`, for `format_with_layout`.
+/// empty when the layout can't be kept, and then `format_with_layout` works like `format`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EdnLayoutHints {
+  lines: Vec<RawItem>,
+  trailing: String,
+}
+
+impl EdnLayoutHints {
+  pub fn is_empty(&self) -> bool {
+    self.lines.is_empty()
+  }
+}
+
+/// text before `$` and `,` are resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RawItem {
+  /// a leaf as written, with its value
+  Leaf { text: Arc<str>, value: Arc<str> },
+  /// an expression in parentheses
+  Paren(Vec<RawItem>),
+  /// an indented line, child lines are at the end of items.
+  /// `trailing` counts spaces after the last item, like the ones `format` leaves after `$`
+  Line {
+    blank_before: usize,
+    trailing: usize,
+    items: Vec<RawItem>,
+  },
+}
+
+impl RawItem {
+  fn leaf(s: &str) -> Self {
+    RawItem::Leaf {
+      text: s.into(),
+      value: s.into(),
+    }
+  }
+
+  fn is_leaf(&self, s: &str) -> bool {
+    matches!(self, RawItem::Leaf { value, .. } if &**value == s)
+  }
+
+  /// items after `,` of an expression spliced into its parent
+  fn comma_items(&self) -> Option<&[RawItem]> {
+    match self {
+      RawItem::Paren(xs) | RawItem::Line { items: xs, .. } if xs.first().is_some_and(|x| x.is_leaf(",")) => {
+        Some(&xs[1..])
+      }
+      _ => None,
+    }
+  }
+}
+
+/// an item of a list after `$` and `,` are resolved
+#[derive(Debug, Clone, Copy)]
+enum Slot<'a> {
+  Item(&'a RawItem),
+  /// items after a `$`, which form a list
+  Dollar(&'a [RawItem]),
+}
+
+/// parse with the layout kept for `format_with_layout`
+pub fn parse_with_layout(s: &str) -> Result<(Edn, EdnLayoutHints), String> {
+  let data = parse(s)?;
+  let tree = cirru_parser::parse(s)?;
+  let hints = match read_layout(s) {
+    // the layout is only kept when it's read the same way as Cirru parser does
+    Ok(hints) if hints.lines.iter().map(|x| slot_cirru(Slot::Item(x))).eq(tree) => hints,
+    _ => EdnLayoutHints::default(),
+  };
+  Ok((data, hints))
+}
+
+/// generate string from Edn, reusing lines of the text `hints` came from.
+/// unchanged data gives the original text, apart from spaces between items,
+/// so text written by `format` comes back byte for byte.
+/// the text is parsed again, and `format` is used when it does not give back `data`
+pub fn format_with_layout(data: &Edn, hints: &EdnLayoutHints) -> Result<String, String> {
+  match write_with_layout(data, hints)? {
+    Some(out) if parse(&out).as_ref() == Ok(data) => Ok(out),
+    _ => format(data, false),
+  }
+}
+
+/// text with reused lines, `None` when the layout can not be reused
+fn write_with_layout(data: &Edn, hints: &EdnLayoutHints) -> Result<Option<String>, String> {
+  let roots = hints
+    .lines
+    .iter()
+    .enumerate()
+    .filter(|(_, x)| !is_comment(&slot_cirru(Slot::Item(x))))
+    .collect::<Vec<_>>();
+  let (idx, root) = match roots.as_slice() {
+    [root] if !data.has_any_ref() => *root,
+    _ => return Ok(None),
+  };
+  let RawItem::Line {
+    blank_before,
+    trailing,
+    items,
+  } = root
+  else {
+    unreachable!("top level items are lines")
+  };
+  let items = match extract_cirru_edn(&list_cirru(items)) {
+    Ok(old) if &old == data => items.to_owned(),
+    Ok(old) => match reconcile_list(items, &old, data, false)? {
+      Some(ys) => ys,
+      None => return Ok(None),
+    },
+    Err(_) => return Ok(None),
+  };
+
+  let mut lines = hints.lines.to_owned();
+  lines[idx] = RawItem::Line {
+    blank_before: *blank_before,
+    trailing: *trailing,
+    items,
+  };
+  let mut out = String::new();
+  for (idx, line) in lines.iter().enumerate() {
+    if let RawItem::Line {
+      blank_before,
+      trailing,
+      items,
+    } = line
+    {
+      if idx > 0 {
+        out.push('\n');
+      }
+      out.push_str(&"\n".repeat(*blank_before));
+      write_line(items, *trailing, 0, &mut out);
+    }
+  }
+  out.push_str(&hints.trailing);
+  Ok(Some(out))
+}
+
+fn read_layout(s: &str) -> Result<EdnLayoutHints, String> {
+  let content = s.trim_end();
+  let mut flat: Vec<(usize, usize, usize, Vec<RawItem>)> = vec![];
+  let mut blank_before = 0;
+  for line in content.split('\n') {
+    let text = line.trim_start_matches(' ');
+    if text.trim().is_empty() {
+      blank_before += 1;
+      continue;
+    }
+    let indent = line.len() - text.len();
+    if indent % 2 != 0 {
+      return Err(format!("odd indentation in line: {}", line));
+    }
+    let trailing = text.len() - text.trim_end_matches(' ').len();
+    flat.push((indent / 2, blank_before, trailing, read_items(text)?));
+    blank_before = 0;
+  }
+  let mut flat = flat.into_iter().peekable();
+  let lines = build_lines(&mut flat, 0)?;
+  if flat.next().is_some() {
+    return Err(String::from("unexpected indentation at top level"));
+  }
+  Ok(EdnLayoutHints {
+    lines,
+    trailing: s[content.len()..].to_owned(),
+  })
+}
+
+/// items of a line, following the lexer of Cirru
+fn read_items(line: &str) -> Result<Vec<RawItem>, String> {
+  let mut stack: Vec<Vec<RawItem>> = vec![vec![]];
+  let mut chars = line.char_indices().peekable();
+  while let Some((start, c)) = chars.next() {
+    match c {
+      ' ' => {}
+      '(' => stack.push(vec![]),
+      ')' => {
+        let xs = stack.pop().expect("stack is not empty");
+        match stack.last_mut() {
+          Some(ys) => ys.push(RawItem::Paren(xs)),
+          None => return Err(format!("unexpected `)` in line: {}", line)),
+        }
+      }
+      '"' => {
+        let mut value = String::new();
+        let mut end = None;
+        while let Some((idx, c)) = chars.next() {
+          match c {
+            '"' => {
+              end = Some(idx);
+              break;
+            }
+            '\\' => match chars.next() {
+              Some((_, 'n')) => value.push('\n'),
+              Some((_, 't')) => value.push('\t'),
+              Some((_, 'r')) => value.push('\r'),
+              Some((_, c @ ('"' | '\'' | '\\'))) => value.push(c),
+              _ => return Err(format!("unsupported escape in line: {}", line)),
+            },
+            _ => value.push(c),
+          }
+        }
+        let end = end.ok_or_else(|| format!("unclosed string in line: {}", line))?;
+        stack.last_mut().expect("stack is not empty").push(RawItem::Leaf {
+          text: line[start..=end].into(),
+          value: value.into(),
+        });
+      }
+      _ => {
+        let mut end = start + c.len_utf8();
+        while let Some((idx, c)) = chars.next_if(|(_, c)| !matches!(c, ' ' | '(' | ')' | '"')) {
+          end = idx + c.len_utf8();
+        }
+        stack
+          .last_mut()
+          .expect("stack is not empty")
+          .push(RawItem::leaf(&line[start..end]));
+      }
+    }
+  }
+  match <[_; 1]>::try_from(stack) {
+    Ok([xs]) => Ok(xs),
+    Err(_) => Err(format!("unclosed `(` in line: {}", line)),
+  }
+}
+
+/// lines at `level` with their child lines
+fn build_lines(
+  flat: &mut std::iter::Peekable<impl Iterator<Item = (usize, usize, usize, Vec<RawItem>)>>,
+  level: usize,
+) -> Result<Vec<RawItem>, String> {
+  let mut lines = vec![];
+  while let Some((line_level, ..)) = flat.peek() {
+    if *line_level < level {
+      break;
+    }
+    if *line_level > level {
+      if level == 0 {
+        return Err(String::from("unexpected indentation at top level"));
+      }
+      // Cirru parser reads lines indented by several levels as an extra list
+      let items = build_lines(flat, level + 1)?;
+      lines.push(RawItem::Line {
+        blank_before: 0,
+        trailing: 0,
+        items,
+      });
+      continue;
+    }
+    let (_, blank_before, trailing, mut items) = flat.next().expect("peeked");
+    items.extend(build_lines(flat, level + 1)?);
+    lines.push(RawItem::Line {
+      blank_before,
+      trailing,
+      items,
+    });
+  }
+  Ok(lines)
+}
+
+fn write_line(items: &[RawItem], trailing: usize, level: usize, out: &mut String) {
+  out.push_str(&"  ".repeat(level));
+  let mut first = true;
+  let mut trailing = Some(trailing);
+  for x in items {
+    match x {
+      RawItem::Line {
+        blank_before,
+        trailing: child_trailing,
+        items,
+      } => {
+        if let Some(n) = trailing.take() {
+          out.push_str(&" ".repeat(n));
+        }
+        write_child(*blank_before, *child_trailing, items, level + 1, out)
+      }
+      _ => {
+        if !first {
+          out.push(' ');
+        }
+        write_inline(x, out);
+        first = false;
+      }
+    }
+  }
+  if let Some(n) = trailing {
+    out.push_str(&" ".repeat(n));
+  }
+}
+
+fn write_child(blank_before: usize, trailing: usize, items: &[RawItem], level: usize, out: &mut String) {
+  match items.first() {
+    // a line of only child lines is written as lines indented one more level
+    Some(RawItem::Line { .. }) if items.iter().all(|x| matches!(x, RawItem::Line { .. })) => {
+      for x in items {
+        if let RawItem::Line {
+          blank_before,
+          trailing,
+          items,
+        } = x
+        {
+          write_child(*blank_before, *trailing, items, level + 1, out);
+        }
+      }
+    }
+    _ => {
+      out.push('\n');
+      out.push_str(&"\n".repeat(blank_before));
+      write_line(items, trailing, level, out);
+    }
+  }
+}
+
+fn write_inline(x: &RawItem, out: &mut String) {
+  match x {
+    RawItem::Leaf { text, .. } => out.push_str(text),
+    RawItem::Paren(xs) | RawItem::Line { items: xs, .. } => {
+      out.push('(');
+      for (idx, y) in xs.iter().enumerate() {
+        if idx > 0 {
+          out.push(' ');
+        }
+        write_inline(y, out);
+      }
+      out.push(')');
+    }
+  }
+}
+
+/// items of a list like Cirru parser resolves `$` and `,`
+fn resolve_slots<'a>(items: &'a [RawItem], slots: &mut Vec<Slot<'a>>) {
+  for (idx, x) in items.iter().enumerate() {
+    if x.is_leaf("$") {
+      let rest = &items[idx + 1..];
+      match rest.first() {
+        Some(first) if first.is_leaf(",") => resolve_slots(&rest[1..], slots),
+        _ => slots.push(Slot::Dollar(rest)),
+      }
+      return;
+    }
+    if x.is_leaf(",") {
+      continue;
+    }
+    match x.comma_items() {
+      Some(ys) => resolve_slots(ys, slots),
+      None => slots.push(Slot::Item(x)),
+    }
+  }
+}
+
+fn slot_items<'a>(slot: Slot<'a>) -> Option<&'a [RawItem]> {
+  match slot {
+    Slot::Item(RawItem::Leaf { .. }) => None,
+    Slot::Item(RawItem::Paren(xs) | RawItem::Line { items: xs, .. }) => Some(xs),
+    Slot::Dollar(xs) => Some(xs),
+  }
+}
+
+fn slot_cirru(slot: Slot) -> Cirru {
+  match (slot, slot_items(slot)) {
+    (Slot::Item(RawItem::Leaf { value, .. }), _) => Cirru::Leaf(value.to_owned()),
+    (_, Some(xs)) => list_cirru(xs),
+    (_, None) => unreachable!("only leaves have no items"),
+  }
+}
+
+fn list_cirru(items: &[RawItem]) -> Cirru {
+  let mut slots = vec![];
+  resolve_slots(items, &mut slots);
+  Cirru::List(slots.into_iter().map(slot_cirru).collect())
+}
+
+/// raw items of a slot as written
+fn slot_raw(slot: Slot) -> Vec<RawItem> {
+  match slot {
+    Slot::Item(x) => vec![x.to_owned()],
+    Slot::Dollar(xs) => [RawItem::leaf("$")].into_iter().chain(xs.iter().cloned()).collect(),
+  }
+}
+
+/// raw items of a list in the same place as the slot
+fn wrap_slot(slot: Slot, items: Vec<RawItem>) -> Vec<RawItem> {
+  match slot {
+    Slot::Item(RawItem::Line {
+      blank_before, trailing, ..
+    }) => vec![RawItem::Line {
+      blank_before: *blank_before,
+      trailing: *trailing,
+      items,
+    }],
+    Slot::Item(_) => vec![RawItem::Paren(items)],
+    Slot::Dollar(_) => [RawItem::leaf("$")].into_iter().chain(items).collect(),
+  }
+}
+
+/// raw items written for `new` in place of a slot, reusing the slot for unchanged data
+fn reconcile_slot(slot: Slot, new: &Edn) -> Result<Vec<RawItem>, String> {
+  let old = extract_cirru_edn(&slot_cirru(slot));
+  if old.as_ref() == Ok(new) {
+    return Ok(slot_raw(slot));
+  }
+  if let (Ok(old), Some(items)) = (&old, slot_items(slot)) {
+    if let Some(ys) = reconcile_list(items, old, new, matches!(slot, Slot::Item(RawItem::Paren(_))))? {
+      return Ok(wrap_slot(slot, ys));
+    }
+  }
+  let node = assemble_cirru_node(new);
+  match (slot, &node) {
+    (Slot::Item(RawItem::Line { blank_before, .. }), _) => Ok(vec![RawItem::Line {
+      blank_before: *blank_before,
+      trailing: 0,
+      items: line_raw(&node)?,
+    }]),
+    (Slot::Item(_), _) | (Slot::Dollar(_), Cirru::Leaf(_)) => Ok(vec![inline_raw(&node)?]),
+    (Slot::Dollar(_), Cirru::List(_)) => Ok(wrap_slot(slot, line_raw(&node)?)),
+  }
+}
+
+/// items of a list reusing the layout of `items`, `None` when the layout does not fit `new`
+fn reconcile_list(items: &[RawItem], old: &Edn, new: &Edn, inline: bool) -> Result<Option<Vec<RawItem>>, String> {
+  let mut slots = vec![];
+  resolve_slots(items, &mut slots);
+  // slots of data, with indexes in `slots`
+  let data = slots
+    .iter()
+    .enumerate()
+    .filter(|(_, slot)| !is_comment(&slot_cirru(**slot)))
+    .map(|(idx, slot)| (idx, *slot))
+    .collect::<Vec<_>>();
+  let head = match data.first() {
+    Some((_, Slot::Item(RawItem::Leaf { value, .. }))) => value.to_owned(),
+    _ => return Ok(None),
+  };
+  // replacements of slots, and new nodes added at the end
+  let mut replaced: Vec<Option<Vec<RawItem>>> = vec![None; slots.len()];
+  let mut added: Vec<Cirru> = vec![];
+
+  match (&*head, old, new) {
+    // `do` is kept for literals, collections are written like `format` does
+    ("do", _, _) => match data.as_slice() {
+      [_, (idx, inner @ Slot::Item(RawItem::Leaf { .. }))] if new.is_literal() => {
+        replaced[*idx] = Some(reconcile_slot(*inner, new)?)
+      }
+      _ => return Ok(None),
+    },
+    ("[]", Edn::List(a), Edn::List(b)) => {
+      if !reconcile_items(&data[1..], a.len(), &b.0, &mut replaced, &mut added)? {
+        return Ok(None);
+      }
+    }
+    ("::", Edn::Tuple(a), Edn::Tuple(EdnTupleView { tag, extra })) => {
+      let b = [(**tag).to_owned()]
+        .into_iter()
+        .chain(extra.iter().cloned())
+        .collect::<Vec<_>>();
+      if !reconcile_items(&data[1..], a.extra.len() + 1, &b, &mut replaced, &mut added)? {
+        return Ok(None);
+      }
+    }
+    ("#{}", Edn::Set(a), Edn::Set(b)) => {
+      if data.len() != a.len() + 1 {
+        return Ok(None);
+      }
+      for (idx, slot) in &data[1..] {
+        let item = extract_cirru_edn(&slot_cirru(*slot))?;
+        if !b.contains(&item) {
+          replaced[*idx] = Some(vec![]);
+        }
+      }
+      let rest = EdnSetView(b.iter().filter(|x| !a.contains(x)).cloned().collect());
+      added.extend(tail_nodes(&Edn::Set(rest)));
+    }
+    ("{}", Edn::Map(a), Edn::Map(b)) => {
+      for (idx, slot) in &data[1..] {
+        let Some((k, v_slot)) = entry_slots(*slot)? else {
+          continue;
+        };
+        let k = extract_cirru_edn(&k)?;
+        replaced[*idx] = Some(match b.get(&k) {
+          Some(v) => reconcile_entry(*slot, v_slot, v)?,
+          None => vec![],
+        });
+      }
+      let rest = EdnMapView(
+        b.iter()
+          .filter(|(k, _)| a.get(k).is_none())
+          .map(|(k, v)| (k.to_owned(), v.to_owned()))
+          .collect(),
+      );
+      added.extend(tail_nodes(&Edn::Map(rest)));
+    }
+    ("%{}", Edn::Record(a), Edn::Record(b)) if a.tag == b.tag => {
+      // fields are kept in place, so the order needs to stay
+      let kept = a.keys().filter(|k| b.has_key(k.ref_str()));
+      let rest = b.pairs.iter().filter(|(k, _)| !a.has_key(k.ref_str()));
+      if !kept.chain(rest.clone().map(|(k, _)| k)).eq(b.keys()) {
+        return Ok(None);
+      }
+      for (idx, slot) in data.iter().skip(2) {
+        let Some((Cirru::Leaf(name), v_slot)) = entry_slots(*slot)? else {
+          continue;
+        };
+        let name = record_tag_name(&name)?;
+        replaced[*idx] = Some(match b.pairs.iter().find(|(k, _)| k.ref_str() == name) {
+          Some((_, v)) => reconcile_entry(*slot, v_slot, v)?,
+          None => vec![],
+        });
+      }
+      // `format` moves fields of literals ahead, while new fields need to follow the order in data
+      added.extend(rest.map(|(k, v)| Cirru::List(vec![format!(":{}", k).into(), assemble_cirru_node(v)])));
+    }
+    _ => return Ok(None),
+  }
+
+  let mut replaced = replaced.into_iter();
+  let mut ys = rebuild(items, &mut replaced);
+  if added.is_empty() {
+    return Ok(Some(ys));
+  }
+  // new items after `$` would go into the list of `$`
+  if ys.iter().any(|y| y.is_leaf("$")) {
+    return Ok(None);
+  }
+  if inline {
+    for node in &added {
+      ys.push(inline_raw(node)?);
+    }
+    return Ok(Some(ys));
+  }
+  for node in &added {
+    match node {
+      Cirru::Leaf(_) if !ys.iter().any(|y| matches!(y, RawItem::Line { .. })) => ys.push(inline_raw(node)?),
+      _ => ys.push(RawItem::Line {
+        blank_before: 0,
+        trailing: 0,
+        items: line_raw(node)?,
+      }),
+    }
+  }
+  Ok(Some(ys))
+}
+
+/// reconcile items by positions, `false` when slots don't match old items
+fn reconcile_items(
+  slots: &[(usize, Slot)],
+  old_len: usize,
+  new: &[Edn],
+  replaced: &mut [Option<Vec<RawItem>>],
+  added: &mut Vec<Cirru>,
+) -> Result<bool, String> {
+  if slots.len() != old_len {
+    return Ok(false);
+  }
+  for (i, (idx, slot)) in slots.iter().enumerate() {
+    replaced[*idx] = Some(match new.get(i) {
+      Some(x) => reconcile_slot(*slot, x)?,
+      None => vec![],
+    });
+  }
+  added.extend(new.iter().skip(slots.len()).map(assemble_cirru_node));
+  Ok(true)
+}
+
+/// key and value slot of an entry, `None` for lists parser skips
+fn entry_slots(slot: Slot) -> Result<Option<(Cirru, Slot)>, String> {
+  let Some(items) = slot_items(slot) else {
+    return Ok(None);
+  };
+  let mut slots = vec![];
+  resolve_slots(items, &mut slots);
+  match slots.as_slice() {
+    [k, v] => Ok(Some((slot_cirru(*k), *v))),
+    _ => Ok(None),
+  }
+}
+
+/// the entry with its value replaced, the key is kept as written
+fn reconcile_entry(entry: Slot, v_slot: Slot, v: &Edn) -> Result<Vec<RawItem>, String> {
+  if extract_cirru_edn(&slot_cirru(v_slot)).as_ref() == Ok(v) {
+    return Ok(slot_raw(entry));
+  }
+  let items = slot_items(entry).expect("entries are lists");
+  let mut replaced = [None, Some(reconcile_slot(v_slot, v)?)].into_iter();
+  Ok(wrap_slot(entry, rebuild(items, &mut replaced)))
+}
+
+/// nodes after the head of a collection, in the order of `format`
+fn tail_nodes(data: &Edn) -> Vec<Cirru> {
+  match assemble_cirru_node(data) {
+    Cirru::List(xs) => xs.into_iter().skip(1).collect(),
+    Cirru::Leaf(_) => vec![],
+  }
+}
+
+/// rebuild items with slots replaced in the order of `resolve_slots`, `None` keeps a slot
+fn rebuild(items: &[RawItem], replaced: &mut impl Iterator<Item = Option<Vec<RawItem>>>) -> Vec<RawItem> {
+  let mut ys = vec![];
+  for (idx, x) in items.iter().enumerate() {
+    if x.is_leaf("$") {
+      let rest = &items[idx + 1..];
+      match rest.first() {
+        Some(first) if first.is_leaf(",") => {
+          ys.push(x.to_owned());
+          ys.push(first.to_owned());
+          ys.extend(rebuild(&rest[1..], replaced));
+        }
+        _ => match replaced.next() {
+          Some(Some(zs)) => ys.extend(zs),
+          _ => ys.extend(slot_raw(Slot::Dollar(rest))),
+        },
+      }
+      break;
+    }
+    if x.is_leaf(",") {
+      ys.push(x.to_owned());
+      continue;
+    }
+    match (x, x.comma_items()) {
+      (RawItem::Paren(_), Some(zs)) => {
+        let inner = rebuild(zs, replaced);
+        if !inner.is_empty() {
+          ys.push(RawItem::Paren([RawItem::leaf(",")].into_iter().chain(inner).collect()));
+        }
+      }
+      (
+        RawItem::Line {
+          blank_before, trailing, ..
+        },
+        Some(zs),
+      ) => {
+        let inner = rebuild(zs, replaced);
+        if !inner.is_empty() {
+          ys.push(RawItem::Line {
+            blank_before: *blank_before,
+            trailing: *trailing,
+            items: [RawItem::leaf(",")].into_iter().chain(inner).collect(),
+          });
+        }
+      }
+      _ => match replaced.next() {
+        Some(Some(zs)) => ys.extend(zs),
+        _ => ys.push(x.to_owned()),
+      },
+    }
+  }
+  ys
+}
+
+/// a node written inside a line
+fn inline_raw(node: &Cirru) -> Result<RawItem, String> {
+  match node {
+    Cirru::Leaf(s) => Ok(RawItem::Leaf {
+      text: one_liner(node)?.into(),
+      value: s.to_owned(),
+    }),
+    Cirru::List(xs) => Ok(RawItem::Paren(xs.iter().map(inline_raw).collect::<Result<_, _>>()?)),
+  }
+}
+
+/// items of a node written as a line like `format`, leaves are written after `,`
+fn line_raw(node: &Cirru) -> Result<Vec<RawItem>, String> {
+  match node {
+    Cirru::Leaf(_) => Ok(vec![RawItem::leaf(","), inline_raw(node)?]),
+    Cirru::List(_) => {
+      let text = cirru_parser::format(&[node.to_owned()], CirruWriterOptions { use_inline: false })?;
+      match read_layout(&text)?.lines.pop() {
+        Some(RawItem::Line { items, .. }) => Ok(items),
+        _ => Err(format!("failed to read formatted line: {}", text)),
+      }
+    }
+  }
+}
@@ -1,52 +1,355 @@
+#[macro_use]
+mod trace;
+
+#[cfg(feature = "bincode")]
+mod codec;
 mod edn;
+mod fields;
+#[cfg(feature = "json")]
+mod json;
+mod layout;
+mod merge;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod partial;
+mod stats;
 mod tag;
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "yaml")]
+mod yaml;
 
+use std::borrow::Cow;
 use std::cmp::Ordering::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::iter::FromIterator;
 use std::sync::Arc;
 use std::vec;
 
 use cirru_parser::{Cirru, CirruWriterOptions};
 
+#[cfg(feature = "toml")]
+pub use crate::toml::{edn_to_toml, toml_to_edn};
 pub use edn::{
-  is_simple_char, DynEq, Edn, EdnAnyRef, EdnListView, EdnMapView, EdnRecordView, EdnSetView, EdnTupleView,
+  is_simple_char, is_simple_str_char, DynEq, Edn, EdnAnyRef, EdnAtomView, EdnKey, EdnListView, EdnListViewIter,
+  EdnMapView, EdnMapViewIntoIter, EdnMapViewIter, EdnRecordView, EdnRecordViewIter, EdnSetView, EdnSetViewIntoIter,
+  EdnSetViewIter, EdnStrictMapView, EdnStringStats, EdnTupleView, FrozenEdn, MapKeyLookup, NameKind, TryFromF64,
 };
-pub use tag::EdnTag;
+pub use fields::EdnFields;
+#[cfg(feature = "json")]
+pub use json::{edn_to_json, json_to_edn};
+pub use layout::{format_with_layout, parse_with_layout, EdnLayoutHints};
+pub use merge::{merge_parse, MergeSource, Provenance};
+pub use partial::extract_path;
+pub use stats::EdnParseStats;
+pub use tag::{EdnTag, TagDispatch};
+#[cfg(feature = "yaml")]
+pub use yaml::{edn_to_yaml_string, yaml_to_edn};
+
+use edn::{escape_str, normalize_zero, unescape_str};
 
-/// parse Cirru code into data
+/// parse Cirru code into data, top-level comments are ignored
 pub fn parse(s: &str) -> Result<Edn, String> {
+  let _span = trace_span!("cirru_edn::parse", bytes = s.len());
+  let xs = {
+    let _span = trace_span!("cirru_edn::read_cirru");
+    cirru_parser::parse(s)?
+  };
+  let node = data_expr(&xs)?;
+  let _span = trace_span!("cirru_edn::extract", nodes = trace::cirru_node_count(node));
+  extract_checked(node)
+}
+
+/// parse like `parse`, also counting nodes of the data during extraction
+pub fn parse_with_stats(s: &str) -> Result<(Edn, EdnParseStats), String> {
   let xs = cirru_parser::parse(s)?;
-  if xs.len() == 1 {
-    match &xs[0] {
-      Cirru::Leaf(s) => Err(format!("expected expr for data, got leaf: {}", s)),
-      Cirru::List(_) => extract_cirru_edn(&xs[0]),
+  let mut counter = stats::CountEdn::default();
+  counter.stats.comments = xs.iter().filter(|x| is_comment(x)).count();
+  let (data, depth) = extract_with(&mut counter, data_expr(&xs)?, &mut vec![])?;
+  counter.stats.max_depth = depth;
+  data.check_atom_labels()?;
+  Ok((data, counter.stats))
+}
+
+/// the only expression of data in a file, besides comments
+fn data_expr(xs: &[Cirru]) -> Result<&Cirru, String> {
+  let xs: Vec<&Cirru> = xs.iter().filter(|x| !is_comment(x)).collect();
+  match xs.as_slice() {
+    [] => Err(String::from("no data expression found (only comments/whitespace)")),
+    [Cirru::Leaf(s)] => Err(format!("expected expr for data, got leaf: {}", s)),
+    [x] => Ok(x),
+    _ => Err(format!("Expected 1 expr for edn, got length {}: {:?} ", xs.len(), xs)),
+  }
+}
+
+/// a step of the position in data for error messages, written like queries of `Edn::select`
+enum ParsePathSeg<'a> {
+  Index(usize),
+  Key(&'a Cirru),
+  Member(&'a Cirru),
+  Field(&'a str),
+}
+
+fn render_parse_path(path: &[ParsePathSeg]) -> String {
+  let mut buf = String::new();
+  for seg in path {
+    match seg {
+      ParsePathSeg::Index(i) => buf.push_str(&format!("[{}]", i)),
+      ParsePathSeg::Key(k) => buf.push_str(&format!(".{}", preview_path_node(k))),
+      ParsePathSeg::Member(x) => buf.push_str(&format!("#{}", preview_path_node(x))),
+      ParsePathSeg::Field(f) => buf.push_str(&format!(".{}", f)),
     }
+  }
+  if buf.is_empty() {
+    String::from("top level")
   } else {
-    Err(format!("Expected 1 expr for edn, got length {}: {:?} ", xs.len(), xs))
+    buf
   }
 }
 
-fn extract_cirru_edn(node: &Cirru) -> Result<Edn, String> {
+fn preview_path_node(node: &Cirru) -> String {
   match node {
-    Cirru::Leaf(s) => match &**s {
-      "nil" => Ok(Edn::Nil),
-      "true" => Ok(Edn::Bool(true)),
-      "false" => Ok(Edn::Bool(false)),
-      "" => Err(String::from("empty string is invalid for edn")),
-      s1 => match s1.chars().next().unwrap() {
-        '\'' => Ok(Edn::Symbol(s1[1..].into())),
-        ':' => Ok(Edn::tag(&s1[1..])),
-        '"' | '|' => Ok(Edn::Str(s1[1..].into())),
-        _ => {
-          if let Ok(f) = s1.trim().parse::<f64>() {
-            Ok(Edn::Number(f))
-          } else {
-            Err(format!("unknown token for edn value: {:?}", s1))
+    Cirru::Leaf(_) => preview_node(node),
+    Cirru::List(_) => format!("({})", preview_node(node)),
+  }
+}
+
+/// one-liner preview of code, long code is truncated
+fn preview_node(node: &Cirru) -> String {
+  let s = match node {
+    Cirru::Leaf(s) => s.to_string(),
+    Cirru::List(_) => cirru_parser::format_expr_one_liner(node).unwrap_or_else(|_| format!("{}", node)),
+  };
+  if s.chars().count() > 40 {
+    format!("{}...", s.chars().take(40).collect::<String>())
+  } else {
+    s
+  }
+}
+
+fn extract_cirru_edn(node: &Cirru) -> Result<Edn, String> {
+  extract_with(&mut BuildEdn, node, &mut vec![])
+}
+
+/// extract data and check labels of atoms in it, all the rules of `parse`
+fn extract_checked(node: &Cirru) -> Result<Edn, String> {
+  let data = extract_cirru_edn(node)?;
+  data.check_atom_labels()?;
+  Ok(data)
+}
+
+/// check if Cirru code is valid data for `parse`, without building the data.
+/// runs the same rules as parsing and returns the same first error, only allocating for the error
+/// and for labels of atoms. values of atoms sharing a label are compared by building the data,
+/// handy for editors checking trees on every change
+pub fn is_valid_edn(node: &Cirru) -> Result<(), String> {
+  let mut checker = CheckEdn::default();
+  extract_with(&mut checker, node, &mut vec![])?;
+  if checker.repeated_label {
+    extract_checked(node)?;
+  }
+  Ok(())
+}
+
+/// a leaf read by `read_leaf`
+enum EdnLeaf<'a> {
+  Nil,
+  Bool(bool),
+  Number(f64),
+  Symbol(&'a str),
+  Tag(&'a str),
+  Str(Cow<'a, str>),
+}
+
+fn read_leaf(s: &str) -> Result<EdnLeaf<'_>, String> {
+  match s {
+    "nil" => Ok(EdnLeaf::Nil),
+    "true" => Ok(EdnLeaf::Bool(true)),
+    "false" => Ok(EdnLeaf::Bool(false)),
+    "" => Err(String::from("empty string is invalid for edn")),
+    s1 => match s1.chars().next().unwrap() {
+      '\'' if s1.len() == 1 => Err(String::from("expected symbol name after `'`")),
+      '\'' => Ok(EdnLeaf::Symbol(&s1[1..])),
+      ':' => Ok(EdnLeaf::Tag(&s1[1..])),
+      '"' | '|' => Ok(EdnLeaf::Str(unescape_str(&s1[1..])?)),
+      _ => parse_number(s1.trim()).map(EdnLeaf::Number),
+    },
+  }
+}
+
+fn read_buffer_byte(x: &Cirru) -> Result<u8, String> {
+  match x {
+    Cirru::Leaf(y) => {
+      if y.len() == 2 {
+        match hex::decode(&(**y)) {
+          Ok(b) => {
+            if b.len() == 1 {
+              Ok(b[0])
+            } else {
+              Err(format!("hex for buffer might be too large, got: {:?}", b))
+            }
           }
+          Err(e) => Err(format!("expected length 2 hex string in buffer, got: {} {}", y, e)),
         }
-      },
-    },
+      } else {
+        Err(format!("expected length 2 hex string in buffer, got: {}", y))
+      }
+    }
+    _ => Err(format!("expected hex string in buffer, got: {}", x)),
+  }
+}
+
+/// values produced by the rules in `extract_with`, data for `parse` and nothing for `is_valid_edn`,
+/// so that both go through the same code
+trait EdnBuilder {
+  type Out;
+  type Set: Default;
+  type Map: Default;
+  type Fields;
+  fn leaf(&mut self, x: EdnLeaf) -> Self::Out;
+  fn quote(&mut self, code: &Cirru) -> Self::Out;
+  fn tuple(&mut self, tag: Self::Out, extra: Vec<Self::Out>) -> Self::Out;
+  fn list(&mut self, xs: Vec<Self::Out>) -> Self::Out;
+  fn set_insert(&mut self, set: &mut Self::Set, x: Self::Out);
+  fn set(&mut self, set: Self::Set) -> Self::Out;
+  fn map_insert(&mut self, map: &mut Self::Map, k: Self::Out, v: Self::Out);
+  fn map(&mut self, map: Self::Map) -> Self::Out;
+  /// text of a map key in error messages
+  fn show_key(&mut self, k: &Self::Out, node: &Cirru) -> String;
+  fn fields(&mut self, capacity: usize) -> Self::Fields;
+  fn field_push(&mut self, fields: &mut Self::Fields, name: &str, v: Self::Out);
+  fn record(&mut self, name: &str, fields: Self::Fields) -> Self::Out;
+  fn buffer(&mut self, bytes: impl Iterator<Item = Result<u8, String>>) -> Result<Self::Out, String>;
+  fn atom(&mut self, label: Option<&str>, v: Self::Out) -> Self::Out;
+  /// a comment skipped in a collection
+  fn comment(&mut self) {}
+}
+
+struct BuildEdn;
+
+impl EdnBuilder for BuildEdn {
+  type Out = Edn;
+  type Set = EdnSetView;
+  type Map = EdnMapView;
+  type Fields = Vec<(EdnTag, Edn)>;
+
+  fn leaf(&mut self, x: EdnLeaf) -> Edn {
+    match x {
+      EdnLeaf::Nil => Edn::Nil,
+      EdnLeaf::Bool(b) => Edn::Bool(b),
+      EdnLeaf::Number(n) => Edn::Number(n),
+      EdnLeaf::Symbol(s) => Edn::Symbol(s.into()),
+      EdnLeaf::Tag(s) => Edn::tag_raw(s),
+      EdnLeaf::Str(s) => Edn::Str(s.into()),
+    }
+  }
+  fn quote(&mut self, code: &Cirru) -> Edn {
+    Edn::Quote(code.to_owned())
+  }
+  fn tuple(&mut self, tag: Edn, extra: Vec<Edn>) -> Edn {
+    Edn::Tuple(EdnTupleView {
+      tag: Arc::new(tag),
+      extra,
+    })
+  }
+  fn list(&mut self, xs: Vec<Edn>) -> Edn {
+    Edn::List(EdnListView(xs))
+  }
+  fn set_insert(&mut self, set: &mut EdnSetView, x: Edn) {
+    set.insert(x);
+  }
+  fn set(&mut self, set: EdnSetView) -> Edn {
+    Edn::Set(set)
+  }
+  fn map_insert(&mut self, map: &mut EdnMapView, k: Edn, v: Edn) {
+    map.insert(k, v);
+  }
+  fn map(&mut self, map: EdnMapView) -> Edn {
+    Edn::Map(map)
+  }
+  fn show_key(&mut self, k: &Edn, _node: &Cirru) -> String {
+    k.to_string()
+  }
+  fn fields(&mut self, capacity: usize) -> Self::Fields {
+    Vec::with_capacity(capacity)
+  }
+  fn field_push(&mut self, fields: &mut Self::Fields, name: &str, v: Edn) {
+    fields.push((EdnTag::new_raw(name), v));
+  }
+  fn record(&mut self, name: &str, fields: Self::Fields) -> Edn {
+    Edn::Record(EdnRecordView {
+      tag: EdnTag::new_raw(name),
+      pairs: fields,
+    })
+  }
+  fn buffer(&mut self, bytes: impl Iterator<Item = Result<u8, String>>) -> Result<Edn, String> {
+    bytes.collect::<Result<Vec<u8>, String>>().map(Edn::Buffer)
+  }
+  fn atom(&mut self, label: Option<&str>, v: Edn) -> Edn {
+    match label {
+      Some(label) => Edn::atom_labeled(label, v),
+      None => Edn::atom(v),
+    }
+  }
+}
+
+/// builds nothing, collections of `()` never allocate. labels of atoms are remembered to find repeated ones
+#[derive(Default)]
+struct CheckEdn {
+  labels: HashSet<String>,
+  repeated_label: bool,
+}
+
+impl EdnBuilder for CheckEdn {
+  type Out = ();
+  type Set = ();
+  type Map = ();
+  type Fields = ();
+
+  fn leaf(&mut self, _x: EdnLeaf) {}
+  fn quote(&mut self, _code: &Cirru) {}
+  fn tuple(&mut self, _tag: (), _extra: Vec<()>) {}
+  fn list(&mut self, _xs: Vec<()>) {}
+  fn set_insert(&mut self, _set: &mut (), _x: ()) {}
+  fn set(&mut self, _set: ()) {}
+  fn map_insert(&mut self, _map: &mut (), _k: (), _v: ()) {}
+  fn map(&mut self, _map: ()) {}
+  fn show_key(&mut self, _k: &(), node: &Cirru) -> String {
+    // only on the error path, the key is already known to be valid
+    extract_cirru_edn(node).map(|k| k.to_string()).unwrap_or_default()
+  }
+  fn fields(&mut self, _capacity: usize) {}
+  fn field_push(&mut self, _fields: &mut (), _name: &str, _v: ()) {}
+  fn record(&mut self, _name: &str, _fields: ()) {}
+  fn buffer(&mut self, mut bytes: impl Iterator<Item = Result<u8, String>>) -> Result<(), String> {
+    bytes.try_for_each(|b| b.map(|_| ()))
+  }
+  fn atom(&mut self, label: Option<&str>, _v: ()) {
+    if let Some(label) = label {
+      if !self.labels.insert(label.to_owned()) {
+        self.repeated_label = true;
+      }
+    }
+  }
+}
+
+fn extract_with<'a, B: EdnBuilder>(
+  b: &mut B,
+  node: &'a Cirru,
+  path: &mut Vec<ParsePathSeg<'a>>,
+) -> Result<B::Out, String> {
+  // a span for each item at top level
+  #[cfg(feature = "tracing")]
+  let _span = (path.len() == 1).then(|| {
+    tracing::debug_span!(
+      "cirru_edn::extract_item",
+      path = %render_parse_path(path),
+      nodes = trace::cirru_node_count(node)
+    )
+    .entered()
+  });
+  match node {
+    Cirru::Leaf(s) => read_leaf(s).map(|x| b.leaf(x)),
     Cirru::List(xs) => {
       if xs.is_empty() {
         Err(String::from("empty expr is invalid for edn"))
@@ -55,124 +358,142 @@ fn extract_cirru_edn(node: &Cirru) -> Result<Edn, String> {
           Cirru::Leaf(s) => match &**s {
             "quote" => {
               if xs.len() == 2 {
-                Ok(Edn::Quote(xs[1].to_owned()))
+                Ok(b.quote(&xs[1]))
               } else {
-                Err(String::from("missing edn quote value"))
+                Err(format!(
+                  "expected 1 value in quote, got {} in `{}` at {}",
+                  xs.len() - 1,
+                  preview_node(node),
+                  render_parse_path(path)
+                ))
               }
             }
             "do" => {
-              let mut ret: Option<Edn> = None;
+              let mut ret: Option<(&Cirru, B::Out)> = None;
 
               for x in xs.iter().skip(1) {
                 if is_comment(x) {
+                  b.comment();
                   continue;
                 }
-                if ret.is_some() {
-                  return Err(String::from("multiple values in do"));
+                if let Some((prev, _)) = ret {
+                  return Err(format!(
+                    "multiple values in do, got `{}` and then `{}` at {}",
+                    preview_node(prev),
+                    preview_node(x),
+                    render_parse_path(path)
+                  ));
                 }
-                ret = Some(extract_cirru_edn(x)?);
+                ret = Some((x, extract_with(b, x, path)?));
               }
-              if ret.is_none() {
-                return Err(String::from("missing edn do value"));
+              match ret {
+                Some((_, v)) => Ok(v),
+                None => Err(format!("missing edn do value at {}", render_parse_path(path))),
               }
-              ret.ok_or_else(|| String::from("missing edn do value"))
             }
             "::" => {
-              let mut tag: Option<Edn> = None;
-              let mut extra: Vec<Edn> = vec![];
+              let mut tag: Option<B::Out> = None;
+              let mut extra: Vec<B::Out> = vec![];
               for x in xs.iter().skip(1) {
                 if is_comment(x) {
+                  b.comment();
                   continue;
                 }
+                path.push(ParsePathSeg::Index(extra.len() + usize::from(tag.is_some())));
+                let v = extract_with(b, x, path)?;
+                path.pop();
                 if tag.is_some() {
-                  extra.push(extract_cirru_edn(x)?);
-                  continue;
+                  extra.push(v);
                 } else {
-                  tag = Some(extract_cirru_edn(x)?);
+                  tag = Some(v);
                 }
               }
               if let Some(x0) = tag {
-                Ok(Edn::Tuple(EdnTupleView {
-                  tag: Arc::new(x0),
-                  extra,
-                }))
+                Ok(b.tuple(x0, extra))
               } else {
                 Err(String::from("missing edn :: fst value"))
               }
             }
             "[]" => {
-              let mut ys: Vec<Edn> = Vec::with_capacity(xs.len() - 1);
+              let mut ys: Vec<B::Out> = Vec::with_capacity(xs.len() - 1);
               for x in xs.iter().skip(1) {
                 if is_comment(x) {
+                  b.comment();
                   continue;
                 }
-                match extract_cirru_edn(x) {
-                  Ok(v) => ys.push(v),
-                  Err(v) => return Err(v),
-                }
+                path.push(ParsePathSeg::Index(ys.len()));
+                ys.push(extract_with(b, x, path)?);
+                path.pop();
               }
-              Ok(Edn::List(EdnListView(ys)))
+              Ok(b.list(ys))
             }
             "#{}" => {
-              #[allow(clippy::mutable_key_type)]
-              let mut ys: HashSet<Edn> = HashSet::new();
+              let mut ys = B::Set::default();
               for x in xs.iter().skip(1) {
                 if is_comment(x) {
+                  b.comment();
                   continue;
                 }
-                match extract_cirru_edn(x) {
-                  Ok(v) => {
-                    ys.insert(v);
-                  }
-                  Err(v) => return Err(v),
-                }
+                path.push(ParsePathSeg::Member(x));
+                let y = extract_with(b, x, path)?;
+                b.set_insert(&mut ys, y);
+                path.pop();
               }
-              Ok(Edn::Set(EdnSetView(ys)))
+              Ok(b.set(ys))
             }
             "{}" => {
-              #[allow(clippy::mutable_key_type)]
-              let mut zs: HashMap<Edn, Edn> = HashMap::new();
+              let mut zs = B::Map::default();
               for x in xs.iter().skip(1) {
                 if is_comment(x) {
+                  b.comment();
                   continue;
                 }
                 match x {
                   Cirru::Leaf(s) => return Err(format!("expected a pair, invalid map entry: {}", s)),
                   Cirru::List(ys) => {
                     if ys.len() == 2 {
-                      match (extract_cirru_edn(&ys[0]), extract_cirru_edn(&ys[1])) {
-                        (Ok(k), Ok(v)) => {
-                          zs.insert(k, v);
-                        }
+                      path.push(ParsePathSeg::Key(&ys[0]));
+                      let pair = (extract_with(b, &ys[0], path), extract_with(b, &ys[1], path));
+                      path.pop();
+                      match pair {
+                        (Ok(k), Ok(v)) => b.map_insert(&mut zs, k, v),
                         (Err(e), _) => return Err(format!("invalid map entry `{}` from `{}`", e, &ys[0])),
-                        (Ok(k), Err(e)) => return Err(format!("invalid map entry for `{}`, got {}", k, e)),
+                        (Ok(k), Err(e)) => {
+                          return Err(format!("invalid map entry for `{}`, got {}", b.show_key(&k, &ys[0]), e))
+                        }
                       }
                     }
                   }
                 }
               }
-              Ok(Edn::Map(EdnMapView(zs)))
+              Ok(b.map(zs))
             }
             "%{}" => {
-              if xs.len() >= 3 {
+              if xs.len() >= 2 {
                 let name = match &xs[1] {
-                  Cirru::Leaf(s) => EdnTag::new(s.strip_prefix(':').unwrap_or(s)),
+                  Cirru::Leaf(s) => record_tag_name(s)?,
                   Cirru::List(e) => return Err(format!("expected record name in string: {:?}", e)),
                 };
-                let mut entries: Vec<(EdnTag, Edn)> = Vec::with_capacity(xs.len() - 1);
+                let mut entries = b.fields(xs.len() - 1);
 
                 for x in xs.iter().skip(2) {
                   if is_comment(x) {
+                    b.comment();
                     continue;
                   }
                   match x {
                     Cirru::Leaf(s) => return Err(format!("expected record, invalid record entry: {}", s)),
                     Cirru::List(ys) => {
                       if ys.len() == 2 {
-                        match (&ys[0], extract_cirru_edn(&ys[1])) {
-                          (Cirru::Leaf(s), Ok(v)) => {
-                            entries.push((EdnTag::new(s.strip_prefix(':').unwrap_or(s)), v));
-                          }
+                        if let Cirru::Leaf(s) = &ys[0] {
+                          path.push(ParsePathSeg::Field(record_tag_name(s)?));
+                        }
+                        let value = extract_with(b, &ys[1], path);
+                        if let Cirru::Leaf(_) = &ys[0] {
+                          path.pop();
+                        }
+                        match (&ys[0], value) {
+                          (Cirru::Leaf(s), Ok(v)) => b.field_push(&mut entries, record_tag_name(s)?, v),
                           (Cirru::Leaf(s), Err(e)) => {
                             return Err(format!("invalid record value for `{}`, got: {}", s, e))
                           }
@@ -184,50 +505,38 @@ fn extract_cirru_edn(node: &Cirru) -> Result<Edn, String> {
                     }
                   }
                 }
-                if entries.is_empty() {
-                  return Err(String::from("empty record is invalid"));
-                }
-                Ok(Edn::Record(EdnRecordView {
-                  tag: name,
-                  pairs: entries,
-                }))
+                Ok(b.record(name, entries))
               } else {
                 Err(String::from("insufficient items for edn record"))
               }
             }
             "buf" => {
-              let mut ys: Vec<u8> = Vec::with_capacity(xs.len() - 1);
-              for x in xs.iter().skip(1) {
-                if is_comment(x) {
-                  continue;
-                }
-                match x {
-                  Cirru::Leaf(y) => {
-                    if y.len() == 2 {
-                      match hex::decode(&(**y)) {
-                        Ok(b) => {
-                          if b.len() == 1 {
-                            ys.push(b[0])
-                          } else {
-                            return Err(format!("hex for buffer might be too large, got: {:?}", b));
-                          }
-                        }
-                        Err(e) => return Err(format!("expected length 2 hex string in buffer, got: {} {}", y, e)),
-                      }
-                    } else {
-                      return Err(format!("expected length 2 hex string in buffer, got: {}", y));
-                    }
-                  }
-                  _ => return Err(format!("expected hex string in buffer, got: {}", x)),
-                }
-              }
-              Ok(Edn::Buffer(ys))
+              xs.iter().skip(1).filter(|x| is_comment(x)).for_each(|_| b.comment());
+              b.buffer(xs.iter().skip(1).filter(|x| !is_comment(x)).map(read_buffer_byte))
             }
             "atom" => {
               if xs.len() == 2 {
-                Ok(Edn::Atom(Box::new(extract_cirru_edn(&xs[1])?)))
+                let v = extract_with(b, &xs[1], path)?;
+                Ok(b.atom(None, v))
+              } else if xs.len() == 3 {
+                match &xs[1] {
+                  Cirru::Leaf(s) if s.len() > 1 && s.starts_with('\'') => {
+                    let v = extract_with(b, &xs[2], path)?;
+                    Ok(b.atom(Some(&s[1..]), v))
+                  }
+                  a => Err(format!(
+                    "expected symbol for atom label, got `{}` at {}",
+                    preview_node(a),
+                    render_parse_path(path)
+                  )),
+                }
               } else {
-                Err(String::from("missing edn atom value"))
+                Err(format!(
+                  "expected atom value, got {} items in `{}` at {}",
+                  xs.len() - 1,
+                  preview_node(node),
+                  render_parse_path(path)
+                ))
               }
             }
             a => Err(format!("invalid operator for edn: {}", a)),
@@ -239,6 +548,52 @@ fn extract_cirru_edn(node: &Cirru) -> Result<Edn, String> {
   }
 }
 
+/// parse a number leaf, following Rust's `f64` rules with `_` allowed between digits like `1_000`:
+///
+/// - integers and decimals with an optional sign, `12`, `-3.5`, `+0.25`, `.5`, `5.`
+/// - exponents in `e` or `E` with an optional sign, `1e21`, `2.5E-7`
+/// - `inf`, `-inf` and `NaN`, case-insensitive, as written by `format` for those values
+///
+/// `-0` is read as `0`, so both zeros are the same number in data
+/// `format` writes numbers without exponents, like `1000000000000000000000` for `1e21`,
+/// and every finite value is read back exactly
+pub fn parse_number(s: &str) -> Result<f64, String> {
+  if !s.contains('_') {
+    return s
+      .parse::<f64>()
+      .map(normalize_zero)
+      .map_err(|_| format!("unknown token for edn value: {:?}", s));
+  }
+  let digits = s.replace('_', "");
+  if digits.parse::<f64>().is_err() {
+    return Err(format!("unknown token for edn value: {:?}", s));
+  }
+  let bytes = s.as_bytes();
+  for (idx, b) in bytes.iter().enumerate() {
+    if *b == b'_' {
+      let prev_is_digit = idx > 0 && bytes[idx - 1].is_ascii_digit();
+      let next_is_digit = bytes.get(idx + 1).is_some_and(|c| c.is_ascii_digit());
+      if !prev_is_digit || !next_is_digit {
+        return Err(format!("invalid number {:?}, `_` is only allowed between digits", s));
+      }
+    }
+  }
+  digits
+    .parse::<f64>()
+    .map(normalize_zero)
+    .map_err(|_| format!("unknown token for edn value: {:?}", s))
+}
+
+/// record names and fields are always tags, so the leading `:` is optional when parsing,
+/// `%{} :A (:b 1)` and `%{} A (b 1)` are the same record. `format` always writes the `:`.
+/// leaves of strings or symbols are rejected rather than turned into tags
+fn record_tag_name(s: &str) -> Result<&str, String> {
+  match s.chars().next() {
+    Some('|' | '"' | '\'') | None => Err(format!("expected tag in record, got: {}", s)),
+    _ => Ok(s.strip_prefix(':').unwrap_or(s)),
+  }
+}
+
 fn is_comment(node: &Cirru) -> bool {
   match node {
     Cirru::Leaf(_) => false,
@@ -246,48 +601,72 @@ fn is_comment(node: &Cirru) -> bool {
   }
 }
 
+/// order of set items and map keys in `format`. `Sorted` compares by `Ord` of Edn, then by written nodes
+/// for values `Ord` doesn't tell apart, so the output never depends on hashing order
+fn cmp_written_keys(order: MapKeyOrder, a: &Edn, a_node: &Cirru, b: &Edn, b_node: &Cirru) -> std::cmp::Ordering {
+  match order {
+    MapKeyOrder::Sorted => a.cmp(b).then_with(|| a_node.cmp(b_node)),
+    MapKeyOrder::Insertion => Equal,
+    MapKeyOrder::GroupedByKind { tags_first } => key_kind_rank(a, tags_first).cmp(&key_kind_rank(b, tags_first)),
+  }
+}
+
+fn key_kind_rank(x: &Edn, tags_first: bool) -> u8 {
+  match x {
+    Edn::Tag(_) if tags_first => 0,
+    Edn::Str(_) if !tags_first => 0,
+    Edn::Tag(_) | Edn::Str(_) => 1,
+    _ => 2,
+  }
+}
+
 fn assemble_cirru_node(data: &Edn) -> Cirru {
+  assemble_cirru_node_by(data, MapKeyOrder::Sorted)
+}
+
+fn assemble_cirru_node_by(data: &Edn, order: MapKeyOrder) -> Cirru {
   match data {
     Edn::Nil => "nil".into(),
     Edn::Bool(v) => v.to_string().as_str().into(),
-    Edn::Number(n) => n.to_string().as_str().into(),
+    Edn::Number(n) => normalize_zero(*n).to_string().as_str().into(),
     Edn::Symbol(s) => format!("'{}", s).as_str().into(),
     Edn::Tag(s) => format!(":{}", s).as_str().into(),
-    Edn::Str(s) => format!("|{}", s).as_str().into(),
+    Edn::Str(s) => format!("|{}", escape_str(s)).as_str().into(),
     Edn::Quote(v) => Cirru::List(vec!["quote".into(), (*v).to_owned()]),
     Edn::List(xs) => {
       let mut ys: Vec<Cirru> = Vec::with_capacity(xs.len() + 1);
       ys.push("[]".into());
       for x in xs {
-        ys.push(assemble_cirru_node(x));
+        ys.push(assemble_cirru_node_by(x, order));
       }
       Cirru::List(ys)
     }
     Edn::Set(xs) => {
       let mut ys: Vec<Cirru> = Vec::with_capacity(xs.len() + 1);
       ys.push("#{}".into());
-      let mut items = xs.0.iter().collect::<Vec<_>>();
-      items.sort();
-      for x in items {
-        ys.push(assemble_cirru_node(x));
+      let mut items = xs
+        .iter()
+        .map(|x| (x, assemble_cirru_node_by(x, order)))
+        .collect::<Vec<_>>();
+      items.sort_by(|(a, a_node), (b, b_node)| cmp_written_keys(order, a, a_node, b, b_node));
+      for (_, node) in items {
+        ys.push(node);
       }
       Cirru::List(ys)
     }
     Edn::Map(xs) => {
       let mut ys: Vec<Cirru> = Vec::with_capacity(xs.len() + 1);
       ys.push("{}".into());
-      let mut items = Vec::from_iter(xs.0.iter());
-      items.sort_by(|(a1, a2): &(&Edn, &Edn), (b1, b2): &(&Edn, &Edn)| {
-        match (a1.is_literal(), b1.is_literal(), a2.is_literal(), b2.is_literal()) {
-          (true, true, true, false) => Less,
-          (true, true, false, true) => Greater,
-          (true, false, ..) => Less,
-          (false, true, ..) => Greater,
-          _ => a1.cmp(b1),
-        }
+      let mut items = Vec::from_iter(xs.iter().map(|(k, v)| (k, v, assemble_cirru_node_by(k, order))));
+      items.sort_by(|(a1, a2, a_node), (b1, b2, b_node)| {
+        let rank = match order {
+          MapKeyOrder::Sorted => map_entry_rank(a1, a2).cmp(&map_entry_rank(b1, b2)),
+          _ => Equal,
+        };
+        rank.then_with(|| cmp_written_keys(order, a1, a_node, b1, b_node))
       });
-      for (k, v) in items {
-        ys.push(Cirru::List(vec![assemble_cirru_node(k), assemble_cirru_node(v)]))
+      for (_, v, k_node) in items {
+        ys.push(Cirru::List(vec![k_node, assemble_cirru_node_by(v, order)]))
       }
       Cirru::List(ys)
     }
@@ -308,16 +687,16 @@ fn assemble_cirru_node(data: &Edn) -> Cirru {
         let v = &entry.1;
         ys.push(Cirru::List(vec![
           format!(":{}", entry.0).as_str().into(),
-          assemble_cirru_node(v),
+          assemble_cirru_node_by(v, order),
         ]));
       }
 
       Cirru::List(ys)
     }
     Edn::Tuple(EdnTupleView { tag, extra }) => {
-      let mut ys: Vec<Cirru> = vec!["::".into(), assemble_cirru_node(tag)];
+      let mut ys: Vec<Cirru> = vec!["::".into(), assemble_cirru_node_by(tag, order)];
       for item in extra {
-        ys.push(assemble_cirru_node(item))
+        ys.push(assemble_cirru_node_by(item, order))
       }
       Cirru::List(ys)
     }
@@ -330,18 +709,377 @@ fn assemble_cirru_node(data: &Edn) -> Cirru {
       Cirru::List(ys)
     }
     Edn::AnyRef(..) => unreachable!("AnyRef is not serializable"),
-    Edn::Atom(v) => {
-      let ys = vec!["atom".into(), assemble_cirru_node(v)];
+    Edn::Atom(EdnAtomView { label, value }) => {
+      let mut ys = vec!["atom".into()];
+      if let Some(label) = label {
+        ys.push(format!("'{}", label).as_str().into());
+      }
+      ys.push(assemble_cirru_node_by(value, order));
       Cirru::List(ys)
     }
   }
 }
 
-/// generate string from Edn
+/// groups of map entries in canonical order, entries in a group are sorted by keys
+fn map_entry_rank(k: &Edn, v: &Edn) -> u8 {
+  match (k.is_literal(), v.is_literal()) {
+    (true, true) => 0,
+    (true, false) => 1,
+    (false, _) => 2,
+  }
+}
+
+/// generate string from Edn.
+///
+/// output is canonical, formatting parsed output again gives the same text:
+///
+/// - map entries with literal keys go first, with literal values before collections, then entries with collection keys.
+///   entries in each group are sorted by keys,
+/// - record fields with literal values go first, otherwise fields keep their order,
+/// - set items are sorted.
+///
+/// values are ordered by the `Ord` of `Edn`, which compares variants first.
 pub fn format(data: &Edn, use_inline: bool) -> Result<String, String> {
-  let options = CirruWriterOptions { use_inline };
-  match assemble_cirru_node(data) {
-    Cirru::Leaf(s) => cirru_parser::format(&[vec!["do", &*s].into()], options),
-    Cirru::List(xs) => cirru_parser::format(&[(Cirru::List(xs))], options),
+  format_with_options(data, use_inline.into())
+}
+
+/// result of `verify_roundtrip`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundtripReport {
+  /// canonical text from `format`
+  pub formatted: String,
+  /// data parsed from the canonical text equals data parsed from the input
+  pub values_equal: bool,
+  /// the input differs from the canonical text, leading and trailing whitespaces are ignored
+  pub text_changed: bool,
+  /// line and column in the input of the first difference, both starting from 1
+  pub first_difference: Option<(usize, usize)>,
+}
+
+impl RoundtripReport {
+  /// values survive formatting and the input is already canonical
+  pub fn is_canonical(&self) -> bool {
+    self.values_equal && !self.text_changed
+  }
+}
+
+/// parse, format with default options and parse again, for tools checking data files are canonical
+pub fn verify_roundtrip(s: &str) -> Result<RoundtripReport, String> {
+  let data = parse(s)?;
+  let formatted = format(&data, false)?;
+  let values_equal = parse(&formatted)? == data;
+
+  let input = s.trim();
+  let offset = s.len() - s.trim_start().len();
+  let first_difference = match input
+    .char_indices()
+    .zip(formatted.trim().chars())
+    .find(|((_, a), b)| a != b)
+  {
+    Some(((idx, _), _)) => Some(offset + idx),
+    None if input.len() != formatted.trim().len() => Some(offset + input.len().min(formatted.trim().len())),
+    None => None,
+  }
+  .map(|pos| {
+    let before = &s[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+  });
+
+  Ok(RoundtripReport {
+    text_changed: first_difference.is_some(),
+    formatted,
+    values_equal,
+    first_difference,
+  })
+}
+
+/// options for `format_with_options`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EdnFormatOptions {
+  /// more compact format, forwarded to `CirruWriterOptions`
+  pub use_inline: bool,
+  /// wrap long runs of leaves, like items of a large list, into `,` lines of about this width.
+  /// only takes effect without `use_inline`, indentations are not counted
+  pub max_width: Option<usize>,
+  /// write each subtree in one line when its one-liner fits in this width, otherwise break it into lines.
+  /// overrides `use_inline` and `max_width`, runs of leaves are wrapped in `,` lines of this width too
+  pub inline_max_width: Option<usize>,
+  /// with `inline_max_width`, also break subtrees having more leaves than this
+  pub inline_max_items: Option<usize>,
+  /// order of map entries and set items, output is only canonical with `MapKeyOrder::Sorted`
+  pub map_key_order: MapKeyOrder,
+  /// with `rayon` feature, top-level lists of at least this many items are formatted in parallel,
+  /// `None` for 10000. output is the same either way
+  pub parallel_min_items: Option<usize>,
+}
+
+/// order of map entries and set items in `format_with_options`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MapKeyOrder {
+  /// sorted as described in `format`
+  #[default]
+  Sorted,
+  /// order of iteration, which is arbitrary for `HashMap` and `HashSet`, and sorted with `btree-map` feature.
+  /// there's no insertion-ordered collection in this crate, don't rely on it for stable output
+  Insertion,
+  /// only grouped by kinds of keys, tags and strings in the order of `tags_first`, then other keys.
+  /// entries in each group keep the order of iteration
+  GroupedByKind { tags_first: bool },
+}
+
+impl From<bool> for EdnFormatOptions {
+  fn from(use_inline: bool) -> Self {
+    EdnFormatOptions {
+      use_inline,
+      ..Default::default()
+    }
+  }
+}
+
+/// generate string from Edn with options
+pub fn format_with_options(data: &Edn, options: EdnFormatOptions) -> Result<String, String> {
+  let _span = trace_span!(
+    "cirru_edn::format",
+    values = data.value_count(),
+    use_inline = options.use_inline
+  );
+  let result = write_edn(data, options);
+  trace_event!(
+    bytes = result.as_ref().map_or(0, String::len),
+    ok = result.is_ok(),
+    "formatted"
+  );
+  result
+}
+
+fn write_edn(data: &Edn, options: EdnFormatOptions) -> Result<String, String> {
+  let writer_options = CirruWriterOptions {
+    use_inline: options.use_inline,
+  };
+  data.check_writable()?;
+  #[cfg(feature = "rayon")]
+  if let (Edn::List(xs), None) = (data, options.inline_max_width) {
+    if xs.len() >= parallel::parallel_min_items(&options) {
+      if let Some(result) = parallel::format_list(xs, options) {
+        return result;
+      }
+    }
+  }
+  let mut node = assemble_cirru_node_by(data, options.map_key_order);
+  if let (Some(width), Cirru::List(xs)) = (options.inline_max_width, &node) {
+    let limits = InlineLimits {
+      width,
+      items: options.inline_max_items.unwrap_or(usize::MAX),
+    };
+    let mut out = String::from("\n");
+    write_mixed(xs, limits, 0, 0, &mut out)?;
+    out.push('\n');
+    return Ok(out);
+  }
+  if let (Some(width), false) = (options.max_width, options.use_inline) {
+    pack_leaves(&mut node, width);
+  }
+  fold_tuple_tails(&mut node);
+  match node {
+    Cirru::Leaf(s) => cirru_parser::format(&[vec!["do", &*s].into()], writer_options),
+    Cirru::List(xs) => cirru_parser::format(&[(Cirru::List(xs))], writer_options),
+  }
+}
+
+/// approximate width of a leaf in output
+fn leaf_width(s: &str) -> usize {
+  if s
+    .chars()
+    .all(|c| is_simple_char(c) || matches!(c, '|' | ':' | '\'' | '{' | '}' | '[' | ']' | '#' | '%'))
+  {
+    s.chars().count()
+  } else {
+    s.chars().count() + 2
+  }
+}
+
+/// thresholds of `inline_max_width` and `inline_max_items`
+#[derive(Debug, Clone, Copy)]
+struct InlineLimits {
+  width: usize,
+  items: usize,
+}
+
+impl InlineLimits {
+  /// width and count of leaves of the one-liner of a node, `None` when it's beyond the limits
+  fn measure(&self, node: &Cirru) -> Option<(usize, usize)> {
+    let (mut width, mut items) = (0, 0);
+    let mut stack = vec![node];
+    while let Some(x) = stack.pop() {
+      match x {
+        Cirru::Leaf(s) => {
+          width += leaf_width(s);
+          items += 1;
+        }
+        Cirru::List(ys) => {
+          // brackets and spaces between items
+          width += 2 + ys.len().saturating_sub(1);
+          stack.extend(ys);
+        }
+      }
+      if width > self.width || items > self.items {
+        return None;
+      }
+    }
+    Some((width, items))
+  }
+}
+
+fn one_liner(node: &Cirru) -> Result<String, String> {
+  Cirru::List(vec![node.to_owned()]).format_one_liner()
+}
+
+/// write items of a list like an expression of a line, items that fit go into the current line,
+/// a list at the tail after a leaf continues the line with `$`, others are written in following lines
+fn write_mixed(
+  xs: &[Cirru],
+  limits: InlineLimits,
+  level: usize,
+  mut line_width: usize,
+  out: &mut String,
+) -> Result<(), String> {
+  let newline = |out: &mut String, level: usize| {
+    out.push('\n');
+    for _ in 0..level {
+      out.push_str("  ");
+    }
+  };
+  let mut line_items = 0;
+  let mut idx = 0;
+  while idx < xs.len() {
+    let x = &xs[idx];
+    if idx == 0 {
+      // the head is always in the line, even if too long
+      let piece = one_liner(x)?;
+      line_width += piece.chars().count();
+      line_items += limits.measure(x).map_or(0, |m| m.1);
+      out.push_str(&piece);
+    } else if let (Cirru::List(ys), Cirru::Leaf(_), true) = (x, &xs[idx - 1], idx == xs.len() - 1) {
+      if ys.is_empty() {
+        // `$` alone stands for an empty list
+        out.push_str(" $");
+        return Ok(());
+      }
+      out.push_str(" $ ");
+      return write_mixed(ys, limits, level, line_width + 3, out);
+    } else {
+      match limits.measure(x) {
+        Some((width, items)) if line_width + 1 + width <= limits.width && line_items + items <= limits.items => {
+          out.push(' ');
+          out.push_str(&one_liner(x)?);
+          line_width += 1 + width;
+          line_items += items;
+        }
+        _ => break,
+      }
+    }
+    idx += 1;
+  }
+
+  while idx < xs.len() {
+    match &xs[idx] {
+      Cirru::Leaf(_) => {
+        // leaves are spliced back into the list from `,` lines
+        newline(out, level + 1);
+        out.push(',');
+        let (mut width, mut items) = (1, 0);
+        while let Some(x @ Cirru::Leaf(s)) = xs.get(idx) {
+          let w = leaf_width(s);
+          if items > 0 && (width + 1 + w > limits.width || items + 1 > limits.items) {
+            break;
+          }
+          out.push(' ');
+          out.push_str(&one_liner(x)?);
+          width += 1 + w;
+          items += 1;
+          idx += 1;
+        }
+      }
+      Cirru::List(ys) => {
+        newline(out, level + 1);
+        if ys.is_empty() {
+          out.push_str(", ()");
+        } else {
+          write_mixed(ys, limits, level + 1, 0, out)?;
+        }
+        idx += 1;
+      }
+    }
+  }
+  Ok(())
+}
+
+/// the last collection of a tuple after a leaf is always written after `$` in the same line.
+/// the writer skips `$` inside another `$` line, so the `$` leaf is added explicitly
+fn fold_tuple_tails(node: &mut Cirru) {
+  let Cirru::List(xs) = node else {
+    return;
+  };
+  for x in xs.iter_mut() {
+    fold_tuple_tails(x);
+  }
+  if !matches!(xs.first(), Some(Cirru::Leaf(s)) if &**s == "::") || xs.len() < 3 {
+    return;
+  }
+  if let [.., Cirru::Leaf(_), Cirru::List(ys)] = &xs[..] {
+    if ys.is_empty() {
+      return;
+    }
+    let Some(Cirru::List(ys)) = xs.pop() else {
+      unreachable!("matched a list")
+    };
+    xs.push("$".into());
+    xs.extend(ys);
+  }
+}
+
+/// leaves after the list head are moved into `,` lists when they are too wide for a line,
+/// `,` lists are spliced back into the parent list by Cirru parser, so data is unchanged
+fn pack_leaves(node: &mut Cirru, max_width: usize) {
+  let Cirru::List(xs) = node else {
+    return;
+  };
+  for x in xs.iter_mut() {
+    pack_leaves(x, max_width);
+  }
+  let run = xs.iter().take_while(|x| matches!(x, Cirru::Leaf(_))).count();
+  if run < 3 {
+    return;
+  }
+  let widths = xs[..run]
+    .iter()
+    .map(|x| match x {
+      Cirru::Leaf(s) => leaf_width(s),
+      Cirru::List(_) => unreachable!("counted leaves"),
+    })
+    .collect::<Vec<_>>();
+  if widths.iter().sum::<usize>() + run - 1 <= max_width {
+    return;
+  }
+
+  let rest = xs.split_off(run);
+  let leaves = xs.split_off(1);
+  // the first chunk is inlined after head in `(, ...)`, following ones are written in `, ...` lines
+  let mut budget = max_width.saturating_sub(widths[0] + 5);
+  let mut chunk: Vec<Cirru> = vec![",".into()];
+  let mut chunk_width = 0;
+  for (leaf, width) in leaves.into_iter().zip(widths.into_iter().skip(1)) {
+    if chunk.len() > 1 && chunk_width + 1 + width > budget {
+      xs.push(Cirru::List(chunk));
+      chunk = vec![",".into()];
+      chunk_width = 0;
+      budget = max_width.saturating_sub(2);
+    }
+    chunk_width += if chunk.len() > 1 { 1 + width } else { width };
+    chunk.push(leaf);
   }
+  xs.push(Cirru::List(chunk));
+  xs.extend(rest);
 }
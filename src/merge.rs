@@ -0,0 +1,129 @@
+//! layered documents merged into one value, for `merge_parse`
+
+use std::{collections::HashMap, fmt};
+
+use crate::{parse, Edn, EdnRecordView, EdnTag};
+
+/// document that a merged value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MergeSource {
+  Base,
+  /// index in `overlays`
+  Overlay(usize),
+}
+
+impl fmt::Display for MergeSource {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      MergeSource::Base => f.write_str("base"),
+      MergeSource::Overlay(idx) => write!(f, "overlay {}", idx),
+    }
+  }
+}
+
+/// sources of values in data from `merge_parse`, in a tree of the same shape as maps and records in data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+  source: MergeSource,
+  /// by keys of a map or by fields of a record, `None` for other values
+  children: Option<HashMap<Edn, Provenance>>,
+}
+
+impl Provenance {
+  fn new(data: &Edn, source: MergeSource) -> Self {
+    let children = match data {
+      Edn::Map(xs) => Some(xs.0.iter().map(|(k, v)| (k.to_owned(), Self::new(v, source))).collect()),
+      Edn::Record(EdnRecordView { pairs, .. }) => Some(
+        pairs
+          .iter()
+          .map(|(k, v)| (Edn::Tag(k.to_owned()), Self::new(v, source)))
+          .collect(),
+      ),
+      _ => None,
+    };
+    Provenance { source, children }
+  }
+
+  /// the document that last set the value at `path`, or last set anything inside it for maps and records.
+  /// segments are names of map keys, as strings looked up before tags, or names of record fields.
+  /// segments after a value of other types, like list indexes, get the source of that value.
+  /// `None` when the path is missing in a map or a record
+  pub fn source_of(&self, path: &[&str]) -> Option<MergeSource> {
+    let mut node = self;
+    for seg in path {
+      let Some(children) = &node.children else {
+        break;
+      };
+      node = children
+        .get(&Edn::str(*seg))
+        .or_else(|| children.get(&Edn::Tag(EdnTag::new_raw(*seg))))?;
+    }
+    Some(node.source)
+  }
+
+  /// source of the whole value, the last document merged into it
+  pub fn source(&self) -> MergeSource {
+    self.source
+  }
+}
+
+/// parse a base document and merge overlays into it in order, returning the sources of values as well.
+/// maps are merged by keys and records of the same name by fields, other values are replaced,
+/// so lists are not concatenated and `nil` does not remove a key
+///
+/// ```
+/// use cirru_edn::MergeSource;
+///
+/// let (data, provenance) = cirru_edn::merge_parse("{} (:port 80) (:host |a)", &["{} (:port 8080)"])?;
+/// assert_eq!(data, cirru_edn::parse("{} (:port 8080) (:host |a)")?);
+/// assert_eq!(provenance.source_of(&["port"]), Some(MergeSource::Overlay(0)));
+/// assert_eq!(provenance.source_of(&["host"]), Some(MergeSource::Base));
+/// # Ok::<(), String>(())
+/// ```
+pub fn merge_parse(base: &str, overlays: &[&str]) -> Result<(Edn, Provenance), String> {
+  let mut data = parse(base).map_err(|e| format!("failed to parse base: {}", e))?;
+  let mut provenance = Provenance::new(&data, MergeSource::Base);
+  for (idx, overlay) in overlays.iter().enumerate() {
+    let layer = parse(overlay).map_err(|e| format!("failed to parse overlay {}: {}", idx, e))?;
+    merge_value(&mut data, &mut provenance, layer, MergeSource::Overlay(idx));
+  }
+  Ok((data, provenance))
+}
+
+// hashing does not touch interior mutability of any-ref
+#[allow(clippy::mutable_key_type)]
+fn merge_value(base: &mut Edn, provenance: &mut Provenance, layer: Edn, source: MergeSource) {
+  match (base, layer) {
+    (Edn::Map(xs), Edn::Map(ys)) => {
+      let children = provenance.children.get_or_insert_with(HashMap::new);
+      for (k, v) in ys {
+        match (xs.0.get_mut(&k), children.get_mut(&k)) {
+          (Some(x), Some(p)) => merge_value(x, p, v, source),
+          _ => {
+            children.insert(k.to_owned(), Provenance::new(&v, source));
+            xs.0.insert(k, v);
+          }
+        }
+      }
+      provenance.source = source;
+    }
+    (Edn::Record(a), Edn::Record(b)) if a.tag == b.tag => {
+      let children = provenance.children.get_or_insert_with(HashMap::new);
+      for (field, v) in b.pairs {
+        let key = Edn::Tag(field.to_owned());
+        match (a.pairs.iter_mut().find(|(f, _)| *f == field), children.get_mut(&key)) {
+          (Some((_, x)), Some(p)) => merge_value(x, p, v, source),
+          _ => {
+            children.insert(key, Provenance::new(&v, source));
+            a.pairs.push((field, v));
+          }
+        }
+      }
+      provenance.source = source;
+    }
+    (base, layer) => {
+      *provenance = Provenance::new(&layer, source);
+      *base = layer;
+    }
+  }
+}
@@ -0,0 +1,80 @@
+//! formatting large top-level lists in parallel, enabled with `rayon` feature.
+//!
+//! items are assembled and written on rayon threads, then concatenated in order.
+//! output is the same text as the sequential path, which is only taken over when every item
+//! is written on its own lines by Cirru writer, i.e. a collection holding other collections,
+//! and when there are at least 2 items. for other lists `format` falls back to the sequential path.
+
+use cirru_parser::{Cirru, CirruWriterOptions};
+use rayon::prelude::*;
+
+use crate::{assemble_cirru_node_by, fold_tuple_tails, pack_leaves, EdnFormatOptions, EdnListView};
+
+/// default of `EdnFormatOptions::parallel_min_items`
+const PARALLEL_MIN_ITEMS: usize = 10_000;
+
+pub(crate) fn parallel_min_items(options: &EdnFormatOptions) -> usize {
+  options.parallel_min_items.unwrap_or(PARALLEL_MIN_ITEMS)
+}
+
+/// text of `[] ...` written by items in parallel, `None` when an item is not a collection of collections
+pub(crate) fn format_list(xs: &EdnListView, options: EdnFormatOptions) -> Option<Result<String, String>> {
+  // a single item right after `[]` is the tail of the expression, which Cirru writer folds with `$`
+  if xs.len() < 2 {
+    return None;
+  }
+  let writer_options = CirruWriterOptions {
+    use_inline: options.use_inline,
+  };
+  let pieces = xs
+    .0
+    .par_iter()
+    .map(|x| {
+      let mut node = assemble_cirru_node_by(x, options.map_key_order);
+      if let (Some(width), false) = (options.max_width, options.use_inline) {
+        pack_leaves(&mut node, width);
+      }
+      fold_tuple_tails(&mut node);
+      if !is_written_in_lines(&node) {
+        return None;
+      }
+      Some(write_item(node, writer_options))
+    })
+    .collect::<Option<Result<Vec<String>, String>>>()?;
+  let pieces = match pieces {
+    Ok(pieces) => pieces,
+    Err(e) => return Some(Err(e)),
+  };
+
+  let mut out = String::with_capacity(pieces.iter().map(String::len).sum::<usize>() + 5);
+  out.push_str("\n[]");
+  for piece in pieces {
+    out.push_str(&piece);
+  }
+  out.push('\n');
+  Some(Ok(out))
+}
+
+/// a list with a leaf head and some lists in it. Cirru writer starts such an item in a new line
+/// whatever items are before it, and writes it the same way as a top-level expression one level deeper
+fn is_written_in_lines(node: &Cirru) -> bool {
+  match node {
+    Cirru::List(ys) => matches!(ys.first(), Some(Cirru::Leaf(_))) && ys.iter().any(|y| matches!(y, Cirru::List(_))),
+    Cirru::Leaf(_) => false,
+  }
+}
+
+fn write_item(node: Cirru, writer_options: CirruWriterOptions) -> Result<String, String> {
+  let text = cirru_parser::format(&[node], writer_options)?;
+  // leaves are escaped, so all newlines are from indentation
+  let content = text
+    .strip_prefix('\n')
+    .and_then(|s| s.strip_suffix('\n'))
+    .ok_or_else(|| format!("unexpected text from writer: {:?}", text))?
+    .replace('\n', "\n  ");
+  if content.starts_with('\n') {
+    Ok(content)
+  } else {
+    Ok(format!("\n  {}", content))
+  }
+}
@@ -0,0 +1,96 @@
+//! reading a part of a document by a path, for `extract_path`
+
+use cirru_parser::Cirru;
+
+use crate::edn::ERROR_PREVIEW_LEN;
+use crate::{data_expr, extract_checked, extract_cirru_edn, is_comment, read_leaf, record_tag_name, Edn, EdnLeaf};
+
+/// parse Cirru code and extract only the value at `path`, siblings on the way are not converted to data.
+/// segments are names of map keys, as tags or strings with strings looked up first, names of record fields,
+/// or indexes of list items like `"2"`. `do` wrappers are looked through.
+/// errors on missing values and on values of other types on the way, atoms in the value are checked like in `parse`,
+/// while errors in skipped siblings are not reported, use `parse` or `is_valid_edn` to check the whole document
+pub fn extract_path(s: &str, path: &[&str]) -> Result<Edn, String> {
+  let xs = cirru_parser::parse(s)?;
+  let mut node = data_expr(&xs)?;
+  let mut visited = String::new();
+  for seg in path {
+    node = unwrap_do(node)?;
+    let child = match node {
+      Cirru::List(ys) => match ys.first() {
+        Some(Cirru::Leaf(op)) if &**op == "{}" => find_map_value(&ys[1..], seg),
+        Some(Cirru::Leaf(op)) if &**op == "%{}" => find_record_value(&ys[2..], seg)?,
+        Some(Cirru::Leaf(op)) if &**op == "[]" => match seg.parse::<usize>() {
+          Ok(idx) => ys.iter().skip(1).filter(|y| !is_comment(y)).nth(idx),
+          Err(_) => return Err(wrong_type(node, seg, &visited)),
+        },
+        _ => return Err(wrong_type(node, seg, &visited)),
+      },
+      Cirru::Leaf(_) => return Err(wrong_type(node, seg, &visited)),
+    };
+    visited.push('.');
+    visited.push_str(seg);
+    node = child.ok_or_else(|| format!("missing value at `{}`", visited))?;
+  }
+  extract_checked(node)
+}
+
+/// the value in `do`, same as parsing when there are several
+fn unwrap_do(node: &Cirru) -> Result<&Cirru, String> {
+  match node {
+    Cirru::List(ys) if ys.first() == Some(&Cirru::Leaf("do".into())) => {
+      let mut values = ys.iter().skip(1).filter(|y| !is_comment(y));
+      match (values.next(), values.next()) {
+        (Some(y), None) => unwrap_do(y),
+        // parsing reports the error
+        _ => extract_cirru_edn(node).map(|_| node),
+      }
+    }
+    _ => Ok(node),
+  }
+}
+
+/// value of the last entry keyed by string `seg`, or else by tag `seg`, as later entries replace earlier ones
+fn find_map_value<'a>(entries: &'a [Cirru], seg: &str) -> Option<&'a Cirru> {
+  let mut by_str = None;
+  let mut by_tag = None;
+  for entry in entries {
+    if let Cirru::List(pair) = entry {
+      if let [Cirru::Leaf(k), v] = pair.as_slice() {
+        match read_leaf(k) {
+          Ok(EdnLeaf::Str(name)) if name == seg => by_str = Some(v),
+          Ok(EdnLeaf::Tag(name)) if name == seg => by_tag = Some(v),
+          _ => {}
+        }
+      }
+    }
+  }
+  by_str.or(by_tag)
+}
+
+fn find_record_value<'a>(entries: &'a [Cirru], seg: &str) -> Result<Option<&'a Cirru>, String> {
+  for entry in entries {
+    if let Cirru::List(pair) = entry {
+      if let [Cirru::Leaf(k), v] = pair.as_slice() {
+        if record_tag_name(k)? == seg {
+          return Ok(Some(v));
+        }
+      }
+    }
+  }
+  Ok(None)
+}
+
+/// converts the node only for the message, errors in it are reported instead
+fn wrong_type(node: &Cirru, seg: &str, visited: &str) -> String {
+  match extract_cirru_edn(node) {
+    Ok(data) => format!(
+      "cannot select `{}` from {} at `{}`: {}",
+      seg,
+      data.type_name(),
+      if visited.is_empty() { "." } else { visited },
+      data.preview(ERROR_PREVIEW_LEN)
+    ),
+    Err(e) => e,
+  }
+}
@@ -0,0 +1,119 @@
+//! counting nodes of data while parsing, for `parse_with_stats`
+
+use cirru_parser::Cirru;
+
+use crate::{BuildEdn, Edn, EdnBuilder, EdnLeaf, EdnMapView, EdnSetView, EdnTag};
+
+/// sizes of parsed data, counted by `parse_with_stats`.
+/// values are counted by their types, map keys included
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EdnParseStats {
+  pub nils: usize,
+  pub bools: usize,
+  pub numbers: usize,
+  pub symbols: usize,
+  pub tags: usize,
+  pub strings: usize,
+  pub quotes: usize,
+  pub tuples: usize,
+  pub lists: usize,
+  pub sets: usize,
+  pub maps: usize,
+  pub records: usize,
+  pub buffers: usize,
+  pub atoms: usize,
+  /// values read from leaves, i.e. nils, bools, numbers, symbols, tags and strings
+  pub leaves: usize,
+  /// levels of nested expressions, 1 for `[] 1 2`, `do` is not counted
+  pub max_depth: usize,
+  /// bytes of all strings after unescaping
+  pub string_bytes: usize,
+  /// comments skipped, top-level ones included
+  pub comments: usize,
+}
+
+/// builds data like `parse`, along with depth of each value
+#[derive(Default)]
+pub(crate) struct CountEdn {
+  pub stats: EdnParseStats,
+}
+
+impl EdnBuilder for CountEdn {
+  type Out = (Edn, usize);
+  type Set = (EdnSetView, usize);
+  type Map = (EdnMapView, usize);
+  type Fields = (Vec<(EdnTag, Edn)>, usize);
+
+  fn leaf(&mut self, x: EdnLeaf) -> (Edn, usize) {
+    let stats = &mut self.stats;
+    stats.leaves += 1;
+    match &x {
+      EdnLeaf::Nil => stats.nils += 1,
+      EdnLeaf::Bool(_) => stats.bools += 1,
+      EdnLeaf::Number(_) => stats.numbers += 1,
+      EdnLeaf::Symbol(_) => stats.symbols += 1,
+      EdnLeaf::Tag(_) => stats.tags += 1,
+      EdnLeaf::Str(s) => {
+        stats.strings += 1;
+        stats.string_bytes += s.len();
+      }
+    }
+    (BuildEdn.leaf(x), 0)
+  }
+  fn quote(&mut self, code: &Cirru) -> (Edn, usize) {
+    self.stats.quotes += 1;
+    (BuildEdn.quote(code), 0)
+  }
+  fn tuple(&mut self, tag: (Edn, usize), extra: Vec<(Edn, usize)>) -> (Edn, usize) {
+    self.stats.tuples += 1;
+    let depth = extra.iter().map(|x| x.1).fold(tag.1, usize::max) + 1;
+    (BuildEdn.tuple(tag.0, extra.into_iter().map(|x| x.0).collect()), depth)
+  }
+  fn list(&mut self, xs: Vec<(Edn, usize)>) -> (Edn, usize) {
+    self.stats.lists += 1;
+    let depth = xs.iter().map(|x| x.1).max().unwrap_or(0) + 1;
+    (BuildEdn.list(xs.into_iter().map(|x| x.0).collect()), depth)
+  }
+  fn set_insert(&mut self, set: &mut (EdnSetView, usize), x: (Edn, usize)) {
+    set.1 = set.1.max(x.1);
+    BuildEdn.set_insert(&mut set.0, x.0);
+  }
+  fn set(&mut self, set: (EdnSetView, usize)) -> (Edn, usize) {
+    self.stats.sets += 1;
+    (BuildEdn.set(set.0), set.1 + 1)
+  }
+  fn map_insert(&mut self, map: &mut (EdnMapView, usize), k: (Edn, usize), v: (Edn, usize)) {
+    map.1 = map.1.max(k.1).max(v.1);
+    BuildEdn.map_insert(&mut map.0, k.0, v.0);
+  }
+  fn map(&mut self, map: (EdnMapView, usize)) -> (Edn, usize) {
+    self.stats.maps += 1;
+    (BuildEdn.map(map.0), map.1 + 1)
+  }
+  fn show_key(&mut self, k: &(Edn, usize), node: &Cirru) -> String {
+    BuildEdn.show_key(&k.0, node)
+  }
+  fn fields(&mut self, capacity: usize) -> Self::Fields {
+    (BuildEdn.fields(capacity), 0)
+  }
+  fn field_push(&mut self, fields: &mut Self::Fields, name: &str, v: (Edn, usize)) {
+    fields.1 = fields.1.max(v.1);
+    BuildEdn.field_push(&mut fields.0, name, v.0);
+  }
+  fn record(&mut self, name: &str, fields: Self::Fields) -> (Edn, usize) {
+    self.stats.records += 1;
+    (BuildEdn.record(name, fields.0), fields.1 + 1)
+  }
+  fn buffer(&mut self, bytes: impl Iterator<Item = Result<u8, String>>) -> Result<(Edn, usize), String> {
+    let data = BuildEdn.buffer(bytes)?;
+    self.stats.buffers += 1;
+    Ok((data, 1))
+  }
+  fn atom(&mut self, label: Option<&str>, v: (Edn, usize)) -> (Edn, usize) {
+    self.stats.atoms += 1;
+    (BuildEdn.atom(label, v.0), v.1 + 1)
+  }
+  fn comment(&mut self) {
+    self.stats.comments += 1;
+  }
+}
@@ -8,11 +8,15 @@
 use std::{
   cmp::Eq,
   cmp::Ordering,
+  collections::HashMap,
   fmt,
   hash::{Hash, Hasher},
-  sync::Arc,
+  sync::{Arc, Mutex, OnceLock},
 };
 
+use crate::edn::ERROR_PREVIEW_LEN;
+use crate::Edn;
+
 /// tags across whole program with strings reused
 #[derive(fmt::Debug, Clone)]
 pub struct EdnTag(
@@ -36,17 +40,41 @@ impl Hash for EdnTag {
   }
 }
 
+/// drops a leading `:` like `EdnTag::new`
 impl From<&str> for EdnTag {
   fn from(s: &str) -> Self {
-    Self(Arc::from(s))
+    Self(Arc::from(s.strip_prefix(':').unwrap_or(s)))
   }
 }
 
 impl EdnTag {
+  /// tag named `s`, a leading `:` is dropped since it's the prefix of tags in code,
+  /// `EdnTag::new(":a")` is the same as `EdnTag::new("a")`, and as `:a` in parsed data
   pub fn new<T: Into<Arc<str>>>(s: T) -> Self {
+    let s: Arc<str> = s.into();
+    match s.strip_prefix(':') {
+      Some(name) => EdnTag(Arc::from(name)),
+      None => EdnTag(s),
+    }
+  }
+
+  /// tag with the name as is, even starting with `:`
+  pub fn new_raw<T: Into<Arc<str>>>(s: T) -> Self {
     EdnTag(s.into())
   }
 
+  /// tag from a static string, the string is allocated once and shared by all later calls.
+  /// this takes a global lock, prefer `edn_tag!` in hot code which caches the tag at its call site
+  pub fn from_static(s: &'static str) -> Self {
+    let s = s.strip_prefix(':').unwrap_or(s);
+    static STATIC_TAGS: OnceLock<Mutex<HashMap<&'static str, Arc<str>>>> = OnceLock::new();
+    let mut tags = STATIC_TAGS
+      .get_or_init(Default::default)
+      .lock()
+      .unwrap_or_else(|e| e.into_inner());
+    EdnTag(tags.entry(s).or_insert_with(|| Arc::from(s)).to_owned())
+  }
+
   /// get Arc<str> from inside
   pub fn arc_str(&self) -> Arc<str> {
     (*self.0).into()
@@ -56,6 +84,21 @@ impl EdnTag {
   pub fn ref_str(&self) -> &str {
     &self.0
   }
+
+  /// tag from a tag, or from a string or a symbol for data storing names as strings.
+  /// contents need to be a non-empty leaf without whitespace, parentheses or double quotes,
+  /// so the tag is written back as it is, like `:a/b` or `:1x`. `TryFrom<Edn>` only accepts tags
+  pub fn try_from_lenient(x: &Edn) -> Result<EdnTag, String> {
+    let s = match x {
+      Edn::Tag(k) => return Ok(k.to_owned()),
+      Edn::Str(s) | Edn::Symbol(s) => s,
+      a => return Err(format!("failed to convert to tag: {}", a.preview(ERROR_PREVIEW_LEN))),
+    };
+    if s.is_empty() || s.contains(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '"')) {
+      return Err(format!("failed to convert to tag, invalid name: {:?}", s));
+    }
+    Ok(EdnTag(s.to_owned()))
+  }
 }
 
 impl Ord for EdnTag {
@@ -77,3 +120,80 @@ impl PartialEq for EdnTag {
     self.0 == other.0
   }
 }
+
+/// tag from a string literal, cached in a static at the call site so only the first call allocates.
+/// use it for tags created repeatedly, like keys for lookups in loops
+///
+/// ```
+/// use cirru_edn::{edn_tag, Edn};
+///
+/// let data = cirru_edn::parse("{} (:a 1)").unwrap();
+/// assert_eq!(data.view_map().unwrap().get(&Edn::Tag(edn_tag!("a"))), Some(&Edn::Number(1.0)));
+/// ```
+#[macro_export]
+macro_rules! edn_tag {
+  ($name:literal) => {{
+    static TAG: ::std::sync::OnceLock<$crate::EdnTag> = ::std::sync::OnceLock::new();
+    TAG.get_or_init(|| $crate::EdnTag::from_static($name)).to_owned()
+  }};
+}
+
+/// name to match in `tag_dispatch!`, `None` for values other than tags
+#[doc(hidden)]
+pub trait TagDispatch {
+  fn dispatch_name(&self) -> Option<&str>;
+}
+
+impl TagDispatch for EdnTag {
+  fn dispatch_name(&self) -> Option<&str> {
+    Some(self.ref_str())
+  }
+}
+
+impl TagDispatch for Edn {
+  fn dispatch_name(&self) -> Option<&str> {
+    self.tag_name()
+  }
+}
+
+impl<T: TagDispatch + ?Sized> TagDispatch for &T {
+  fn dispatch_name(&self) -> Option<&str> {
+    (**self).dispatch_name()
+  }
+}
+
+/// match an `EdnTag`, or an `Edn` holding a tag, by names of tags. names are identifiers,
+/// or string literals for names like `"move-to"`. arms end with commas, block arms too.
+/// the `_` arm is required and comes last, values other than tags go to it. expands to a `match` on the name
+///
+/// ```
+/// use cirru_edn::{tag_dispatch, Edn};
+///
+/// let step = |op: &Edn| tag_dispatch!(op, {
+///   add => 1,
+///   remove => -1,
+///   "move-to" => 0,
+///   _ => i32::MIN,
+/// });
+/// assert_eq!(step(&Edn::tag("remove")), -1);
+/// assert_eq!(step(&Edn::tag("move-to")), 0);
+/// assert_eq!(step(&Edn::str("add")), i32::MIN);
+/// ```
+#[macro_export]
+macro_rules! tag_dispatch {
+  ($value:expr, { $($arms:tt)* }) => {
+    $crate::tag_dispatch!(@arms ($value) [] $($arms)*)
+  };
+  (@arms ($value:expr) [$($acc:tt)*] _ => $default:expr $(,)?) => {
+    match $crate::TagDispatch::dispatch_name(&$value) {
+      $($acc)*
+      _ => $default,
+    }
+  };
+  (@arms ($value:expr) [$($acc:tt)*] $name:ident => $arm:expr, $($rest:tt)*) => {
+    $crate::tag_dispatch!(@arms ($value) [$($acc)* Some(stringify!($name)) => $arm,] $($rest)*)
+  };
+  (@arms ($value:expr) [$($acc:tt)*] $name:literal => $arm:expr, $($rest:tt)*) => {
+    $crate::tag_dispatch!(@arms ($value) [$($acc)* Some($name) => $arm,] $($rest)*)
+  };
+}
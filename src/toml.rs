@@ -0,0 +1,91 @@
+//! converting Edn from and to TOML values, enabled with `toml` feature.
+//!
+//! conventions:
+//!
+//! - tables become maps with string keys, arrays become lists,
+//! - datetimes become tuples of `(:: :datetime |rfc3339-text)`, and such tuples are written back as datetimes,
+//! - Edn has one type of numbers, so numbers without fractions in range of `i64` are written as integers,
+//!   `x = 2.0` comes back as `x = 2`, other numbers are written as floats.
+//!
+//! TOML has no nil, and no sets, buffers, records, tuples or quoted code, converting them is an error
+//! with the path to the value. map keys need to be strings, tags are written as `:name` strings.
+
+use std::str::FromStr;
+
+use ::toml::{value::Datetime, Table, Value};
+
+use crate::{Edn, EdnListView, EdnMapView, EdnTupleView};
+
+/// read a TOML value into Edn
+pub fn toml_to_edn(v: &Value) -> Edn {
+  match v {
+    Value::String(s) => Edn::str(s.as_str()),
+    Value::Integer(n) => Edn::Number(*n as f64),
+    Value::Float(n) => Edn::Number(*n),
+    Value::Boolean(b) => Edn::Bool(*b),
+    Value::Datetime(d) => Edn::tuple(Edn::tag("datetime"), vec![Edn::str(d.to_string())]),
+    Value::Array(xs) => Edn::List(EdnListView(xs.iter().map(toml_to_edn).collect())),
+    Value::Table(t) => Edn::Map(EdnMapView(
+      t.iter().map(|(k, v)| (Edn::str(k.as_str()), toml_to_edn(v))).collect(),
+    )),
+  }
+}
+
+/// generate a TOML value from Edn, errors on data TOML can not express
+pub fn edn_to_toml(data: &Edn) -> Result<Value, String> {
+  edn_to_toml_at(data, &mut String::new())
+}
+
+fn edn_to_toml_at(data: &Edn, path: &mut String) -> Result<Value, String> {
+  let failed = |path: &str| {
+    format!(
+      "failed to convert {} to toml at {}",
+      data.type_name(),
+      if path.is_empty() { "top level" } else { path }
+    )
+  };
+  match data {
+    Edn::Bool(b) => Ok(Value::Boolean(*b)),
+    Edn::Number(n) => {
+      if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Ok(Value::Integer(*n as i64))
+      } else {
+        Ok(Value::Float(*n))
+      }
+    }
+    Edn::Str(s) => Ok(Value::String((**s).to_owned())),
+    Edn::Tag(t) => Ok(Value::String(format!(":{}", t))),
+    Edn::Tuple(EdnTupleView { tag, extra }) if tag.is_tag_named("datetime") => match extra.as_slice() {
+      [Edn::Str(s)] => Datetime::from_str(s)
+        .map(Value::Datetime)
+        .map_err(|e| format!("{}, invalid datetime {}: {}", failed(path), s, e)),
+      _ => Err(format!("{}, expected 1 string in datetime", failed(path))),
+    },
+    Edn::List(xs) => {
+      let mut ys = Vec::with_capacity(xs.len());
+      for (idx, x) in xs.iter().enumerate() {
+        let len = path.len();
+        path.push_str(&format!("[{}]", idx));
+        ys.push(edn_to_toml_at(x, path)?);
+        path.truncate(len);
+      }
+      Ok(Value::Array(ys.into_iter().collect()))
+    }
+    Edn::Map(xs) => {
+      let mut t = Table::new();
+      for (k, v) in xs {
+        let key = match k {
+          Edn::Str(s) => (**s).to_owned(),
+          Edn::Tag(s) => format!(":{}", s),
+          _ => return Err(format!("{}, key is not a string: {}", failed(path), k)),
+        };
+        let len = path.len();
+        path.push_str(&format!(".{}", k));
+        t.insert(key, edn_to_toml_at(v, path)?);
+        path.truncate(len);
+      }
+      Ok(Value::Table(t))
+    }
+    _ => Err(failed(path)),
+  }
+}
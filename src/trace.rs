@@ -0,0 +1,39 @@
+//! spans and events of `tracing` feature, expanding to nothing without it.
+//! fields are only evaluated by enabled spans and events, so sizes are counted on demand
+
+/// enter a debug span, returns a guard to keep in scope
+macro_rules! trace_span {
+  ($($arg:tt)+) => {{
+    #[cfg(feature = "tracing")]
+    let guard = ::tracing::debug_span!($($arg)+).entered();
+    #[cfg(not(feature = "tracing"))]
+    let guard = $crate::trace::NoSpan;
+    guard
+  }};
+}
+
+/// emit a debug event
+macro_rules! trace_event {
+  ($($arg:tt)+) => {{
+    #[cfg(feature = "tracing")]
+    ::tracing::debug!($($arg)+);
+  }};
+}
+
+/// guard of a span when `tracing` feature is off
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+/// count of leaves and expressions in code
+#[cfg(feature = "tracing")]
+pub(crate) fn cirru_node_count(node: &cirru_parser::Cirru) -> usize {
+  let mut count = 0;
+  let mut stack = vec![node];
+  while let Some(x) = stack.pop() {
+    count += 1;
+    if let cirru_parser::Cirru::List(xs) = x {
+      stack.extend(xs);
+    }
+  }
+  count
+}
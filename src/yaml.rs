@@ -0,0 +1,234 @@
+//! converting Edn from and to YAML, enabled with `yaml` feature.
+//!
+//! conventions, since YAML has fewer data types:
+//!
+//! - tags are written as strings like `:name`, symbols are tagged with `!symbol`,
+//! - sets are sequences tagged with `!set`, buffers are base64 strings tagged with `!binary`,
+//! - tuples are sequences tagged with `!tuple`, starting with the tag,
+//! - records are tagged with `!record`, holding a mapping of record name to its fields,
+//! - quoted code is tagged with `!quote` in one-liner text, atoms are tagged with `!atom`,
+//!   labeled atoms are tagged with `!labeled-atom`, holding a sequence of label and value.
+//!
+//! map entries and set items are sorted to make output stable.
+
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_yaml::{
+  value::{Tag, TaggedValue},
+  Mapping, Number, Value,
+};
+
+use crate::{Edn, EdnAtomView, EdnListView, EdnMapView, EdnRecordView, EdnSetView, EdnTag, EdnTupleView};
+
+/// generate YAML text from Edn, AnyRef is not supported
+pub fn edn_to_yaml_string(data: &Edn) -> Result<String, String> {
+  let v = edn_to_yaml(data)?;
+  serde_yaml::to_string(&v).map_err(|e| format!("failed to generate yaml: {}", e))
+}
+
+/// read YAML text into Edn. strings are kept as strings by default,
+/// turn on `tags_from_strings` to read strings like `:name` as tags.
+pub fn yaml_to_edn(s: &str, tags_from_strings: bool) -> Result<Edn, String> {
+  let v: Value = serde_yaml::from_str(s).map_err(|e| format!("failed to parse yaml: {}", e))?;
+  yaml_value_to_edn(v, tags_from_strings)
+}
+
+fn tagged(tag: &str, value: Value) -> Value {
+  Value::Tagged(Box::new(TaggedValue {
+    tag: Tag::new(tag),
+    value,
+  }))
+}
+
+fn edn_to_yaml(data: &Edn) -> Result<Value, String> {
+  match data {
+    Edn::Nil => Ok(Value::Null),
+    Edn::Bool(b) => Ok(Value::Bool(*b)),
+    Edn::Number(n) => {
+      if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Ok(Value::Number(Number::from(*n as i64)))
+      } else {
+        Ok(Value::Number(Number::from(*n)))
+      }
+    }
+    Edn::Symbol(s) => Ok(tagged("symbol", Value::String((**s).to_owned()))),
+    Edn::Tag(t) => Ok(Value::String(format!(":{}", t))),
+    Edn::Str(s) => Ok(Value::String((**s).to_owned())),
+    Edn::Quote(_) => Ok(tagged("quote", Value::String(data.read_quoted_string()?))),
+    Edn::Tuple(EdnTupleView { tag, extra }) => {
+      let mut ys = Vec::with_capacity(extra.len() + 1);
+      ys.push(edn_to_yaml(tag)?);
+      for x in extra {
+        ys.push(edn_to_yaml(x)?);
+      }
+      Ok(tagged("tuple", Value::Sequence(ys)))
+    }
+    Edn::List(xs) => {
+      let mut ys = Vec::with_capacity(xs.len());
+      for x in xs {
+        ys.push(edn_to_yaml(x)?);
+      }
+      Ok(Value::Sequence(ys))
+    }
+    Edn::Set(xs) => {
+      let mut items = xs.iter().collect::<Vec<_>>();
+      items.sort();
+      let mut ys = Vec::with_capacity(items.len());
+      for x in items {
+        ys.push(edn_to_yaml(x)?);
+      }
+      Ok(tagged("set", Value::Sequence(ys)))
+    }
+    Edn::Map(xs) => {
+      let mut items = xs.iter().collect::<Vec<_>>();
+      items.sort_by(|a, b| a.0.cmp(b.0));
+      let mut m = Mapping::with_capacity(items.len());
+      for (k, v) in items {
+        m.insert(edn_to_yaml(k)?, edn_to_yaml(v)?);
+      }
+      Ok(Value::Mapping(m))
+    }
+    Edn::Record(EdnRecordView { tag, pairs }) => {
+      let mut fields = Mapping::with_capacity(pairs.len());
+      for (k, v) in pairs {
+        fields.insert(Value::String(format!(":{}", k)), edn_to_yaml(v)?);
+      }
+      let mut m = Mapping::with_capacity(1);
+      m.insert(Value::String(format!(":{}", tag)), Value::Mapping(fields));
+      Ok(tagged("record", Value::Mapping(m)))
+    }
+    Edn::Buffer(buf) => Ok(tagged("binary", Value::String(STANDARD.encode(buf)))),
+    Edn::AnyRef(..) => Err(String::from("AnyRef is not serializable to yaml")),
+    Edn::Atom(EdnAtomView { label: None, value }) => Ok(tagged("atom", edn_to_yaml(value)?)),
+    Edn::Atom(EdnAtomView {
+      label: Some(label),
+      value,
+    }) => Ok(tagged(
+      "labeled-atom",
+      Value::Sequence(vec![Value::String((**label).to_owned()), edn_to_yaml(value)?]),
+    )),
+  }
+}
+
+fn yaml_string(v: Value) -> Result<String, String> {
+  match v {
+    Value::String(s) => Ok(s),
+    a => Err(format!("expected string in yaml, got: {:?}", a)),
+  }
+}
+
+fn yaml_value_to_edn(v: Value, tags_from_strings: bool) -> Result<Edn, String> {
+  match v {
+    Value::Null => Ok(Edn::Nil),
+    Value::Bool(b) => Ok(Edn::Bool(b)),
+    Value::Number(n) => match n.as_f64() {
+      Some(f) => Ok(Edn::Number(f)),
+      None => Err(format!("invalid number in yaml: {}", n)),
+    },
+    Value::String(s) => {
+      if tags_from_strings && s.len() > 1 && s.starts_with(':') {
+        Ok(Edn::tag_raw(&s[1..]))
+      } else {
+        Ok(Edn::Str(Arc::from(s)))
+      }
+    }
+    Value::Sequence(xs) => {
+      let mut ys = Vec::with_capacity(xs.len());
+      for x in xs {
+        ys.push(yaml_value_to_edn(x, tags_from_strings)?);
+      }
+      Ok(Edn::List(EdnListView(ys)))
+    }
+    Value::Mapping(m) => {
+      let mut zs = EdnMapView::default();
+      for (k, v) in m {
+        zs.insert(
+          yaml_value_to_edn(k, tags_from_strings)?,
+          yaml_value_to_edn(v, tags_from_strings)?,
+        );
+      }
+      Ok(Edn::Map(zs))
+    }
+    Value::Tagged(t) => {
+      let TaggedValue { tag, value } = *t;
+      if tag == "symbol" {
+        Ok(Edn::sym(yaml_string(value)?))
+      } else if tag == "quote" {
+        Edn::quote_from_str(&yaml_string(value)?)
+      } else if tag == "binary" {
+        STANDARD
+          .decode(yaml_string(value)?)
+          .map(Edn::Buffer)
+          .map_err(|e| format!("invalid base64 for buffer: {}", e))
+      } else if tag == "atom" {
+        Ok(Edn::atom(yaml_value_to_edn(value, tags_from_strings)?))
+      } else if tag == "labeled-atom" {
+        match value {
+          Value::Sequence(xs) if xs.len() == 2 => {
+            let mut xs = xs.into_iter();
+            let label = yaml_string(xs.next().expect("checked length"))?;
+            let value = yaml_value_to_edn(xs.next().expect("checked length"), tags_from_strings)?;
+            Ok(Edn::atom_labeled(label, value))
+          }
+          a => Err(format!(
+            "expected sequence of label and value for !labeled-atom, got: {:?}",
+            a
+          )),
+        }
+      } else if tag == "set" {
+        match value {
+          Value::Sequence(xs) => {
+            let mut ys = EdnSetView::default();
+            for x in xs {
+              ys.insert(yaml_value_to_edn(x, tags_from_strings)?);
+            }
+            Ok(Edn::Set(ys))
+          }
+          a => Err(format!("expected sequence for !set, got: {:?}", a)),
+        }
+      } else if tag == "tuple" {
+        match value {
+          Value::Sequence(xs) if !xs.is_empty() => {
+            let mut ys = Vec::with_capacity(xs.len());
+            for x in xs {
+              ys.push(yaml_value_to_edn(x, tags_from_strings)?);
+            }
+            // tuple tags are always read as tags
+            let tag = match ys.remove(0) {
+              Edn::Str(s) if s.starts_with(':') => Edn::tag_raw(&s[1..]),
+              a => a,
+            };
+            Ok(Edn::tuple(tag, ys))
+          }
+          a => Err(format!("expected non-empty sequence for !tuple, got: {:?}", a)),
+        }
+      } else if tag == "record" {
+        match value {
+          Value::Mapping(m) if m.len() == 1 => {
+            let (name, fields) = m.into_iter().next().expect("checked length");
+            let name = yaml_string(name)?;
+            let mut record = EdnRecordView::new(EdnTag::new_raw(name.strip_prefix(':').unwrap_or(&name)));
+            match fields {
+              Value::Mapping(fields) => {
+                for (k, v) in fields {
+                  let k = yaml_string(k)?;
+                  record.insert(
+                    k.strip_prefix(':').unwrap_or(&k),
+                    yaml_value_to_edn(v, tags_from_strings)?,
+                  );
+                }
+              }
+              a => return Err(format!("expected mapping for record fields, got: {:?}", a)),
+            }
+            Ok(Edn::Record(record))
+          }
+          a => Err(format!("expected mapping of 1 entry for !record, got: {:?}", a)),
+        }
+      } else {
+        // unknown tags are dropped, best effort
+        yaml_value_to_edn(value, tags_from_strings)
+      }
+    }
+  }
+}
@@ -14,3 +14,77 @@ fn any_ref_values() {
   assert_ne!(a, b);
   assert_ne!(a, c);
 }
+
+#[test]
+fn any_ref_identity_and_order() {
+  let a = EdnAnyRef::new(vec![1, 2]);
+  // holding a write lock, clones are compared without reading
+  let b = a.to_owned();
+  {
+    let _guard = a.0.write().expect("write any-ref");
+    assert_eq!(a, b);
+  }
+
+  // distinct refs with equal data
+  let c = EdnAnyRef::new(vec![1, 2]);
+  assert_eq!(a, c);
+  // refs with equal data are `Equal`, consistent with `==`
+  assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+  assert_eq!(a.cmp(&c), std::cmp::Ordering::Equal);
+  assert_ne!(a.cmp(&EdnAnyRef::new(vec![1, 3])), std::cmp::Ordering::Equal);
+  let mut refs = [
+    EdnAnyRef::new(1),
+    c.to_owned(),
+    EdnAnyRef::new("x"),
+    a.to_owned(),
+    EdnAnyRef::new(vec![3]),
+    EdnAnyRef::new(2),
+  ];
+  refs.sort();
+  assert!(refs.windows(2).all(|w| w[0] <= w[1]));
+  for x in &refs {
+    for y in &refs {
+      assert_eq!(x.cmp(y) == std::cmp::Ordering::Equal, x == y);
+      assert_eq!(x.cmp(y), y.cmp(x).reverse());
+    }
+  }
+
+  // order of data doesn't depend on where refs are allocated
+  let mut ys = [EdnAnyRef::new(2), EdnAnyRef::new(vec![3]), EdnAnyRef::new(1)];
+  let mut zs = [EdnAnyRef::new(1), EdnAnyRef::new(2), EdnAnyRef::new(vec![3])];
+  ys.sort();
+  zs.sort();
+  assert_eq!(format!("{:?}", ys), format!("{:?}", zs));
+
+  let mut xs = [
+    Edn::AnyRef(EdnAnyRef::new(2)),
+    Edn::Number(1.0),
+    Edn::AnyRef(EdnAnyRef::new("x")),
+    Edn::AnyRef(a.to_owned()),
+    Edn::Nil,
+  ];
+  xs.sort();
+  assert_eq!(xs[0], Edn::Nil);
+  assert_eq!(xs[1], Edn::Number(1.0));
+  assert!(xs[2..].iter().all(|x| matches!(x, Edn::AnyRef(_))));
+}
+
+#[test]
+fn any_ref_across_threads() {
+  let a = Edn::any_ref(vec![1, 2]);
+  let b = a.to_owned();
+  let handle = std::thread::spawn(move || {
+    if let Edn::AnyRef(r) = &b {
+      r.0
+        .write()
+        .expect("write any-ref")
+        .as_any()
+        .downcast_ref::<Vec<i32>>()
+        .map(|xs| xs.len())
+    } else {
+      None
+    }
+  });
+  assert_eq!(handle.join().expect("join"), Some(2));
+  assert_eq!(a, Edn::any_ref(vec![1, 2]));
+}
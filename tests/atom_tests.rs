@@ -4,24 +4,80 @@ extern crate cirru_edn;
 
 #[test]
 fn atom_parse() {
-  let atom = Edn::Atom(Box::new("test".into()));
+  let atom = Edn::atom("test".into());
   let formatted = cirru_edn::parse("atom |test");
   assert_eq!(Ok(atom), formatted);
 
-  let atom = Edn::Atom(Box::new(Edn::List(vec![Edn::Number(1.), Edn::Number(2.)].into())));
+  let atom = Edn::atom(Edn::List(vec![Edn::Number(1.), Edn::Number(2.)].into()));
   let formatted = cirru_edn::parse("atom $ [] 1 2");
   assert_eq!(Ok(atom), formatted);
 }
 
 #[test]
 fn atom_format() -> Result<(), String> {
-  let data = Edn::Atom(Box::new("test".into()));
+  let data = Edn::atom("test".into());
   let formatted = cirru_edn::format(&data, true)?;
   assert_eq!(formatted, "\natom |test\n");
 
-  let data = Edn::Atom(Box::new(Edn::List(vec![Edn::Number(1.), Edn::Number(2.)].into())));
+  let data = Edn::atom(Edn::List(vec![Edn::Number(1.), Edn::Number(2.)].into()));
   let formatted = cirru_edn::format(&data, true)?;
   assert_eq!(formatted, "\natom $ [] 1 2\n");
 
   Ok(())
 }
+
+#[test]
+fn labeled_atom() -> Result<(), String> {
+  let data = Edn::atom_labeled("a", Edn::List(vec![Edn::Number(1.), Edn::Number(2.)].into()));
+  assert_eq!(cirru_edn::parse("atom 'a $ [] 1 2"), Ok(data.to_owned()));
+  assert_eq!(cirru_edn::format(&data, true)?, "\natom 'a $ [] 1 2\n");
+  assert_eq!(data.to_string(), "(atom 'a ([] 1 2))");
+
+  // labels are part of identity
+  assert_ne!(
+    data,
+    Edn::atom(Edn::List(vec![Edn::Number(1.), Edn::Number(2.)].into()))
+  );
+  assert_ne!(
+    data,
+    Edn::atom_labeled("b", Edn::List(vec![Edn::Number(1.), Edn::Number(2.)].into()))
+  );
+
+  let shared = cirru_edn::parse("[] (atom 'a 1) (atom 'a 1) (atom 1)")?;
+  assert_eq!(
+    cirru_edn::parse(&cirru_edn::format(&shared, false)?),
+    Ok(shared.to_owned())
+  );
+  match shared {
+    Edn::List(xs) => {
+      assert_eq!(xs.0[0], xs.0[1]);
+      assert_ne!(xs.0[1], xs.0[2]);
+    }
+    _ => unreachable!(),
+  }
+
+  assert!(cirru_edn::parse("atom |a 1").is_err());
+
+  // atoms sharing a label hold the same value
+  assert_eq!(
+    cirru_edn::parse("[] (atom 'a 1) (atom 'a 2)"),
+    Err(String::from("atoms labeled 'a hold different values: 1 and 2"))
+  );
+  // values are listed in the order they are written, nested ones included
+  assert_eq!(
+    cirru_edn::parse("[] ([] (atom 'a 1) 0) (atom 'a 2) (atom 'a 3)"),
+    Err(String::from("atoms labeled 'a hold different values: 1 and 2"))
+  );
+  let data = Edn::from(vec![
+    Edn::atom_labeled("a", Edn::Nil),
+    Edn::atom_labeled("a", Edn::tag("x")),
+  ]);
+  assert_eq!(
+    cirru_edn::format(&data, true),
+    Err(String::from(
+      "failed to format, atoms labeled 'a hold different values: nil and :x"
+    ))
+  );
+  assert!(cirru_edn::parse("atom 'a $ [] (atom 'a 1)").is_err());
+  Ok(())
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "bincode")]
+
+extern crate cirru_edn;
+
+use cirru_edn::{Edn, EdnListView, EdnMapView, EdnRecordView, EdnTag};
+
+fn encode(data: &Edn) -> Result<Vec<u8>, String> {
+  bincode::encode_to_vec(data, bincode::config::standard()).map_err(|e| e.to_string())
+}
+
+fn decode(buf: &[u8]) -> Result<Edn, String> {
+  let (decoded, length): (Edn, usize) =
+    bincode::decode_from_slice(buf, bincode::config::standard()).map_err(|e| e.to_string())?;
+  assert_eq!(length, buf.len());
+  Ok(decoded)
+}
+
+fn large_demo() -> Edn {
+  let mut xs = EdnListView::default();
+  for i in 0..2000 {
+    let mut record = EdnRecordView::new(EdnTag::new("Item"));
+    record.insert("id", Edn::Number(i as f64));
+    record.insert("name", Edn::str(format!("item {}", i)));
+    record.insert("kind", Edn::sym("k"));
+    record.insert("buf", Edn::Buffer(vec![i as u8, 1, 2]));
+    let mut map = EdnMapView::default();
+    map.insert(Edn::tag("a"), Edn::Bool(i % 2 == 0));
+    map.insert(Edn::from(vec![Edn::Number(1.0)]), Edn::Nil);
+    record.insert("meta", Edn::Map(map));
+    xs.push(Edn::Record(record));
+  }
+  xs.push(cirru_edn::parse("#{} :a :b 1").unwrap());
+  xs.push(cirru_edn::parse("quote (a (b |c))").unwrap());
+  xs.push(cirru_edn::parse(":: :point 1 2").unwrap());
+  xs.push(cirru_edn::parse("atom 'a $ [] 1").unwrap());
+  xs.push(cirru_edn::parse("atom 1").unwrap());
+  Edn::List(xs)
+}
+
+#[test]
+fn bincode_roundtrip() -> Result<(), String> {
+  let data = large_demo();
+  let buf = encode(&data)?;
+  assert_eq!(decode(&buf)?, data);
+  Ok(())
+}
+
+#[test]
+fn bincode_wire_format() -> Result<(), String> {
+  assert_eq!(encode(&Edn::Nil)?, vec![0]);
+  assert_eq!(encode(&Edn::Bool(true))?, vec![1, 1]);
+  assert_eq!(encode(&Edn::tag("a"))?, vec![4, 1, b'a']);
+  assert_eq!(encode(&Edn::from(vec![Edn::Nil]))?, vec![8, 1, 0]);
+
+  assert!(encode(&Edn::any_ref(1)).is_err());
+  assert!(decode(&[13]).is_err());
+  assert!(decode(&[8, 2, 0]).is_err());
+  Ok(())
+}
+
+#[test]
+fn bincode_sorted_collections() -> Result<(), String> {
+  // hash sets built apart iterate in different orders
+  let xs = (0..64).map(|i| Edn::Number(i as f64)).collect::<Vec<_>>();
+  let a = Edn::Set(xs.iter().cloned().collect());
+  let b = Edn::Set(xs.iter().rev().cloned().collect());
+  assert_eq!(encode(&a)?, encode(&b)?);
+
+  let data = cirru_edn::parse("#{} :b :a")?;
+  assert_eq!(encode(&data)?, vec![9, 2, 4, 1, b'a', 4, 1, b'b']);
+  let data = cirru_edn::parse("{} (:b 1) (:a nil)")?;
+  let buf = encode(&data)?;
+  assert_eq!(buf[..5], [10, 2, 4, 1, b'a']);
+  assert_eq!(decode(&buf)?, data);
+  Ok(())
+}
+
+#[test]
+fn bincode_decode_depth() -> Result<(), String> {
+  let nested = |levels: usize| {
+    let mut buf = [8, 1].repeat(levels);
+    buf.push(0);
+    buf
+  };
+  assert!(decode(&nested(512)).is_ok());
+  let e = decode(&nested(513)).unwrap_err();
+  assert!(e.contains("data nested deeper than 512 levels"), "{}", e);
+  assert!(decode(&nested(100_000)).is_err());
+
+  // quoted Cirru is limited too
+  let mut buf = vec![6];
+  buf.extend([1, 1].repeat(100_000));
+  buf.extend([0, 0]);
+  assert!(decode(&buf).is_err());
+  Ok(())
+}
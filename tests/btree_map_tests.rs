@@ -0,0 +1,68 @@
+#![cfg(feature = "btree-map")]
+
+extern crate cirru_edn;
+
+use cirru_edn::{Edn, EdnSetView};
+
+#[test]
+fn sorted_map_iteration() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:c 3) (:a 1) (|d 4) (:b 2) (1 0)")?;
+  let keys = data.view_map()?.0.into_keys().collect::<Vec<_>>();
+  assert_eq!(
+    keys,
+    vec![
+      Edn::Number(1.0),
+      Edn::tag("a"),
+      Edn::tag("b"),
+      Edn::tag("c"),
+      Edn::str("d")
+    ]
+  );
+  Ok(())
+}
+
+#[test]
+fn sorted_set_iteration() -> Result<(), String> {
+  let data = cirru_edn::parse("#{} :z |b 3 :a 1")?;
+  let items = data.view_set()?.0.into_iter().collect::<Vec<_>>();
+  assert_eq!(
+    items,
+    vec![
+      Edn::Number(1.0),
+      Edn::Number(3.0),
+      Edn::tag("a"),
+      Edn::tag("z"),
+      Edn::str("b")
+    ]
+  );
+  Ok(())
+}
+
+#[test]
+fn nested_maps_in_set() -> Result<(), String> {
+  // maps of same size are comparable when sorted
+  let data = cirru_edn::parse("#{} ({} (:b 2)) ({} (:a 1))")?;
+  let items = data.view_set()?.0.into_iter().collect::<Vec<_>>();
+  assert_eq!(
+    items,
+    vec![
+      Edn::map_from_iter([(Edn::tag("a"), Edn::Number(1.0))]),
+      Edn::map_from_iter([(Edn::tag("b"), Edn::Number(2.0))]),
+    ]
+  );
+  assert_eq!(cirru_edn::parse(&cirru_edn::format(&data, true)?), Ok(data));
+  Ok(())
+}
+
+#[test]
+fn any_refs_in_sorted_set() {
+  let a = Edn::any_ref(1i32);
+  let b = Edn::any_ref(1i32);
+  // distinct refs with equal data are the same item, agreeing with `Ord`, same as in hash sets
+  assert_eq!(a, b);
+  assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+  let set = Edn::from(EdnSetView([a.to_owned(), b.to_owned(), Edn::any_ref(2i32)].into()));
+  let items = set.view_set().expect("set");
+  assert_eq!(items.len(), 2);
+  assert!(items.contains(&Edn::any_ref(1i32)));
+}
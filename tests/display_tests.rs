@@ -3,6 +3,7 @@ extern crate cirru_edn;
 use std::{sync::Arc, vec};
 
 use cirru_edn::{Edn, EdnListView, EdnRecordView, EdnTag};
+use cirru_parser::Cirru;
 
 #[test]
 fn display_data() {
@@ -27,5 +28,143 @@ fn display_data() {
 fn display_with_cjk() {
   let r = Edn::List(EdnListView(vec![Edn::str("你好"), Edn::str("世界"), Edn::str("海 洋")]));
 
+  #[cfg(feature = "cjk-detect")]
   assert_eq!(format!("{r}"), "([] |你好 |世界 \"|海 洋\")");
+  #[cfg(not(feature = "cjk-detect"))]
+  assert_eq!(format!("{r}"), "([] \"|你好\" \"|世界\" \"|海 洋\")");
+
+  assert_eq!(
+    cirru_edn::parse(&format!("do {}", Edn::str("你好"))),
+    Ok(Edn::str("你好"))
+  );
+  assert_eq!(cirru_edn::parse(&cirru_edn::format(&r, true).unwrap()), Ok(r));
+  assert_eq!(cirru_edn::is_simple_char('你'), cfg!(feature = "cjk-detect"));
+  assert!(!cirru_edn::is_simple_char('é'));
+}
+
+fn brackets_balanced(s: &str) -> bool {
+  let mut depth = 0;
+  for c in s.chars() {
+    match c {
+      '(' => depth += 1,
+      ')' => {
+        if depth == 0 {
+          return false;
+        }
+        depth -= 1
+      }
+      _ => {}
+    }
+  }
+  depth == 0
+}
+
+#[test]
+fn bounded_preview() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:a $ [] 1 2) (:b $ %{} :R (:x |s))")?;
+  assert_eq!(data.preview(1000), data.to_string());
+
+  let xs = Edn::from((0..1_000_000).map(|i| Edn::Number(i as f64)).collect::<Vec<_>>());
+  let p = xs.preview(40);
+  assert!(p.chars().count() <= 40, "{}", p);
+  assert!(p.starts_with("([] 0 1 2"), "{}", p);
+  assert!(p.ends_with(" …)"), "{}", p);
+  assert!(brackets_balanced(&p));
+
+  let mut nested = Edn::Nil;
+  for i in 0..10_000 {
+    nested = Edn::from(vec![Edn::Number(i as f64), nested]);
+  }
+  for len in [0, 1, 3, 10, 50] {
+    let p = nested.preview(len);
+    assert!(p.chars().count() <= len.max(1), "{}", p);
+    assert!(brackets_balanced(&p), "{}", p);
+  }
+  assert_eq!(Edn::str("a long string").preview(8), "…");
+  assert_eq!(cirru_edn::parse("#{} :c :a :b")?.preview(40), "(#{} :a :b :c)");
+
+  // keys holding collections are not sorted, which would compare them recursively
+  let mut deep = Edn::Nil;
+  for i in 0..500 {
+    deep = Edn::from(vec![Edn::Number(i as f64), deep]);
+  }
+  let data = Edn::map([(deep.to_owned(), Edn::Nil), (Edn::from(vec![deep]), Edn::Nil)]);
+  let p = data.preview(30);
+  assert!(p.chars().count() <= 30, "{}", p);
+  assert!(brackets_balanced(&p), "{}", p);
+
+  let err = xs.view_map().unwrap_err();
+  assert!(err.len() < 120, "{}", err);
+  Ok(())
+}
+
+#[test]
+fn simple_str_chars_roundtrip() -> Result<(), String> {
+  for b in 0u8..0x80 {
+    let c = b as char;
+    for s in [
+      format!("{c}"),
+      format!("a{c}b"),
+      format!("{c}a"),
+      format!("a{c}"),
+      format!("{c}{c}"),
+    ] {
+      let data = Edn::str(s.as_str());
+      let shown = data.to_string();
+      assert_eq!(
+        !shown.starts_with('"'),
+        cirru_edn::is_simple_str_char(c),
+        "quoting of {:?} in {}",
+        c,
+        shown
+      );
+      assert_eq!(cirru_edn::parse(&format!("do {}", shown))?, data, "display of {:?}", s);
+      assert_eq!(
+        cirru_edn::parse(&format!("[] 1 {} 2", shown))?,
+        Edn::from(vec![Edn::Number(1.0), data.to_owned(), Edn::Number(2.0)])
+      );
+      assert_eq!(
+        cirru_edn::parse(&cirru_edn::format(&data, true)?)?,
+        data,
+        "format of {:?}",
+        s
+      );
+      assert_eq!(
+        cirru_edn::parse(&cirru_edn::format(&data, false)?)?,
+        data,
+        "format of {:?}",
+        s
+      );
+    }
+  }
+  assert_eq!(Edn::str("https://a.b/c_d?e=f&g").to_string(), "|https://a.b/c_d?e=f&g");
+  assert_eq!(Edn::str("a b").to_string(), "\"|a b\"");
+  Ok(())
+}
+
+#[test]
+fn display_quoted_code() -> Result<(), String> {
+  let code = Edn::quote_from_str("defn factorial (n)\n  if (<= n 1) 1\n    * n $ factorial $ - n 1")?;
+  assert_eq!(
+    code.to_string(),
+    "(quote (defn factorial (n) (if (<= n 1) 1 (* n (factorial (- n 1))))))"
+  );
+  assert_eq!(
+    format!("{:#}", code),
+    "quote\n  defn factorial (n)\n    if (<= n 1) 1 $ * n\n      factorial $ - n 1"
+  );
+  assert_eq!(cirru_edn::parse(&format!("{:#}", code))?, code);
+
+  assert_eq!(Edn::quote_from_str("a \"|b c\"")?.to_string(), "(quote (a \"|b c\"))");
+  assert_eq!(Edn::Quote(Cirru::Leaf("a".into())).to_string(), "(quote a)");
+
+  // empty code is still shown
+  let empty = Edn::Quote(Cirru::Leaf("".into()));
+  assert_eq!(empty.to_string(), "(quote \"\")");
+  assert_eq!(format!("{:#}", empty), "(quote \"\")");
+  let empty = Edn::Quote(Cirru::List(vec![]));
+  assert_eq!(empty.to_string(), "(quote ())");
+  assert_eq!(format!("{:#}", empty), "quote ()");
+  assert_eq!(cirru_edn::parse(&format!("{:#}", empty))?, empty);
+  Ok(())
 }
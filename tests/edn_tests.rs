@@ -1,7 +1,8 @@
 extern crate cirru_edn;
 
 use cirru_edn::EdnRecordView;
-use cirru_edn::{Edn, EdnListView, EdnTag};
+use cirru_edn::{Edn, EdnAtomView, EdnListView, EdnSetView, EdnTag};
+use cirru_parser::Cirru;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
@@ -153,12 +154,34 @@ fn set_writing() -> Result<(), String> {
   v.insert(Edn::Number(1.0));
   v.insert(Edn::from(vec![Edn::Number(3.0)]));
 
-  // TODO order is not stable
   let r = cirru_edn::format(&Edn::from(v), true)?;
-  let r1 = "\n#{} ([] 3) 1\n";
-  let r2 = "\n#{} 1 $ [] 3\n";
+  assert_eq!(r, "\n#{} 1 $ [] 3\n");
+  Ok(())
+}
 
-  assert!(r == r1 || r == r2);
+#[test]
+fn set_writing_order() -> Result<(), String> {
+  let items = [
+    Edn::str("b"),
+    Edn::Number(10.0),
+    Edn::tag("z"),
+    Edn::Bool(true),
+    Edn::sym("s"),
+    Edn::Nil,
+    Edn::Number(-1.5),
+    Edn::str("a"),
+    Edn::tag("a"),
+    Edn::Bool(false),
+  ];
+  let expected = "\n#{} nil false true -1.5 10 's :a :z |a |b\n";
+  for shift in 0..items.len() {
+    let mut xs = items.to_vec();
+    xs.rotate_left(shift);
+    #[allow(clippy::mutable_key_type)]
+    let data = Edn::from(xs.into_iter().collect::<HashSet<_>>());
+    assert_eq!(cirru_edn::format(&data, true)?, expected);
+    assert_eq!(cirru_edn::format(&data, false)?, expected);
+  }
   Ok(())
 }
 
@@ -251,13 +274,15 @@ fn demo_parsing() -> Result<(), String> {
 
 #[test]
 fn debug_format() {
-  // TODO order for hashmap is unstable
-
-  // let DICT_INLINE2: &str =
-  //   r#"({} (:a 1) (:b ([] 2 3 4)) (:c ({} (:h ({} (|b true) (|a 1)) (:e true) (:d 4) (:f :g))"#;
-
-  // let data = cirru_edn::parse(String::from(DICT_DEMO2))?;
-  // assert_eq!(format!("{}", data), DICT_INLINE2);
+  let dict_inline2 = r#"({} (:a 1) (:b ([] 2 3 4)) (:c ({} (:d 4) (:e true) (:f :g) (:h ({} (|a 1) (|b true))))))"#;
+  let data = cirru_edn::parse(DICT_DEMO2).unwrap();
+  assert_eq!(format!("{}", data), dict_inline2);
+  // a map with the same entries may iterate in another order
+  assert_eq!(format!("{}", data.to_owned()), format!("{}", data));
+  assert_eq!(
+    format!("{}", cirru_edn::parse("#{} :c 1 |b :a").unwrap()),
+    "(#{} 1 :a :c |b)"
+  );
 
   #[allow(clippy::mutable_key_type)]
   let empty: HashMap<Edn, Edn> = HashMap::new();
@@ -399,8 +424,998 @@ fn test_iter() -> Result<(), String> {
     9u8.into(),
   ];
   let data = Edn::from(xs);
-  for item in &data.view_list()? {
-    println!("{:?}", item);
+  let mut sum = 0.0;
+  for item in data.iter_list()? {
+    sum += item.read_number()?;
+  }
+  assert_eq!(sum, 45.0);
+  Ok(())
+}
+
+#[test]
+fn iter_variants() -> Result<(), String> {
+  let list = cirru_edn::parse("[] 1 2 3")?;
+  assert_eq!(
+    list.iter_list()?.collect::<Vec<_>>(),
+    [&Edn::ONE, &Edn::Number(2.0), &Edn::Number(3.0)]
+  );
+
+  let map = cirru_edn::parse("{} (:a 1) (:b 2)")?;
+  let mut entries = map.iter_map()?.collect::<Vec<_>>();
+  entries.sort();
+  assert_eq!(
+    entries,
+    [(&Edn::tag("a"), &Edn::ONE), (&Edn::tag("b"), &Edn::Number(2.0))]
+  );
+
+  let set = cirru_edn::parse("#{} :x :y")?;
+  let mut items = set.iter_set()?.collect::<Vec<_>>();
+  items.sort();
+  assert_eq!(items, [&Edn::tag("x"), &Edn::tag("y")]);
+
+  let record = cirru_edn::parse("%{} :Cat (:name |t) (:age 2)")?;
+  let fields = record.iter_record()?.map(|(k, _)| k.ref_str()).collect::<Vec<_>>();
+  assert_eq!(fields, ["name", "age"]);
+
+  // nil is empty for collections, but not a record
+  assert_eq!(Edn::Nil.iter_list()?.count(), 0);
+  assert_eq!(Edn::Nil.iter_map()?.count(), 0);
+  assert_eq!(Edn::Nil.iter_set()?.count(), 0);
+  assert_eq!(
+    Edn::Nil.iter_record().err(),
+    Some(String::from("failed to iterate record, got nil: nil"))
+  );
+  assert_eq!(
+    list.iter_map().err(),
+    Some(String::from("failed to iterate map, got list: ([] 1 2 3)"))
+  );
+  assert!(map.iter_list().is_err());
+  assert!(record.iter_set().is_err());
+  Ok(())
+}
+
+#[test]
+fn quote_from_text() -> Result<(), String> {
+  let code = Edn::quote_from_str("defn f (x) (+ x 1)")?;
+  assert_eq!(
+    code,
+    Edn::Quote(Cirru::List(vec![
+      "defn".into(),
+      "f".into(),
+      vec!["x"].into(),
+      vec!["+", "x", "1"].into(),
+    ]))
+  );
+  assert_eq!(code.read_quoted_string()?, "defn f (x) (+ x 1)");
+
+  // multiple expressions are wrapped in one list
+  let code = Edn::quote_from_str("a b\nc d")?;
+  assert_eq!(
+    code,
+    Edn::Quote(Cirru::List(vec![vec!["a", "b"].into(), vec!["c", "d"].into()]))
+  );
+  assert_eq!(code.read_quoted_string()?, "(a b) (c d)");
+  assert_eq!(Edn::quote_from_str(&code.read_quoted_string()?)?, code);
+
+  assert!(Edn::quote_from_str("").is_err());
+  assert!(Edn::Nil.read_quoted_string().is_err());
+  Ok(())
+}
+
+#[test]
+fn string_escapes() -> Result<(), String> {
+  let s = Edn::str("a\0b 😀 中文 海 洋\n\t\"'\\u{41}");
+
+  let formatted = cirru_edn::format(&s, true)?;
+  assert_eq!(
+    formatted,
+    "\ndo \"|a\\\\u{0}b 😀 中文 海 洋\\n\\t\\\"\\'\\\\u{5c}u{41}\"\n"
+  );
+  assert_eq!(cirru_edn::parse(&formatted), Ok(s.to_owned()));
+
+  let displayed = format!("{}", s);
+  assert_eq!(displayed, "\"|a\\\\u{0}b 😀 中文 海 洋\\n\\t\\\"\\'\\\\u{5c}u{41}\"");
+  assert_eq!(cirru_edn::parse(&format!("do {}", displayed)), Ok(s));
+
+  assert_eq!(cirru_edn::parse(r#"do "|\\u{1F600}\\u{4e2d}""#), Ok(Edn::str("😀中")));
+  // examples in README, in the form `format` writes
+  assert_eq!(
+    cirru_edn::parse(r#"do "|tab\tand nul \\u{0} and emoji \\u{1F600}""#),
+    Ok(Edn::str("tab\tand nul \0 and emoji 😀"))
+  );
+  assert_eq!(
+    cirru_edn::parse(r#"do "|a regex \\u{5c}u{41}""#),
+    Ok(Edn::str("a regex \\u{41}"))
+  );
+  // text of `\u{` is kept as text wherever it is
+  for text in ["\\u{", "\\u{}", "a\\u{zz}", "\\\\u{41}", "\\u{5c}u{41}"] {
+    let data = Edn::str(text);
+    assert_eq!(cirru_edn::parse(&cirru_edn::format(&data, true)?), Ok(data), "{}", text);
+  }
+  assert!(cirru_edn::parse(r#"do "|\\u{zz}""#).is_err());
+  assert!(cirru_edn::parse(r#"do "|\\u{41""#).is_err());
+  Ok(())
+}
+
+#[test]
+fn number_literals() {
+  assert_eq!(Ok(Edn::Number(1000.0)), cirru_edn::parse("do 1_000"));
+  assert_eq!(Ok(Edn::Number(1_000_000.0)), cirru_edn::parse("do 1_000_000"));
+  assert_eq!(Ok(Edn::Number(-12.5)), cirru_edn::parse("do -1_2.5"));
+  assert_eq!(Ok(Edn::Number(7.0)), cirru_edn::parse("do +7"));
+  assert_eq!(Ok(Edn::Number(0.25)), cirru_edn::parse("do +0.2_5"));
+
+  for token in ["_1", "1__2", "1_", "-_1", "1_.5"] {
+    let e = cirru_edn::parse(&format!("do {}", token)).unwrap_err();
+    assert!(e.contains("`_` is only allowed between digits"), "{}", e);
+  }
+  let e = cirru_edn::parse("do a_b").unwrap_err();
+  assert!(e.contains("unknown token for edn value"), "{}", e);
+
+  // formatting never emits underscores
+  assert_eq!(
+    cirru_edn::format(&Edn::Number(1e6), true),
+    Ok(String::from("\ndo 1000000\n"))
+  );
+}
+
+#[test]
+fn lenient_reading() {
+  let coercions = [
+    (Edn::Bool(true), Ok(true)),
+    (Edn::Bool(false), Ok(false)),
+    (Edn::str("true"), Ok(true)),
+    (Edn::str("false"), Ok(false)),
+    (Edn::tag("true"), Ok(true)),
+    (Edn::tag("false"), Ok(false)),
+    (Edn::str("yes"), Err("string")),
+    (Edn::Nil, Err("nil")),
+    (Edn::Number(1.0), Err("number")),
+    (Edn::sym("true"), Err("symbol")),
+    (Edn::from(vec![Edn::Bool(true)]), Err("list")),
+  ];
+  for (x, expected) in coercions {
+    match (x.coerce_bool(), expected) {
+      (Ok(b), Ok(e)) => assert_eq!(b, e),
+      (Err(msg), Err(type_name)) => {
+        assert_eq!(x.type_name(), type_name);
+        assert!(msg.contains(type_name), "{}", msg);
+      }
+      (v, e) => panic!("unexpected {:?} for {}, expected {:?}", v, x, e),
+    }
+  }
+
+  assert!(Edn::str("true").read_bool_or(false));
+  assert!(Edn::Nil.read_bool_or(true));
+  assert!(!Edn::Number(1.0).read_bool_or(false));
+
+  assert_eq!(Edn::Number(2.0).read_number_or(1.0), 2.0);
+  assert_eq!(Edn::str(" 3.5").read_number_or(1.0), 3.5);
+  assert_eq!(Edn::str("x").read_number_or(1.0), 1.0);
+  // same rules as numbers in code
+  assert_eq!(Edn::str("1_000").read_number_or(1.0), 1000.0);
+  assert_eq!(Edn::str("1__0").read_number_or(1.0), 1.0);
+  assert_eq!(Edn::Nil.read_number_or(1.0), 1.0);
+
+  assert_eq!(Edn::str("a").read_string_or("b"), "a");
+  assert_eq!(Edn::tag("a").read_string_or("b"), "a");
+  assert_eq!(Edn::sym("a").read_string_or("b"), "b");
+  assert_eq!(Edn::Nil.read_string_or("b"), "b");
+}
+
+#[test]
+fn errors_with_previews() {
+  let err = cirru_edn::parse("do 1 2").unwrap_err();
+  assert_eq!(err, "multiple values in do, got `1` and then `2` at top level");
+
+  let err = cirru_edn::parse("[] 1 (do 2 3)").unwrap_err();
+  assert!(err.contains("got `2` and then `3` at [1]"), "{}", err);
+
+  let err = cirru_edn::parse("{} (:a $ [] 1 (do ([] 2) (:: :t 3)))").unwrap_err();
+  assert!(err.contains("got `[] 2` and then `:: :t 3` at .:a[1]"), "{}", err);
+
+  let err = cirru_edn::parse("%{} :A (:b $ #{} (quote a b))").unwrap_err();
+  assert!(err.contains("got 2 in `quote a b` at .b#(quote a b)"), "{}", err);
+
+  let err = cirru_edn::parse("[] (atom)").unwrap_err();
+  assert!(err.contains("`atom` at [0]"), "{}", err);
+  let err = cirru_edn::parse(":: :t (atom 1 2 3)").unwrap_err();
+  assert!(err.contains("`atom 1 2 3` at [1]"), "{}", err);
+  let err = cirru_edn::parse("[] (do)").unwrap_err();
+  assert!(err.contains("missing edn do value at [0]"), "{}", err);
+}
+
+#[test]
+#[allow(clippy::mutable_key_type)]
+fn tuple_construction_paths() -> Result<(), String> {
+  let built = Edn::tuple(Edn::tag("point"), vec![Edn::Number(1.0), Edn::str("a")]);
+  let parsed = cirru_edn::parse(":: :point 1 |a")?;
+  assert_eq!(built, parsed);
+  assert_eq!(built.cmp(&parsed), std::cmp::Ordering::Equal);
+  assert_ne!(built, cirru_edn::parse(":: :point 1 |b")?);
+
+  let mut keys = HashSet::new();
+  keys.insert(built.to_owned());
+  assert!(keys.contains(&parsed));
+
+  let map = cirru_edn::parse("{} ((:: :point 1 |a) |found)")?;
+  assert_eq!(map.view_map()?.get(&built), Some(&Edn::str("found")));
+  Ok(())
+}
+
+#[test]
+fn tolerant_structural_eq() -> Result<(), String> {
+  let a = cirru_edn::parse("{} (:a 1) (:b $ [] 0.5 (#{} 2 3)) (:c $ %{} :R (:x 4)) (:d $ :: :t 5)")?;
+  let b = Edn::map_from_iter([
+    (Edn::tag("a"), Edn::Number(1.0 + 1e-10)),
+    (
+      Edn::tag("b"),
+      Edn::from(vec![
+        Edn::Number(0.5 - 1e-10),
+        Edn::Set(EdnSetView([Edn::Number(2.0 + 1e-10), Edn::Number(3.0)].into())),
+      ]),
+    ),
+    (
+      Edn::tag("c"),
+      Edn::record_from_pairs(EdnTag::new("R"), &[(EdnTag::new("x"), Edn::Number(4.0 - 1e-10))]),
+    ),
+    (Edn::tag("d"), Edn::tuple(Edn::tag("t"), vec![Edn::Number(5.0 + 1e-10)])),
+  ]);
+  assert_ne!(a, b);
+  assert!(a.structural_eq_within(&b, 1e-9));
+  assert!(b.structural_eq_within(&a, 1e-9));
+  assert!(!a.structural_eq_within(&b, 1e-12));
+  assert!(a.structural_eq_within(&a, 0.0));
+
+  // exact equality keeps hashing consistent
+  assert_ne!(Edn::Number(0.1 + 0.2), Edn::Number(0.3));
+  assert_eq!(Edn::Number(0.0), Edn::Number(-0.0));
+  assert_eq!(Edn::Number(f64::NAN), Edn::Number(f64::NAN));
+  Ok(())
+}
+
+#[test]
+fn top_level_comments() -> Result<(), String> {
+  let err = cirru_edn::parse("; note\n\n; another note\n").unwrap_err();
+  assert_eq!(err, "no data expression found (only comments/whitespace)");
+  assert_eq!(
+    cirru_edn::parse("").unwrap_err(),
+    "no data expression found (only comments/whitespace)"
+  );
+
+  let data = cirru_edn::parse("; header\n{} (:a 1)\n  :b 2\n; trailing note\n")?;
+  assert_eq!(data.map_get("b")?, &Edn::Number(2.0));
+  assert!(cirru_edn::parse("do 1\ndo 2\n; note")
+    .unwrap_err()
+    .contains("got length 2"));
+  Ok(())
+}
+
+#[test]
+fn record_fields_without_colons() -> Result<(), String> {
+  let with_colons = cirru_edn::parse("%{} :Demo (:a 1) (:b $ [] 2)")?;
+  let without = cirru_edn::parse("%{} Demo (a 1) (b $ [] 2)")?;
+  let mixed = cirru_edn::parse("%{} :Demo (a 1) (:b $ [] 2)")?;
+  assert_eq!(with_colons, without);
+  assert_eq!(with_colons, mixed);
+  assert_eq!(without.record_get("a")?, &Edn::Number(1.0));
+
+  // format always writes tags with colons
+  assert_eq!(cirru_edn::format(&without, true)?, "\n%{} :Demo (:a 1)\n  :b $ [] 2\n");
+
+  // map keys are values, bare words are not
+  assert!(cirru_edn::parse("{} (a 1)").is_err());
+
+  assert!(cirru_edn::parse("%{} :Demo (|a 1)")
+    .unwrap_err()
+    .contains("expected tag in record, got: |a"));
+  assert!(cirru_edn::parse("%{} 'Demo (:a 1)")
+    .unwrap_err()
+    .contains("expected tag in record"));
+  Ok(())
+}
+
+#[test]
+fn named_predicates() {
+  let tag = Edn::tag("update");
+  let sym = Edn::sym("update");
+  let s = Edn::str("update");
+
+  assert!(tag.is_tag_named("update"));
+  assert!(!tag.is_tag_named("delete"));
+  assert!(!tag.is_symbol_named("update"));
+  assert!(!tag.is_str_eq("update"));
+  assert_eq!(tag.tag_name(), Some("update"));
+
+  assert!(sym.is_symbol_named("update"));
+  assert!(!sym.is_symbol_named("up"));
+  assert!(!sym.is_tag_named("update"));
+  assert_eq!(sym.tag_name(), None);
+
+  assert!(s.is_str_eq("update"));
+  assert!(!s.is_str_eq("update "));
+  assert!(!s.is_tag_named("update"));
+
+  for x in [Edn::Nil, Edn::Number(1.0), Edn::from(vec![Edn::tag("update")])] {
+    assert!(!x.is_tag_named("update") && !x.is_symbol_named("update") && !x.is_str_eq("update"));
+    assert_eq!(x.tag_name(), None);
   }
+}
+
+#[test]
+fn buffer_migration() -> Result<(), String> {
+  assert_eq!(
+    Edn::list_to_buffer(&Edn::list([0, 1, 255]))?,
+    Edn::Buffer(vec![0, 1, 255])
+  );
+  assert!(Edn::list_to_buffer(&Edn::list([1, 256]))
+    .unwrap_err()
+    .contains("item 1 is not a byte: 256"));
+  assert!(Edn::list_to_buffer(&Edn::list([1.5])).is_err());
+  assert!(Edn::list_to_buffer(&Edn::list([-1])).is_err());
+  assert!(Edn::list_to_buffer(&Edn::Nil).is_err());
+
+  let mut data = cirru_edn::parse(
+    "{} (:data $ [] 1 2 255) (:scores $ [] 1 2 3) (:files $ [] ({} (:data $ [] 16 32)) (%{} :File (:data $ [] 0) (:name |a))) (:nested $ {} (:data $ [] 1 300))",
+  )?;
+  let count = data.normalize_buffers(|k| k.is_tag_named("data"));
+  assert_eq!(count, 3);
+  assert_eq!(data.map_get("data")?, &Edn::Buffer(vec![1, 2, 255]));
+  assert_eq!(data.map_get("scores")?, &Edn::list([1, 2, 3]));
+  // out of byte range is kept as it is
+  assert_eq!(data.map_get("nested")?.map_get("data")?, &Edn::list([1, 300]));
+  assert_eq!(data.select(":files[0].:data")?, Some(&Edn::Buffer(vec![16, 32])));
+  assert_eq!(data.select(":files[1].:data")?, Some(&Edn::Buffer(vec![0])));
+
+  let text = cirru_edn::format(&data.map_get("files")?.list_get(0)?.to_owned(), true)?;
+  assert_eq!(text, "\n{} $ :data (buf 10 20)\n");
+
+  let mut data = cirru_edn::parse("#{} ({} (:data $ [] 1 2))")?;
+  assert_eq!(data.normalize_buffers(|k| k.is_tag_named("data")), 1);
+  assert_eq!(
+    data,
+    Edn::Set(
+      [Edn::map([(Edn::tag("data"), Edn::Buffer(vec![1, 2]))])]
+        .into_iter()
+        .collect()
+    )
+  );
+  Ok(())
+}
+
+#[test]
+fn redact_secrets() -> Result<(), String> {
+  let data = cirru_edn::parse(
+    r#"{} (:password |p1) (:user |u) (|token |t1)
+  :servers $ [] ({} (:host |a) (:password |p2)) (%{} :Conf (:token |t2) (:port 80))
+  :nested $ {} (:deep $ #{} ({} (|token |t3)))
+  :wrapped $ :: :secret ({} (:password $ {} (:token |inner)))
+  :held $ atom $ {} (:password |p4)
+  :Password |case-sensitive"#,
+  )?;
+  let hidden = Edn::str("***");
+  let redacted = data.redact(&["password", "token"], hidden.to_owned());
+
+  let mut counted = data.to_owned();
+  // nested values under a replaced key are not visited
+  assert_eq!(counted.redact_in_place(&["password", "token"], &hidden), 7);
+  assert_eq!(counted, redacted);
+
+  let expected = cirru_edn::parse(
+    r#"{} (:password |***) (:user |u) (|token |***)
+  :servers $ [] ({} (:host |a) (:password |***)) (%{} :Conf (:token |***) (:port 80))
+  :nested $ {} (:deep $ #{} ({} (|token |***)))
+  :wrapped $ :: :secret ({} (:password |***))
+  :held $ atom $ {} (:password |***)
+  :Password |case-sensitive"#,
+  )?;
+  assert_eq!(redacted, expected);
+  assert_ne!(data, redacted);
+  assert_eq!(data.redact(&[], Edn::Nil), data);
+  Ok(())
+}
+
+#[test]
+fn rename_in_quotes() -> Result<(), String> {
+  let api_doc_demo = r#"
+{}
+  :docs $ []
+    {} (:name |add)
+      :code $ quote
+        defn add (a b) (+ a b)
+    {} (:name |twice)
+      :code $ quote
+        defn twice (f x)
+          f (f x)
+  :examples $ #{} (quote (defn id (x) x))
+  :meta $ %{} :Meta (:core $ atom $ quote (defmacro m () (quote defn)))
+  :text |defn
+"#;
+  let mut data = cirru_edn::parse(api_doc_demo)?;
+  assert_eq!(data.replace_quote_leaf("defn", "defn!"), Ok(4));
+  // strings are not quoted code
+  assert_eq!(data.map_get("text")?, &Edn::str("defn"));
+  assert_eq!(
+    data.select(":docs[1].:code")?.unwrap().read_quoted_string()?,
+    "defn! twice (f x) (f (f x))"
+  );
+
+  let text = cirru_edn::format(&data, false)?;
+  assert!(!text.contains("defn "), "{}", text);
+  assert_eq!(text.matches("defn!").count(), 4);
+  assert_eq!(cirru_edn::parse(&text)?, data);
+
+  let mut leaves = 0;
+  data.map_quotes(|code| {
+    if let Cirru::List(xs) = code {
+      leaves += xs.len();
+    }
+  })?;
+  assert_eq!(leaves, 4 + 4 + 4 + 4);
+
+  // keys and items becoming equal are reported, the map or set is kept
+  let original = cirru_edn::parse("{} ((quote a) 1) ((quote b) 2)")?;
+  let mut data = original.to_owned();
+  let e = data.replace_quote_leaf("a", "b").unwrap_err();
+  assert!(
+    e.starts_with("failed to map quotes, `(quote b)` would appear twice in map: "),
+    "{}",
+    e
+  );
+  assert_eq!(data, original);
+  let original = cirru_edn::parse("[] (#{} (quote a) (quote b)) (quote a)")?;
+  let mut data = original.to_owned();
+  assert!(data
+    .replace_quote_leaf("a", "b")
+    .unwrap_err()
+    .contains("would appear twice in set"));
+  let mut data = cirru_edn::parse("{} ((quote a) 1) ((quote c) 2)")?;
+  assert_eq!(data.replace_quote_leaf("a", "b"), Ok(1));
+  assert_eq!(data, cirru_edn::parse("{} ((quote b) 1) ((quote c) 2)")?);
+
+  // nested sets are as deep as hashing allows
+  let mut deep = Edn::quote_from_str("defn f () 1")?;
+  for _ in 0..500 {
+    deep = Edn::Set([Edn::map([(Edn::tag("a"), deep)])].into_iter().collect());
+  }
+  assert_eq!(deep.replace_quote_leaf("defn", "defn!"), Ok(1));
+  let mut x = &deep;
+  for _ in 0..500 {
+    x = match x {
+      Edn::Set(items) => match items.0.iter().next() {
+        Some(Edn::Map(m)) => m.0.get(&Edn::tag("a")).ok_or("missing key")?,
+        _ => return Err(String::from("expected a map in set")),
+      },
+      _ => return Err(String::from("expected a set")),
+    };
+  }
+  assert_eq!(x.read_quoted_string()?, "defn! f () 1");
+  deep.drop_iteratively();
+  Ok(())
+}
+
+#[test]
+fn layout_roundtrip() -> Result<(), String> {
+  for s in [
+    ORDER_DEMO,
+    RECORD_DEMO,
+    DICT_DEMO,
+    DICT_DEMO2,
+    DICT_DEMO_COMMENT,
+    "do 42",
+    "\ndo 1.50\n\n",
+    ":: :a",
+    "[] (:: :a 1)\n\n  ; note\n  [] \"|a b\" 2",
+  ] {
+    let (data, hints) = cirru_edn::parse_with_layout(s)?;
+    assert!(!hints.is_empty(), "{}", s);
+    assert_eq!(cirru_edn::format_with_layout(&data, &hints)?, s);
+  }
+
+  // files written by `format` come back byte for byte, including the space `format` leaves after `$`
+  for s in [
+    ORDER_DEMO,
+    RECORD_DEMO,
+    DICT_DEMO,
+    DICT_DEMO2,
+    DICT_DEMO_COMMENT,
+    "{} (([] 1) ([] 2))",
+  ] {
+    for use_inline in [true, false] {
+      let text = cirru_edn::format(&cirru_edn::parse(s)?, use_inline)?;
+      let (data, hints) = cirru_edn::parse_with_layout(&text)?;
+      assert!(!hints.is_empty(), "{}", text);
+      assert_eq!(cirru_edn::format_with_layout(&data, &hints)?, text);
+    }
+  }
+  Ok(())
+}
+
+#[test]
+fn layout_of_edited_data() -> Result<(), String> {
+  let (data, hints) = cirru_edn::parse_with_layout(DICT_DEMO_COMMENT)?;
+  let mut map = data.view_map()?;
+
+  // only the changed line is written again
+  map.insert(Edn::tag("a"), Edn::Number(2.0));
+  let text = cirru_edn::format_with_layout(&Edn::Map(map.to_owned()), &hints)?;
+  assert_eq!(text, DICT_DEMO_COMMENT.replace(":a 1", ":a 2"));
+
+  // nested values are changed in place, new entries come at the end
+  let mut inner = map.get_or_nil("c").view_map()?;
+  inner.insert(Edn::tag("e"), Edn::Bool(false));
+  map.insert(Edn::tag("c"), Edn::Map(inner));
+  map.insert(Edn::tag("z"), Edn::from(vec![Edn::Number(1.0)]));
+  let text = cirru_edn::format_with_layout(&Edn::Map(map.to_owned()), &hints)?;
+  assert_eq!(
+    text,
+    DICT_DEMO_COMMENT
+      .replace(":e true", ":e false")
+      .replace(":a 1", ":a 2")
+      .trim_end()
+      .to_owned()
+      + "\n  :z $ [] 1\n"
+  );
+  assert_eq!(cirru_edn::parse(&text)?, Edn::Map(map.to_owned()));
+
+  // removed entries take their lines away
+  let data = cirru_edn::parse_with_layout(ORDER_DEMO)?;
+  let mut map = data.0.view_map()?;
+  map.0.remove(&Edn::tag("b"));
+  assert_eq!(
+    cirru_edn::format_with_layout(&Edn::Map(map), &data.1)?,
+    "\n{} (:a 1) (:c 2)\n"
+  );
+
+  // items added after `$` in an inline expression would go into the list of `$`
+  let (data, hints) = cirru_edn::parse_with_layout("\n[] ([] 1 2) ([] 3 4) (:: :a $ [] 1)\n")?;
+  let mut xs = data.view_list()?;
+  match &mut xs.0[2] {
+    Edn::Tuple(t) => t.extra.push(Edn::Number(3.0)),
+    _ => unreachable!(),
+  }
+  let data = Edn::List(xs);
+  let text = cirru_edn::format_with_layout(&data, &hints)?;
+  assert_eq!(cirru_edn::parse(&text)?, data, "{}", text);
+
+  // scalars keep `do`, other data falls back to `format`
+  let (_, hints) = cirru_edn::parse_with_layout("do 1.50")?;
+  assert_eq!(cirru_edn::format_with_layout(&Edn::Number(2.0), &hints)?, "do 2");
+  assert_eq!(
+    cirru_edn::format_with_layout(&Edn::from(vec![Edn::Nil]), &hints)?,
+    cirru_edn::format(&Edn::from(vec![Edn::Nil]), false)?
+  );
+
+  // records keep positions of fields
+  let (data, hints) = cirru_edn::parse_with_layout(RECORD_DEMO)?;
+  let mut record = data.view_record()?;
+  record.pairs[2].1 = Edn::from(vec![Edn::Number(3.0)]);
+  record.insert("d", Edn::Nil);
+  let text = cirru_edn::format_with_layout(&Edn::Record(record.to_owned()), &hints)?;
+  assert_eq!(text, "\n%{} :Demo (:a 1)\n  :b 2\n  :c $ [] 3\n  :d nil\n");
+  assert_eq!(cirru_edn::parse(&text)?, Edn::Record(record));
+  Ok(())
+}
+
+#[test]
+fn common_values_and_defaults() -> Result<(), String> {
+  assert_eq!(Edn::default(), Edn::NIL);
+  assert_eq!(cirru_edn::parse("do true")?, Edn::TRUE);
+  assert_eq!(cirru_edn::parse("do false")?, Edn::FALSE);
+  assert_eq!(cirru_edn::parse("do 0")?, Edn::ZERO);
+  assert_eq!(cirru_edn::parse("do 1")?, Edn::ONE);
+  assert_eq!(cirru_edn::parse("[]")?, Edn::empty_list());
+  assert_eq!(cirru_edn::parse("{}")?, Edn::empty_map());
+  assert_eq!(cirru_edn::parse("#{}")?, Edn::empty_set());
+  assert!(matches!(Edn::ONE, Edn::Number(n) if n == 1.0));
+
+  let atom = EdnAtomView {
+    label: Some("a".into()),
+    ..Default::default()
+  };
+  assert_eq!(Edn::Atom(atom), Edn::atom_labeled("a", Edn::Nil));
+  assert_eq!(Edn::List(EdnListView::default()), Edn::empty_list());
+  Ok(())
+}
+
+#[test]
+fn numeric_grammar() -> Result<(), String> {
+  let accepted = [
+    ("0", 0.0),
+    ("-0", 0.0),
+    ("+0.25", 0.25),
+    ("42", 42.0),
+    ("-17", -17.0),
+    ("2.71", 2.71),
+    (".5", 0.5),
+    ("5.", 5.0),
+    ("1_000_000", 1e6),
+    ("1_000.5", 1000.5),
+    ("1e21", 1e21),
+    ("1E21", 1e21),
+    ("1e-7", 1e-7),
+    ("-2.5e+3", -2500.0),
+    ("6.02214076e23", 6.02214076e23),
+    ("1e308", 1e308),
+    ("5e-324", 5e-324),
+    ("9007199254740993", 9007199254740992.0),
+    ("0.1", 0.1),
+    ("123456789.987654321", 123_456_789.987_654_33),
+    ("inf", f64::INFINITY),
+    ("-inf", f64::NEG_INFINITY),
+  ];
+  for (text, n) in accepted {
+    assert_eq!(cirru_edn::parse_number(text)?, n, "{}", text);
+    let data = cirru_edn::parse(&format!("do {}", text))?;
+    assert_eq!(data, Edn::Number(n));
+    let written = cirru_edn::format(&data, true)?;
+    assert!(!written.contains('e'), "no exponents in {}", written);
+    let back = cirru_edn::parse(&written)?;
+    // bits are compared to tell `-0` from `0`, which is normalized
+    assert_eq!(f64::try_from(back)?.to_bits(), n.to_bits(), "{}", written);
+  }
+  assert_eq!(
+    cirru_edn::format(&Edn::Number(1e21), true)?,
+    "\ndo 1000000000000000000000\n"
+  );
+  assert_eq!(cirru_edn::format(&Edn::Number(-0.0), true)?, "\ndo 0\n");
+  assert!(cirru_edn::parse_number("NaN")?.is_nan());
+  assert!(f64::try_from(cirru_edn::parse(&cirru_edn::format(&Edn::Number(f64::NAN), true)?)?)?.is_nan());
+
+  for text in [
+    "1e", "e5", "1_", "_1", "1__0", "1_e5", "0x10", "--1", "1.2.3", "1 2", "12px", "-",
+  ] {
+    assert!(cirru_edn::parse_number(text).is_err(), "{}", text);
+  }
+  Ok(())
+}
+
+#[test]
+fn type_names_and_tags() -> Result<(), String> {
+  let values = [
+    (Edn::Nil, "nil"),
+    (Edn::Bool(true), "bool"),
+    (Edn::Number(1.0), "number"),
+    (Edn::sym("a"), "symbol"),
+    (Edn::tag("a"), "tag"),
+    (Edn::str("a"), "string"),
+    (cirru_edn::parse("quote (a b)")?, "quote"),
+    (Edn::tuple(Edn::tag("t"), vec![]), "tuple"),
+    (Edn::empty_list(), "list"),
+    (Edn::empty_set(), "set"),
+    (Edn::empty_map(), "map"),
+    (Edn::Record(EdnRecordView::new(EdnTag::new("R"))), "record"),
+    (Edn::Buffer(vec![1]), "buffer"),
+    (Edn::any_ref(1), "any-ref"),
+    (Edn::atom(Edn::Nil), "atom"),
+  ];
+  assert_eq!(values.len(), Edn::TYPE_NAMES.len());
+  for ((data, name), listed) in values.iter().zip(Edn::TYPE_NAMES) {
+    assert_eq!(data.type_name(), *name);
+    assert_eq!(listed, *name);
+    assert_eq!(data.type_tag(), &EdnTag::new(*name));
+    assert!(data.is_type(name));
+    assert!(!data.is_type("other"));
+  }
+  // tags are shared across calls
+  assert!(std::ptr::eq(Edn::Nil.type_tag(), Edn::Nil.type_tag()));
+  Ok(())
+}
+
+#[test]
+fn checked_numbers() -> Result<(), String> {
+  assert_eq!(Edn::number(-0.0)?, Edn::number(0.0)?);
+  assert!(matches!(Edn::number(-0.0)?, Edn::Number(n) if n.is_sign_positive()));
+  assert!(matches!(Edn::from(-0.0), Edn::Number(n) if n.is_sign_negative()));
+  assert_eq!(Edn::number(2.5)?, Edn::Number(2.5));
+  for n in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+    assert!(Edn::number(n).is_err());
+  }
+
+  let map = Edn::map_from_iter([(Edn::number(0.0)?, Edn::str("a")), (Edn::number(-0.0)?, Edn::str("b"))]);
+  assert_eq!(map.view_map()?.len(), 1);
+  // parsed zeros are normalized
+  assert!(matches!(cirru_edn::parse("do -0")?, Edn::Number(n) if n.is_sign_positive()));
+  assert_eq!(
+    cirru_edn::format(&cirru_edn::parse("{} (-0 1) (0 2)")?, true)?,
+    "\n{} $ 0 2\n"
+  );
+  Ok(())
+}
+
+#[test]
+fn symbol_apis() -> Result<(), String> {
+  let data = cirru_edn::parse("{} ('k $ [] 'a 'b) (:k 'v)")?;
+  let map = data.view_map()?;
+  let items = map.get(&Edn::sym("k")).ok_or("missing symbol key")?;
+  assert!(items.iter_list()?.all(|x| x.symbol_name().is_some()));
+  assert_eq!(map.get_or_nil("k").symbol_name(), Some("v"));
+  assert!(map.get_or_nil("k").is_symbol_named("v"));
+  assert_eq!(cirru_edn::parse(&cirru_edn::format(&data, true)?)?, data);
+  assert_eq!(cirru_edn::format(&data, true)?, "\n{} (:k 'v)\n  'k $ [] 'a 'b\n");
+
+  assert_eq!(Edn::tag("v").symbol_name(), None);
+  assert_eq!(Edn::try_sym("v")?, Edn::sym("v"));
+  assert!(Edn::try_sym("").is_err());
+  assert_eq!(
+    cirru_edn::parse("[] 'a '").err(),
+    Some(String::from("expected symbol name after `'`"))
+  );
+
+  // symbols and tags are rejected as strings, unless converted lossily
+  assert!(String::try_from(Edn::sym("v")).is_err());
+  assert!(String::try_from(Edn::tag("v")).is_err());
+  assert_eq!(Edn::sym("v").read_string_lossy()?, "v");
+  assert_eq!(Edn::tag("v").read_string_lossy()?, "v");
+  assert_eq!(Edn::str("v").read_string_lossy()?, "v");
+  assert!(Edn::Nil.read_string_lossy().is_err());
+  Ok(())
+}
+
+fn deep_tree(depth: usize) -> Edn {
+  // a set only at the bottom, since hashing items is recursive too
+  let mut x = Edn::set([Edn::str("leaf")]);
+  for i in 0..depth {
+    x = match i % 5 {
+      0 => Edn::List(EdnListView(vec![x, Edn::Nil])),
+      1 => Edn::map([(Edn::tag("k"), x)]),
+      2 => Edn::Record(EdnRecordView {
+        tag: EdnTag::new("R"),
+        pairs: vec![(EdnTag::new("f"), x)],
+      }),
+      3 => Edn::tuple(Edn::tag("t"), vec![x]),
+      _ => Edn::atom(x),
+    };
+  }
+  x
+}
+
+#[test]
+fn drop_deep_trees() {
+  deep_tree(200_000).drop_iteratively();
+
+  let payload: std::sync::Arc<str> = "shared payload".into();
+  let weak = std::sync::Arc::downgrade(&payload);
+  let mut data = deep_tree(1_000);
+  if let Edn::Atom(atom) = &mut data {
+    *atom.value = Edn::List(EdnListView(vec![Edn::Str(payload), (*atom.value).to_owned()]));
+  }
+  data.drop_in_background().join().expect("join");
+  assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn validate_without_parsing() {
+  let check = |code: &str| {
+    let tree = cirru_parser::parse(code).unwrap();
+    cirru_edn::is_valid_edn(&tree[0])
+  };
+  for code in [
+    "[] 1 2 |a :b 'c nil true",
+    "{} (:a 1) (:b $ [] 2 3)",
+    "#{} 1 2 3",
+    "%{} :Person (:name |Chen) (:age 20)",
+    ":: :point 1 2",
+    "buf 00 ff 7a",
+    "atom 'counter 1",
+    "[] (atom 'a 1) (atom 'a 1) (atom 'b 2)",
+    "do (; comment) ([] |\\u{4e2d})",
+    "quote $ a b c",
+  ] {
+    assert_eq!(check(code), Ok(()), "{}", code);
+    assert!(cirru_edn::parse(code).is_ok());
+  }
+  for code in [
+    "[] 1 (x 2)",
+    "{} (:a 1) (:b $ [] 2 bad)",
+    "{} (:a 1) (([] 1 x) 2)",
+    "#{} 1 ()",
+    "%{} :Person (:name) ",
+    "%{} :Person (:name 1) ((a b) 2)",
+    "buf 00 fff",
+    "buf 00 zz",
+    "atom 1 2",
+    "do 1 2",
+    "quote a b",
+    "[] |\\u{zz}",
+    "[] '",
+    "[] (atom 'a 1) (atom 'a 2)",
+  ] {
+    let result = check(code);
+    assert!(result.is_err(), "{}", code);
+    assert_eq!(result, cirru_edn::parse(code).map(|_| ()), "{}", code);
+  }
+}
+
+#[test]
+fn parse_stats() -> Result<(), String> {
+  let code = r#"
+; "header"
+[] nil 1 |ab 'c (; "skipped")
+  :: :point 1 2
+  %{} :Cat (:name |Mimi) (:toys $ #{} :ball)
+  buf 00 ff
+  atom $ quote $ a b
+"#;
+  let (data, stats) = cirru_edn::parse_with_stats(code)?;
+  assert_eq!(Ok(data), cirru_edn::parse(code));
+  assert_eq!(
+    stats,
+    cirru_edn::EdnParseStats {
+      nils: 1,
+      numbers: 3,
+      symbols: 1,
+      tags: 2,
+      strings: 2,
+      quotes: 1,
+      tuples: 1,
+      lists: 1,
+      sets: 1,
+      records: 1,
+      buffers: 1,
+      atoms: 1,
+      leaves: 9,
+      max_depth: 3,
+      string_bytes: 6,
+      comments: 2,
+      ..Default::default()
+    }
+  );
+
+  let (_, stats) = cirru_edn::parse_with_stats(DICT_DEMO)?;
+  assert_eq!((stats.maps, stats.lists, stats.tags, stats.numbers), (3, 1, 8, 6));
+  assert_eq!((stats.bools, stats.strings, stats.string_bytes), (2, 2, 2));
+  assert_eq!((stats.leaves, stats.max_depth, stats.comments), (18, 3, 0));
+  Ok(())
+}
+
+#[test]
+fn renaming_keys() -> Result<(), String> {
+  let mut data = cirru_edn::parse(
+    r#"
+{}
+  :old-name 1
+  |old-name 2
+  :other $ [] 
+    {} (:old-name :old-name) (:kept |old-name)
+    %{} :Row (:old-name 3) (:id 4)
+  :set $ #{} ({} (:old-name 5))
+"#,
+  )?;
+  let renames = HashMap::from([("old-name", "new-name")]);
+  assert_eq!(data.rename_keys(&renames), Ok(5));
+  let expected = cirru_edn::parse(
+    r#"
+{}
+  :new-name 1
+  |new-name 2
+  :other $ []
+    {} (:new-name :old-name) (:kept |old-name)
+    %{} :Row (:new-name 3) (:id 4)
+  :set $ #{} ({} (:new-name 5))
+"#,
+  )?;
+  assert_eq!(data, expected);
+  assert_eq!(data.rename_keys(&renames), Ok(0));
+
+  // keys swapped in one pass do not collide
+  let mut data = cirru_edn::parse("{} (:a 1) (:b 2)")?;
+  assert_eq!(data.rename_keys(&HashMap::from([("a", "b"), ("b", "a")])), Ok(2));
+  assert_eq!(data, cirru_edn::parse("{} (:b 1) (:a 2)")?);
+
+  let original = cirru_edn::parse("[] ({} (:a 1)) ({} (:a 1) (:b 2))")?;
+  let mut data = original.to_owned();
+  let e = data.rename_keys(&HashMap::from([("a", "b")])).unwrap_err();
+  assert!(
+    e.starts_with("failed to rename keys, `:b` would appear twice in map: "),
+    "{}",
+    e
+  );
+  assert_eq!(data, original);
+
+  let mut data = cirru_edn::parse("%{} :Row (:a 1) (:b 2)")?;
+  assert_eq!(
+    data.rename_keys(&HashMap::from([("a", "b")])),
+    Err(String::from(
+      "failed to rename keys, field `b` would appear twice in record :Row"
+    ))
+  );
+
+  // set items and map keys holding renamed keys must stay distinct too
+  let original = cirru_edn::parse("#{} ({} (:a 1)) ({} (:b 1))")?;
+  let mut data = original.to_owned();
+  let e = data.rename_keys(&HashMap::from([("a", "b")])).unwrap_err();
+  assert!(e.starts_with("failed to rename keys, `({} (:b 1))` would appear twice in set: "));
+  assert_eq!(data, original);
+  let original = cirru_edn::parse("{} (({} (:a 1)) 1) (({} (:b 1)) 2)")?;
+  let mut data = original.to_owned();
+  let e = data.rename_keys(&HashMap::from([("a", "b")])).unwrap_err();
+  assert!(e.starts_with("failed to rename keys, `({} (:b 1))` would appear twice in map: "));
+  assert_eq!(data, original);
+
+  let mut data = cirru_edn::parse("{} (({} (:a 1)) $ #{} ({} (:a 2)) ({} (:b 2)))")?;
+  assert_eq!(data.rename_keys(&HashMap::from([("a", "c")])), Ok(2));
+  assert_eq!(
+    data,
+    cirru_edn::parse("{} (({} (:c 1)) $ #{} ({} (:c 2)) ({} (:b 2)))")?
+  );
+
+  // nested sets are as deep as hashing allows
+  let mut deep = Edn::Nil;
+  for _ in 0..500 {
+    deep = Edn::Set([Edn::map([(Edn::tag("a"), deep)])].into_iter().collect());
+  }
+  assert_eq!(deep.rename_keys(&HashMap::from([("a", "b")])), Ok(500));
+  let mut x = &deep;
+  for _ in 0..500 {
+    x = match x {
+      Edn::Set(items) => match items.0.iter().next() {
+        Some(Edn::Map(m)) => m.0.get(&Edn::tag("b")).ok_or("missing renamed key")?,
+        _ => return Err(String::from("expected a map in set")),
+      },
+      _ => return Err(String::from("expected a set")),
+    };
+  }
+  assert_eq!(x, &Edn::Nil);
+  deep.drop_iteratively();
+  Ok(())
+}
+
+#[test]
+fn sampling_data() -> Result<(), String> {
+  let xs = Edn::from((0..1000).map(|i| i as f64).collect::<Vec<_>>());
+  let sample = xs.sample(5);
+  let items = sample.view_list()?;
+  assert_eq!(items.len(), 6);
+  assert_eq!(items.0[4], Edn::Number(4.0));
+  assert_eq!(items.0[5], Edn::elided(995));
+  assert_eq!(cirru_edn::format(&items.0[5], true)?.trim(), ":: :elided 995");
+
+  let data = cirru_edn::parse(
+    r#"
+{}
+  :rows $ [] ([] 1 2 3) ([] 4) ([] 5 6 7 8)
+  :row $ %{} :Row (:ids $ #{} 1 2 3) (:name |a)
+"#,
+  )?;
+  let expected = cirru_edn::parse(
+    r#"
+{}
+  :rows $ [] ([] 1 2 (:: :elided 1)) ([] 4) (:: :elided 1)
+  :row $ %{} :Row (:ids $ #{} 1 2 3) (:name |a)
+"#,
+  )?;
+  assert_eq!(data.sample(3).map_get("row")?.view_record()?.len(), 2);
+  assert_eq!(data.sample(2).map_get("rows")?, expected.map_get("rows")?);
+  assert_eq!(data.sample(10), data);
+
+  let m = cirru_edn::parse("{} (:a 1) (:b 2) (:c 3)")?.sample(1);
+  assert_eq!(m.view_map()?.len(), 2);
+  assert_eq!(m.view_map()?.get(&Edn::elided(2)), Some(&Edn::Nil));
+
+  // sampled items and keys becoming equal are merged and counted
+  let s = cirru_edn::parse("#{} ([] 1 2) ([] 1 3)")?.sample(1);
+  assert_eq!(s, cirru_edn::parse("#{} ([] 1 (:: :elided 1)) (:: :elided 1)")?);
+  let m = cirru_edn::parse("{} (([] 1 2 3) :x) (([] 1 2 4) :x)")?.sample(2);
+  assert_eq!(m.view_map()?.len(), 2);
+  assert_eq!(m.view_map()?.get(&Edn::elided(1)), Some(&Edn::Nil));
+
+  // markers don't merge into kept items and keys equal to them
+  let s = cirru_edn::parse("#{} (:: :elided 1) ([] 0 1 2 3) ([] 0 1 2 4)")?.sample(3);
+  assert_eq!(
+    s,
+    cirru_edn::parse("#{} (:: :elided 1) ([] 0 1 2 (:: :elided 1)) (:: :elided 1 1)")?
+  );
+  let m = cirru_edn::parse("{} ((:: :elided 1) :kept) (([] 0 1 2 3) :x) (([] 0 1 2 4) :x)")?.sample(3);
+  let m = m.view_map()?;
+  assert_eq!(m.len(), 3);
+  assert_eq!(m.get(&Edn::elided(1)), Some(&Edn::tag("kept")));
+  assert_eq!(m.get(&cirru_edn::parse(":: :elided 1 1")?), Some(&Edn::Nil));
+
+  let deep = deep_tree(200_000);
+  let sample = deep.sample(5);
+  assert!(matches!(sample, Edn::Atom(_)));
+  sample.drop_iteratively();
+  deep.drop_iteratively();
   Ok(())
 }
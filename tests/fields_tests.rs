@@ -0,0 +1,61 @@
+extern crate cirru_edn;
+
+use cirru_edn::{Edn, EdnFields};
+
+#[test]
+fn collecting_all_errors() {
+  let data = cirru_edn::parse(
+    r#"
+{} (:name 1) (:port |80) (:debug true)
+  :db $ %{} :Db (:host |localhost) (:pool :many)
+"#,
+  )
+  .unwrap();
+
+  let mut fields = EdnFields::new(&data);
+  let name: Option<String> = fields.field("name");
+  let port: Option<f64> = fields.field("port");
+  let debug: Option<bool> = fields.field("debug");
+  let db = fields.nested("db", |f| {
+    let host: Option<String> = f.field("host");
+    let pool: Option<f64> = f.field("pool");
+    let timeout: Option<f64> = f.optional("timeout");
+    Some((host?, pool?, timeout))
+  });
+  let user: Option<String> = fields.optional("user");
+
+  assert_eq!(name, None);
+  assert_eq!(port, None);
+  assert_eq!(debug, Some(true));
+  assert_eq!(db, None);
+  assert_eq!(user, None);
+
+  let errors = fields.finish().unwrap_err();
+  let paths = errors.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>();
+  assert_eq!(paths, vec!["name", "port", "db.pool"]);
+}
+
+#[test]
+fn missing_fields() {
+  let data = cirru_edn::parse("{} (|a 1)").unwrap();
+  let mut fields = EdnFields::new(&data);
+  assert_eq!(fields.field::<f64>("a"), Some(1.0));
+  assert_eq!(fields.field::<f64>("b"), None);
+  assert_eq!(fields.nested("c", |f| f.optional::<f64>("d")), None);
+  assert_eq!(
+    fields.errors(),
+    &[
+      (String::from("b"), String::from("missing field")),
+      (String::from("c"), String::from("expected map or record, got nil: nil"))
+    ]
+  );
+
+  let mut fields = EdnFields::new(&Edn::Number(1.0));
+  assert_eq!(fields.optional::<f64>("a"), None);
+  assert_eq!(fields.finish().unwrap_err().len(), 1);
+
+  let data = cirru_edn::parse("%{} :A (:a 1)").unwrap();
+  let mut fields = EdnFields::new(&data);
+  assert_eq!(fields.field::<f64>("a"), Some(1.0));
+  assert!(fields.finish().is_ok());
+}
@@ -0,0 +1,477 @@
+extern crate cirru_edn;
+
+use cirru_edn::{Edn, EdnFormatOptions, EdnRecordView, EdnSetView, EdnTag, MapKeyOrder};
+
+const DICT_DEMO: &str = r#"
+{} (:a 1.0)
+  :b $ [] 2.0 3.0 4.0
+  :c $ {} (:d 4.0)
+    :e true
+    :f :g
+    :h $ {} (|a 1.0)
+      |b true
+"#;
+
+const FIXTURES: [&str; 6] = [
+  r#"
+{} (:a 1) (:c 2)
+  :b $ [] 1 2
+"#,
+  r#"
+%{} :Demo (:a 1)
+  :b 2
+  :c $ [] 1 2 3
+"#,
+  DICT_DEMO,
+  r#"
+{}
+  ([] 1) 2
+  :a $ [] 3
+  |b nil
+  ({} (:x 1)) 4
+  ({} (:y 1)) 5
+  :c :d
+"#,
+  r#"
+#{} ({} (:a 1)) ({} (:b 1)) (#{} 1 2) (#{} 1 3) :x |y 1
+"#,
+  r#"
+[] (:: :t 1 |a) (quote $ a b) (buf 01 02) (atom 1) nil
+"#,
+];
+
+/// simple pseudo random generator, to keep cases reproducible without extra dependencies
+struct Lcg(u64);
+
+impl Lcg {
+  fn next(&mut self, n: u64) -> u64 {
+    self.0 = self
+      .0
+      .wrapping_mul(6364136223846793005)
+      .wrapping_add(1442695040888963407);
+    (self.0 >> 33) % n
+  }
+}
+
+fn gen_literal(r: &mut Lcg) -> Edn {
+  match r.next(6) {
+    0 => Edn::Nil,
+    1 => Edn::Bool(r.next(2) == 0),
+    2 => Edn::Number(r.next(20) as f64 - 5.0),
+    3 => Edn::tag(format!("t{}", r.next(8))),
+    4 => Edn::str(format!("s {}", r.next(8))),
+    _ => Edn::sym(format!("s{}", r.next(8))),
+  }
+}
+
+fn gen_edn(r: &mut Lcg, depth: usize) -> Edn {
+  if depth == 0 {
+    return gen_literal(r);
+  }
+  match r.next(7) {
+    0 => Edn::from((0..r.next(4)).map(|_| gen_edn(r, depth - 1)).collect::<Vec<_>>()),
+    1 => Edn::Set(EdnSetView((0..r.next(4)).map(|_| gen_edn(r, depth - 1)).collect())),
+    2 => Edn::map_from_iter((0..r.next(5)).map(|_| (gen_edn(r, depth - 1), gen_edn(r, depth - 1)))),
+    3 => {
+      let mut record = EdnRecordView::new(EdnTag::new("R"));
+      for i in 0..r.next(4) {
+        record.insert(EdnTag::new(format!("f{}", i)), gen_edn(r, depth - 1));
+      }
+      Edn::Record(record)
+    }
+    4 => Edn::tuple(gen_literal(r), (0..r.next(3)).map(|_| gen_edn(r, depth - 1)).collect()),
+    _ => gen_literal(r),
+  }
+}
+
+fn check_idempotent(data: &Edn) -> Result<(), String> {
+  for use_inline in [true, false] {
+    let first = cirru_edn::format(data, use_inline)?;
+    let parsed = cirru_edn::parse(&first)?;
+    let second = cirru_edn::format(&parsed, use_inline)?;
+    assert_eq!(first, second);
+    // record fields may be reordered at first, but stay after that
+    assert_eq!(cirru_edn::parse(&second)?, parsed, "parsed from:{}", second);
+  }
+  Ok(())
+}
+
+fn check_packed(data: &Edn, max_width: usize) -> Result<(), String> {
+  let options = EdnFormatOptions {
+    use_inline: false,
+    max_width: Some(max_width),
+    ..Default::default()
+  };
+  let first = cirru_edn::format_with_options(data, options)?;
+  let parsed = cirru_edn::parse(&first)?;
+  assert_eq!(cirru_edn::format_with_options(&parsed, options)?, first);
+  assert_eq!(parsed, cirru_edn::parse(&cirru_edn::format(data, false)?)?);
+  Ok(())
+}
+
+fn check_mixed(data: &Edn, width: usize, items: Option<usize>) -> Result<(), String> {
+  let options = EdnFormatOptions {
+    inline_max_width: Some(width),
+    inline_max_items: items,
+    ..Default::default()
+  };
+  let first = cirru_edn::format_with_options(data, options)?;
+  let parsed = cirru_edn::parse(&first)?;
+  assert_eq!(cirru_edn::format_with_options(&parsed, options)?, first);
+  assert_eq!(parsed, cirru_edn::parse(&cirru_edn::format(data, false)?)?, "{}", first);
+  Ok(())
+}
+
+#[test]
+fn fixtures_idempotent() -> Result<(), String> {
+  for s in FIXTURES {
+    check_idempotent(&cirru_edn::parse(s)?)?;
+    check_packed(&cirru_edn::parse(s)?, 8)?;
+    for width in [0, 8, 20, 80] {
+      check_mixed(&cirru_edn::parse(s)?, width, None)?;
+      check_mixed(&cirru_edn::parse(s)?, width, Some(3))?;
+    }
+  }
+  Ok(())
+}
+
+#[test]
+fn random_idempotent() -> Result<(), String> {
+  let mut r = Lcg(20);
+  for _ in 0..400 {
+    let data = gen_edn(&mut r, 4);
+    check_idempotent(&data)?;
+    check_packed(&data, 16)?;
+    check_mixed(&data, 24, Some(6))?;
+  }
+  Ok(())
+}
+
+#[test]
+fn packing_long_lists() -> Result<(), String> {
+  let data = Edn::from((0..1000).map(|i| Edn::Number(i as f64)).collect::<Vec<_>>());
+  let plain = cirru_edn::format(&data, false)?;
+  let packed = cirru_edn::format_with_options(
+    &data,
+    EdnFormatOptions {
+      use_inline: false,
+      max_width: Some(80),
+      ..Default::default()
+    },
+  )?;
+  assert_eq!(cirru_edn::parse(&packed), Ok(data.to_owned()));
+
+  assert_eq!(plain.lines().filter(|l| !l.is_empty()).count(), 1);
+  assert!(packed.lines().count() > 40);
+  // indentations are not counted
+  assert!(packed.lines().all(|l| l.trim_start().len() <= 80), "{}", packed);
+  // only a few bytes added for each line
+  assert!(packed.len() < plain.len() + packed.lines().count() * 4);
+
+  // short lists are kept
+  let short = cirru_edn::parse("[] 1 2 3")?;
+  let options = EdnFormatOptions {
+    use_inline: false,
+    max_width: Some(80),
+    ..Default::default()
+  };
+  assert_eq!(
+    cirru_edn::format_with_options(&short, options)?,
+    cirru_edn::format(&short, false)?
+  );
+  Ok(())
+}
+
+#[test]
+fn canonical_map_order() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:b ([] 1)) (([] 1) 2) (:c 1) (:a ([] 2)) (:d 1)")?;
+  assert_eq!(
+    cirru_edn::format(&data, true)?,
+    "\n{} (:c 1) (:d 1)\n  :a $ [] 2\n  :b $ [] 1\n  ([] 1) 2\n"
+  );
+  Ok(())
+}
+
+#[test]
+fn ordering_collections() {
+  // sets and maps of the same size are compared by sorted items
+  let a = Edn::Set(EdnSetView([Edn::Number(1.0), Edn::Number(2.0)].into_iter().collect()));
+  let b = Edn::Set(EdnSetView([Edn::Number(1.0), Edn::Number(3.0)].into_iter().collect()));
+  assert!(a < b);
+  assert_eq!(a.cmp(&a.to_owned()), std::cmp::Ordering::Equal);
+
+  let m1 = Edn::map_from_iter([(Edn::tag("a"), Edn::Number(1.0))]);
+  let m2 = Edn::map_from_iter([(Edn::tag("b"), Edn::Number(1.0))]);
+  assert!(m1 < m2);
+
+  assert!(Edn::Number(1.0) < Edn::Number(f64::NAN));
+  assert!(Edn::Number(f64::NAN) > Edn::Number(f64::INFINITY));
+}
+
+#[test]
+fn format_sets_of_collections() -> Result<(), String> {
+  let data = cirru_edn::parse("#{} ({} (:b 2)) ({} (:a 1))")?;
+  let text = cirru_edn::format(&data, true)?;
+  assert_eq!(text.trim(), "#{}\n  {} $ :a 1\n  {} $ :b 2");
+  assert_eq!(cirru_edn::parse(&text)?, data);
+
+  let data = cirru_edn::parse("{} (({} (:b 2)) 1) (({} (:a 1)) 2) ((#{} 1 2) 3) ((#{} 1 3) 4)")?;
+  let text = cirru_edn::format(&data, true)?;
+  assert_eq!(cirru_edn::parse(&text)?, data);
+  assert_eq!(cirru_edn::format(&cirru_edn::parse(&text)?, true)?, text);
+
+  let err = cirru_edn::format(&Edn::from(vec![Edn::Set(EdnSetView([Edn::any_ref(1)].into()))]), true).unwrap_err();
+  assert!(err.contains("any-ref is not serializable"), "{}", err);
+  Ok(())
+}
+
+#[test]
+fn format_errors_show_nodes_failing() {
+  // nested after items that fill up the preview of the whole document
+  let mut xs: Vec<Edn> = (0..100).map(|i| Edn::Number(i as f64)).collect();
+  xs.push(Edn::from(vec![Edn::str("x"), Edn::any_ref(1)]));
+  assert_eq!(
+    cirru_edn::format(&Edn::from(xs.to_owned()), true),
+    Err(String::from(
+      "failed to format, any-ref is not serializable: (any-ref ...)"
+    ))
+  );
+
+  xs.pop();
+  xs.push(Edn::atom_labeled("a", Edn::Number(1.0)));
+  xs.push(Edn::from(vec![Edn::atom_labeled("a", Edn::tag("x"))]));
+  assert_eq!(
+    cirru_edn::format(&Edn::from(xs), true),
+    Err(String::from(
+      "failed to format, atoms labeled 'a hold different values: 1 and :x"
+    ))
+  );
+}
+
+#[test]
+fn roundtrip_reports() -> Result<(), String> {
+  for s in FIXTURES {
+    let report = cirru_edn::verify_roundtrip(s)?;
+    assert!(report.values_equal, "{}", s);
+    if report.is_canonical() {
+      assert_eq!(report.first_difference, None);
+    }
+    // formatted text is always canonical
+    assert!(cirru_edn::verify_roundtrip(&report.formatted)?.is_canonical());
+  }
+  assert!(cirru_edn::verify_roundtrip("\n{} (:a 1)\n  :c 2\n  :b $ [] 1 2\n")?.is_canonical());
+  // `:c 2` goes before collections
+  assert_eq!(
+    cirru_edn::verify_roundtrip(FIXTURES[0])?.first_difference,
+    Some((2, 10))
+  );
+
+  let report = cirru_edn::verify_roundtrip("\n{} (:a 1)\n  :b $ [] 1 2\n  :c 2\n")?;
+  assert!(report.values_equal);
+  assert!(report.text_changed);
+  assert_eq!(report.first_difference, Some((3, 4)));
+
+  let report = cirru_edn::verify_roundtrip("[] 1 2")?;
+  assert!(report.is_canonical());
+  let report = cirru_edn::verify_roundtrip("[] 1 2 (; note)")?;
+  assert_eq!(report.first_difference, Some((1, 7)));
+
+  assert!(cirru_edn::verify_roundtrip("[] (").is_err());
+  Ok(())
+}
+
+#[test]
+fn empty_collections_roundtrip() -> Result<(), String> {
+  let cases = [
+    (Edn::Record(EdnRecordView::new(EdnTag::new("Tag"))), "%{} :Tag"),
+    (Edn::map_from_iter([]), "{}"),
+    (Edn::from(Vec::<Edn>::new()), "[]"),
+    (Edn::Set(EdnSetView::default()), "#{}"),
+    (Edn::Buffer(vec![]), "buf"),
+  ];
+  for (data, text) in cases {
+    for use_inline in [true, false] {
+      let formatted = cirru_edn::format(&data, use_inline)?;
+      assert_eq!(formatted.trim(), text);
+      assert_eq!(cirru_edn::parse(&formatted)?, data);
+    }
+  }
+  assert_eq!(
+    cirru_edn::parse("[] (%{} :A) (%{} B)")?,
+    Edn::list([
+      Edn::record_from_pairs(EdnTag::new("A"), &[]),
+      Edn::record_from_pairs(EdnTag::new("B"), &[])
+    ])
+  );
+  // tuples still need a tag
+  assert!(cirru_edn::parse("::").is_err());
+  assert!(cirru_edn::parse("%{}").is_err());
+  Ok(())
+}
+
+#[test]
+fn inline_thresholds() -> Result<(), String> {
+  let data = cirru_edn::parse(DICT_DEMO)?;
+  let mixed = |width, items| {
+    cirru_edn::format_with_options(
+      &data,
+      EdnFormatOptions {
+        inline_max_width: Some(width),
+        inline_max_items: items,
+        ..Default::default()
+      },
+    )
+  };
+  let inline = cirru_edn::format(&data, true)?;
+  let multiline = cirru_edn::format(&data, false)?;
+  let mixed_40 = mixed(40, None)?;
+
+  assert_eq!(
+    mixed_40,
+    "\n{} (:a 1) (:b ([] 2 3 4))\n  :c $ {} (:d 4) (:e true) (:f :g)\n    :h $ {} (|a 1) (|b true)\n"
+  );
+  // small subtrees are kept in lines, large ones are broken
+  let lines = |s: &str| s.trim().lines().count();
+  assert!(lines(&mixed_40) < lines(&inline), "{}", inline);
+  assert!(lines(&inline) < lines(&multiline));
+  // everything fits in one line
+  assert_eq!(mixed(200, None)?.trim().lines().count(), 1);
+  // fewer leaves allowed in a line
+  assert!(lines(&mixed(40, Some(4))?) > lines(&mixed_40));
+  // everything broken
+  assert!(lines(&mixed(0, None)?) >= lines(&multiline));
+  for s in [inline, multiline, mixed_40, mixed(0, None)?, mixed(10, Some(2))?] {
+    assert_eq!(cirru_edn::parse(&s)?, data, "{}", s);
+  }
+
+  // long runs of leaves are wrapped in `,` lines
+  let data = Edn::from((0..100).map(|i| Edn::Number(i as f64)).collect::<Vec<_>>());
+  let text = cirru_edn::format_with_options(
+    &data,
+    EdnFormatOptions {
+      inline_max_width: Some(40),
+      ..Default::default()
+    },
+  )?;
+  assert!(text.lines().all(|l| l.trim_start().len() <= 40), "{}", text);
+  assert_eq!(cirru_edn::parse(&text)?, data);
+  Ok(())
+}
+
+#[test]
+fn stable_tuple_layout() -> Result<(), String> {
+  let cases = [
+    // a trailing collection is always after `$`
+    (":: :a ({} (:x 1))", ":: :a $ {} $ :x 1"),
+    (
+      "{} (:j 2) (:k (:: :a ({} (:x 1))))",
+      "{} (:j 2)\n  :k $ :: :a $ {} (:x 1)",
+    ),
+    ("[] 1 (:: :a ({} (:x 1)))", "[] 1 $ :: :a $ {} (:x 1)"),
+    // collections followed by other extras are in lines
+    (":: :a ({} (:x 1)) 1", ":: :a\n  {} $ :x 1\n  , 1"),
+    (
+      "{} (:j 2) (:k (:: :a ({} (:x 1)) 1))",
+      "{} (:j 2)\n  :k $ :: :a\n    {} $ :x 1\n    , 1",
+    ),
+    // nested tuples
+    ("[] (:: :a (:: :b ({} (:x 1))))", "[] $ :: :a $ :: :b $ {} (:x 1)"),
+    (
+      "[] (:: :a 1 ({} (:x ([] 1 2))) (:: :b ({} (:y 2)) 3))",
+      "[] $ :: :a 1\n  {} $ :x ([] 1 2)\n  :: :b\n    {} $ :y 2\n    , 3",
+    ),
+  ];
+  for (s, expected) in cases {
+    let data = cirru_edn::parse(s)?;
+    for use_inline in [true, false] {
+      let text = cirru_edn::format(&data, use_inline)?;
+      assert_eq!(text.trim(), expected);
+      assert_eq!(cirru_edn::parse(&text)?, data);
+    }
+    check_packed(&data, 4)?;
+  }
+  Ok(())
+}
+
+#[test]
+fn random_layout_edits() -> Result<(), String> {
+  let mut r = Lcg(7);
+  for _ in 0..300 {
+    let a = gen_edn(&mut r, 3);
+    let b = gen_edn(&mut r, 3);
+    let text = cirru_edn::format(&a, r.next(2) == 0)?;
+    let (parsed, hints) = cirru_edn::parse_with_layout(&text)?;
+    assert!(!hints.is_empty(), "{}", text);
+    // text written by `format` comes back as it is
+    assert_eq!(cirru_edn::format_with_layout(&parsed, &hints)?, text);
+    // the other data, and data edited in one place
+    let mut edited = parsed.to_owned();
+    if let Edn::Map(m) = &mut edited {
+      m.insert(gen_literal(&mut r), b.to_owned());
+    }
+    for data in [b, edited] {
+      let written = cirru_edn::format_with_layout(&data, &hints)?;
+      // record fields keep their order in lines, compared after formatting
+      let normalize = |x: &Edn| cirru_edn::parse(&cirru_edn::format(x, false)?);
+      let parsed = cirru_edn::parse(&written)?;
+      assert_eq!(normalize(&parsed)?, normalize(&data)?, "from:{}\nto:{}", text, written);
+    }
+  }
+  Ok(())
+}
+
+#[test]
+fn map_key_orders() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:b 1) (|b 2) (:a $ [] 3) (|a 4) (5 6) (:c 7)")?;
+  // keys of entries as written, read from the Cirru tree
+  let keys_in = |text: &str| -> Vec<String> {
+    match &cirru_parser::parse(text).expect("parse cirru")[..] {
+      [cirru_parser::Cirru::List(xs)] => xs[1..]
+        .iter()
+        .map(|entry| match entry {
+          cirru_parser::Cirru::List(ys) => ys[0].to_string(),
+          leaf => leaf.to_string(),
+        })
+        .collect(),
+      _ => vec![],
+    }
+  };
+  let write = |order: MapKeyOrder| {
+    cirru_edn::format_with_options(
+      &data,
+      EdnFormatOptions {
+        map_key_order: order,
+        ..Default::default()
+      },
+    )
+  };
+
+  let sorted = write(MapKeyOrder::Sorted)?;
+  assert_eq!(sorted, cirru_edn::format(&data, false)?);
+  assert_eq!(sorted, "\n{} (5 6)\n  :b 1\n  :c 7\n  |a 4\n  |b 2\n  :a $ [] 3\n");
+
+  let grouped = write(MapKeyOrder::GroupedByKind { tags_first: true })?;
+  let keys = keys_in(&grouped);
+  assert_eq!(keys.len(), 6, "{}", grouped);
+  assert!(keys[..3].iter().all(|k| k.starts_with(':')), "{}", grouped);
+  assert!(keys[3..5].iter().all(|k| k.starts_with('|')), "{}", grouped);
+  assert_eq!(keys[5], "5");
+
+  let grouped = write(MapKeyOrder::GroupedByKind { tags_first: false })?;
+  let keys = keys_in(&grouped);
+  assert!(keys[..2].iter().all(|k| k.starts_with('|')), "{}", grouped);
+  assert!(keys[2..5].iter().all(|k| k.starts_with(':')), "{}", grouped);
+
+  // iteration order of the map
+  let iterated = write(MapKeyOrder::Insertion)?;
+  let expected = data.view_map()?.keys().map(|k| k.to_string()).collect::<Vec<_>>();
+  assert_eq!(
+    keys_in(&iterated),
+    expected.iter().map(|k| k.replace('"', "")).collect::<Vec<_>>()
+  );
+
+  for text in [grouped, iterated] {
+    assert_eq!(cirru_edn::parse(&text)?, data);
+  }
+  Ok(())
+}
@@ -3,7 +3,7 @@ extern crate cirru_edn;
 use std::convert::TryFrom;
 use std::{collections::HashMap, convert::TryInto, iter::FromIterator};
 
-use cirru_edn::{Edn, EdnMapView, EdnTag};
+use cirru_edn::{Edn, EdnListView, EdnMapView, EdnSetView, EdnTag};
 
 struct Cat {
   name: String,
@@ -40,7 +40,7 @@ impl TryFrom<Edn> for Cat {
 
 impl From<Cat> for Edn {
   fn from(x: Cat) -> Edn {
-    Edn::Map(EdnMapView(HashMap::from_iter([
+    Edn::Map(EdnMapView::from(HashMap::from_iter([
       ("name".into(), x.name.into()),
       ("category".into(), x.category.into()),
       ("weight".into(), x.weight.into()),
@@ -54,7 +54,7 @@ impl From<Cat> for Edn {
 
 #[test]
 fn from_to_test() -> Result<(), String> {
-  let data: Edn = Edn::Map(EdnMapView(HashMap::from_iter([
+  let data: Edn = Edn::Map(EdnMapView::from(HashMap::from_iter([
     ("name".into(), Edn::str("Kii")),
     ("category".into(), Edn::tag("ying")),
     ("weight".into(), Edn::Number(1.0)),
@@ -64,7 +64,7 @@ fn from_to_test() -> Result<(), String> {
     ),
     (
       "counts".into(),
-      Edn::Map(EdnMapView(HashMap::from_iter([("a".into(), Edn::Number(1.))]))),
+      Edn::Map(EdnMapView::from(HashMap::from_iter([("a".into(), Edn::Number(1.))]))),
     ),
     ("injection_times".into(), Edn::Number(10.0)),
     // ("owner".into(), Edn::str("Kii")),
@@ -76,3 +76,219 @@ fn from_to_test() -> Result<(), String> {
   assert_eq!(data2.view_map()?.get_or_nil("name"), Edn::str("Kii"));
   Ok(())
 }
+
+#[test]
+fn collection_constructors() -> Result<(), String> {
+  assert_eq!(Edn::list([1, 2, 3]), cirru_edn::parse("[] 1 2 3")?);
+  assert_eq!(Edn::list(["a", "b"]), cirru_edn::parse("[] |a |b")?);
+  assert_eq!(Edn::list(Vec::<Edn>::new()), cirru_edn::parse("[]")?);
+  assert_eq!(
+    Edn::list([Edn::from(1), Edn::from("a"), Edn::from(true), Edn::Nil]),
+    cirru_edn::parse("[] 1 |a true nil")?
+  );
+  assert_eq!(Edn::set([1.5, 2.5, 1.5]), cirru_edn::parse("#{} 1.5 2.5")?);
+  assert_eq!(Edn::map([("a", 1), ("b", 2)]), cirru_edn::parse("{} (|a 1) (|b 2)")?);
+  assert_eq!(
+    Edn::map([(EdnTag::new("a"), Edn::list([1])), (EdnTag::new("b"), Edn::from("x"))]),
+    cirru_edn::parse("{} (:a $ [] 1) (:b |x)")?
+  );
+  assert_eq!(Edn::map([("a", 1), ("a", 2)]), Edn::map([("a", 2)]));
+
+  let xs: Vec<i64> = vec![4, 5];
+  assert_eq!(Edn::list(&xs), Edn::list([4, 5]));
+  Ok(())
+}
+
+#[test]
+fn arc_str_shared() -> Result<(), String> {
+  let name: std::sync::Arc<str> = "interned".into();
+  let data = Edn::from(&name);
+  let back: std::sync::Arc<str> = data.try_into()?;
+  assert!(std::sync::Arc::ptr_eq(&name, &back));
+
+  let data = Edn::from(name.to_owned());
+  assert!(std::sync::Arc::ptr_eq(&name, &data.read_str()?));
+  Ok(())
+}
+
+#[test]
+fn collecting_into_edn() {
+  let xs = (0..3).map(|i| Edn::Number(i as f64)).collect::<Edn>();
+  assert_eq!(xs, Edn::from(vec![0.0, 1.0, 2.0]));
+
+  let m = (0..3).map(|i| (Edn::Number(i as f64), Edn::tag("x"))).collect::<Edn>();
+  assert_eq!(m.view_map().map(|m| m.len()), Ok(3));
+  assert_eq!(
+    m.view_map().map(|m| m.get(&Edn::Number(1.0)).cloned()),
+    Ok(Some(Edn::tag("x")))
+  );
+
+  let mut list = ["a", "b"].iter().map(|s| Edn::str(*s)).collect::<EdnListView>();
+  list.extend([Edn::str("c")]);
+  assert_eq!(Edn::from(list), cirru_edn::parse("[] |a |b |c").unwrap());
+
+  let mut set = [1.0, 1.0, 2.0].iter().map(Edn::from).collect::<EdnSetView>();
+  set.extend([Edn::Number(3.0), Edn::Number(2.0)]);
+  assert_eq!(Edn::from(set), cirru_edn::parse("#{} 1 2 3").unwrap());
+
+  let mut map = [(Edn::tag("a"), Edn::Nil)].into_iter().collect::<EdnMapView>();
+  map.extend([(Edn::tag("a"), Edn::Bool(true)), (Edn::tag("b"), Edn::Nil)]);
+  assert_eq!(Edn::from(map), cirru_edn::parse("{} (:a true) (:b nil)").unwrap());
+}
+
+#[test]
+fn tag_keyed_map_roundtrip() -> Result<(), String> {
+  let scores = HashMap::from([(EdnTag::new("a"), 1.0), (EdnTag::new("b"), 2.5)]);
+  let text = cirru_edn::format(&Edn::from(scores.to_owned()), true)?;
+  let data = cirru_edn::parse(&text)?;
+  assert_eq!(data.view_map()?.get(&Edn::tag("a")), Some(&Edn::Number(1.0)));
+  assert_eq!(HashMap::<EdnTag, f64>::try_from(data.to_owned())?, scores);
+  // string keys are not read as tags
+  assert!(HashMap::<EdnTag, f64>::try_from(cirru_edn::parse("{} (|a 1)")?).is_err());
+  assert_eq!(
+    HashMap::<String, f64>::try_from(cirru_edn::parse("{} (|a 1)")?)?,
+    HashMap::from([(String::from("a"), 1.0)])
+  );
+  Ok(())
+}
+
+#[test]
+fn reading_numbers_as() {
+  fn check<T: cirru_edn::TryFromF64 + PartialEq + std::fmt::Debug>(ok: &[(f64, T)], bad: &[f64]) {
+    for (n, expected) in ok {
+      assert_eq!(Edn::Number(*n).read_number_as::<T>().as_ref(), Ok(expected), "{}", n);
+    }
+    for n in bad {
+      assert_eq!(
+        Edn::Number(*n).read_number_as::<T>(),
+        Err(format!("expected {}-compatible number, got {}", T::NAME, n))
+      );
+    }
+  }
+  let pow2 = |e: i32| 2f64.powi(e);
+  check::<i8>(
+    &[(-128.0, -128), (127.0, 127), (-0.0, 0)],
+    &[-129.0, 128.0, 1.5, f64::NAN],
+  );
+  check::<u8>(&[(0.0, 0), (255.0, 255)], &[-1.0, 256.0, 0.5]);
+  check::<i16>(&[(-32768.0, i16::MIN), (32767.0, i16::MAX)], &[-32769.0, 32768.0]);
+  check::<u16>(&[(65535.0, u16::MAX)], &[65536.0, 70000.0]);
+  check::<i32>(
+    &[(-pow2(31), i32::MIN), (pow2(31) - 1.0, i32::MAX)],
+    &[pow2(31), -pow2(31) - 1.0],
+  );
+  check::<u32>(&[(pow2(32) - 1.0, u32::MAX)], &[pow2(32), -1.0]);
+  check::<i64>(
+    &[(-pow2(63), i64::MIN), (pow2(62), 1 << 62)],
+    &[pow2(63), 0.25, f64::INFINITY],
+  );
+  check::<u64>(&[(pow2(63), 1 << 63)], &[pow2(64), -1.0]);
+  check::<i128>(&[(-pow2(127), i128::MIN)], &[pow2(127)]);
+  check::<u128>(&[(pow2(127), 1 << 127)], &[pow2(128), -1.0]);
+  check::<isize>(&[(-1.0, -1)], &[0.1]);
+  check::<usize>(&[(3.0, 3)], &[-3.0]);
+  check::<f32>(&[(0.1, 0.1), (f64::INFINITY, f32::INFINITY)], &[1e39, -1e39]);
+  check::<f64>(&[(1e300, 1e300)], &[]);
+
+  assert_eq!(
+    Edn::str("1").read_number_as::<u8>(),
+    Err(String::from("failed to convert to number: |1"))
+  );
+  assert_eq!(
+    u16::try_from(Edn::Number(70000.0)),
+    Err(String::from("expected u16-compatible number, got 70000"))
+  );
+  assert_eq!(Vec::<u32>::try_from(Edn::from(vec![1.0, 2.0])), Ok(vec![1, 2]));
+}
+
+#[test]
+fn reading_names_with_kinds() -> Result<(), String> {
+  use cirru_edn::NameKind;
+
+  assert_eq!(Edn::str("a").read_name()?, (NameKind::Str, "a"));
+  assert_eq!(Edn::tag("a").read_name()?, (NameKind::Tag, "a"));
+  assert_eq!(Edn::sym("a").read_name()?, (NameKind::Symbol, "a"));
+  assert_eq!(
+    Edn::Number(1.0).read_name(),
+    Err(String::from("failed to read name from: 1"))
+  );
+  for kind in [NameKind::Str, NameKind::Tag, NameKind::Symbol] {
+    assert_eq!(kind.to_edn("a").read_name()?, (kind, "a"));
+  }
+
+  // only strings convert to String, tags and symbols are told apart
+  assert_eq!(String::try_from(Edn::str("a"))?, "a");
+  assert_eq!(std::sync::Arc::<str>::try_from(Edn::str("a"))?.as_ref(), "a");
+  for x in [Edn::tag("a"), Edn::sym("a"), Edn::Nil] {
+    assert!(String::try_from(&x).is_err());
+    assert!(std::sync::Arc::<str>::try_from(x).is_err());
+  }
+  // reading lossily accepts all kinds, which drops the kind
+  for x in [Edn::str("a"), Edn::tag("a"), Edn::sym("a")] {
+    assert_eq!(x.read_string_lossy()?, "a");
+  }
+  // tags give their own string without copying
+  let tag = Edn::tag("a");
+  assert!(std::sync::Arc::ptr_eq(&tag.read_tag_str()?, &tag.read_tag_str()?));
+
+  // prefixing keys keeps them as tags, strings or symbols when written back
+  let data = cirru_edn::parse("{} (:a 1) (|b 2) ('c 3)")?;
+  let prefixed = data
+    .view_map()?
+    .iter()
+    .map(|(k, v)| {
+      let (kind, name) = k.read_name()?;
+      Ok((kind.to_edn(&format!("x-{name}")), v.to_owned()))
+    })
+    .collect::<Result<Edn, String>>()?;
+  assert_eq!(prefixed, cirru_edn::parse("{} (:x-a 1) (|x-b 2) ('x-c 3)")?);
+  let text = cirru_edn::format(&prefixed, true)?;
+  assert_eq!(cirru_edn::parse(&text)?, prefixed);
+
+  let lossy = data
+    .view_map()?
+    .iter()
+    .map(|(k, v)| Ok((Edn::str(format!("x-{}", k.read_string_lossy()?)), v.to_owned())))
+    .collect::<Result<Edn, String>>()?;
+  assert_ne!(lossy, prefixed);
+  Ok(())
+}
+
+#[test]
+fn bytes_from_buffers() -> Result<(), String> {
+  struct P {
+    data: Vec<u8>,
+    key: [u8; 4],
+  }
+
+  impl TryFrom<Edn> for P {
+    type Error = String;
+    fn try_from(value: Edn) -> Result<Self, Self::Error> {
+      let m = value.view_map()?;
+      Ok(P {
+        data: m.get_or_nil("data").try_into()?,
+        key: m.get_or_nil("key").try_into()?,
+      })
+    }
+  }
+
+  let p = P::try_from(cirru_edn::parse("{} (:data $ buf 01 ff) (:key $ buf 0a 0b 0c 0d)")?)?;
+  assert_eq!(p.data, vec![1, 255]);
+  assert_eq!(p.key, [10, 11, 12, 13]);
+
+  // lists of numbers are read the same way
+  let p = P::try_from(cirru_edn::parse("{} (:data $ [] 1 255) (:key $ [] 10 11 12 13)")?)?;
+  assert_eq!((p.data, p.key), (vec![1, 255], [10, 11, 12, 13]));
+
+  assert_eq!(
+    P::try_from(cirru_edn::parse("{} (:data $ buf) (:key $ buf 0a 0b 0c)")?).err(),
+    Some(String::from("failed to convert to array of 4 items, got 3"))
+  );
+  assert_eq!(
+    <[u8; 2]>::try_from(Edn::Nil),
+    Err(String::from("failed to convert to array: nil"))
+  );
+  assert_eq!(Vec::<i64>::try_from(Edn::Buffer(vec![7]))?, vec![7]);
+  assert!(Vec::<String>::try_from(Edn::Buffer(vec![7])).is_err());
+  Ok(())
+}
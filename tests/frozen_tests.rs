@@ -0,0 +1,49 @@
+extern crate cirru_edn;
+
+use std::thread;
+
+use cirru_edn::{Edn, EdnListView, EdnMapView, FrozenEdn};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn freezing() -> Result<(), String> {
+  assert_send_sync::<FrozenEdn>();
+
+  let frozen = cirru_edn::parse("{} (:a $ [] 1 2) (:b $ atom 'x 1)")?.freeze()?;
+  assert_eq!(frozen.map_get("a")?.list_get(1)?, &Edn::Number(2.0));
+  assert_eq!(frozen.select(":a[0]")?, Some(&Edn::Number(1.0)));
+
+  let shared = frozen.to_owned();
+  let handle = thread::spawn(move || shared.map_get("a").map(|x| x.to_owned()));
+  assert_eq!(handle.join().unwrap()?, cirru_edn::parse("[] 1 2")?);
+
+  let mut thawed = frozen.thaw();
+  if let Edn::Map(xs) = &mut thawed {
+    xs.insert(Edn::tag("c"), Edn::Nil);
+  }
+  assert_ne!(&thawed, frozen.as_edn());
+  Ok(())
+}
+
+#[test]
+fn freezing_rejects_any_ref() {
+  assert!(Edn::any_ref(1).freeze().is_err());
+  let nested = Edn::from(vec![Edn::map_from_iter([(Edn::tag("a"), Edn::any_ref(1))])]);
+  assert!(nested.freeze().is_err());
+  assert!(FrozenEdn::try_from(Edn::atom(Edn::any_ref(1))).is_err());
+}
+
+#[test]
+fn cheap_cloning() -> Result<(), String> {
+  let mut xs = EdnListView::default();
+  for i in 0..100_000 {
+    let mut m = EdnMapView::default();
+    m.insert_key("id", Edn::Number(i as f64));
+    xs.push(Edn::Map(m));
+  }
+  let frozen = Edn::List(xs).freeze()?;
+  let copies = (0..1000).map(|_| frozen.to_owned()).collect::<Vec<_>>();
+  assert!(copies.iter().all(|x| FrozenEdn::ptr_eq(x, &frozen)));
+  Ok(())
+}
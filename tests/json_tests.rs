@@ -0,0 +1,94 @@
+#![cfg(feature = "json")]
+
+extern crate cirru_edn;
+
+use cirru_edn::{edn_to_json, json_to_edn, Edn};
+use serde_json::{json, Value};
+
+#[test]
+fn json_roundtrip() -> Result<(), String> {
+  let value = json!({
+    "name": "demo",
+    "count": 3,
+    "ratio": 0.5,
+    "negative": -7,
+    "ok": true,
+    "missing": null,
+    "items": [1, "two", [3.25], {"nested": false}],
+    "empty": {}
+  });
+  let data: Edn = value.clone().into();
+  assert_eq!(data.select("name")?, Some(&Edn::str("demo")));
+  assert_eq!(data.select("items[2][0]")?, Some(&Edn::Number(3.25)));
+  assert_eq!(data.select("missing")?, Some(&Edn::Nil));
+  assert!(data.view_map()?.contains_key("count"));
+  assert_eq!(data.view_map()?.get(&Edn::tag("count")), None);
+
+  // through Cirru text and back
+  let text = cirru_edn::format(&data, false)?;
+  let back = Value::try_from(cirru_edn::parse(&text)?)?;
+  assert_eq!(back, value);
+  assert!(back["count"].is_i64());
+  assert!(back["ratio"].is_f64());
+
+  for x in [
+    json!(null),
+    json!(true),
+    json!(1),
+    json!(-1.5),
+    json!("a"),
+    json!([]),
+    json!({}),
+  ] {
+    assert_eq!(edn_to_json(&json_to_edn(&x)), Ok(x));
+  }
+  Ok(())
+}
+
+#[test]
+fn edn_to_json_values() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:tag :a) (:set $ #{} 1) (:row $ %{} :Row (:id 1) (:name |a))")?;
+  let v = Value::try_from(&data)?;
+  assert_eq!(v, json!({":tag": ":a", ":set": [1], ":row": {"id": 1, "name": "a"}}));
+  Ok(())
+}
+
+#[test]
+fn json_unsupported_values() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (|a $ {} (|b $ [] 1 (atom 2)))")?;
+  assert_eq!(
+    edn_to_json(&data).unwrap_err(),
+    "failed to convert atom to json at .|a.|b[1]"
+  );
+  let data = Edn::from(vec![Edn::Nil, Edn::any_ref(1_i32)]);
+  assert_eq!(
+    Value::try_from(data).unwrap_err(),
+    "failed to convert any-ref to json at [1]"
+  );
+  assert!(edn_to_json(&cirru_edn::parse("[] (buf 01)")?).is_err());
+  assert!(edn_to_json(&cirru_edn::parse("[] (:: :point 1 2)")?).is_err());
+  assert!(edn_to_json(&Edn::Number(f64::INFINITY))
+    .unwrap_err()
+    .contains("got inf"));
+  assert!(edn_to_json(&cirru_edn::parse("{} (1 2)")?)
+    .unwrap_err()
+    .contains("key is not a string: 1"));
+
+  // string keys written like tags
+  let e = edn_to_json(&cirru_edn::parse("{} (|:a 1) (:a 2)")?).unwrap_err();
+  assert!(
+    e.starts_with("failed to convert map to json at top level, key "),
+    "{}",
+    e
+  );
+  assert!(e.ends_with(" collides with another key as \":a\""), "{}", e);
+
+  // set items in paths are previewed
+  let mut items = (0..1000).map(Edn::from).collect::<Vec<_>>();
+  items.push(Edn::atom(Edn::Nil));
+  let data = Edn::Set([Edn::from(items)].into_iter().collect());
+  let e = edn_to_json(&data).unwrap_err();
+  assert!(e.starts_with("failed to convert atom to json at #([] 0 1 2"), "{}", e);
+  assert!(e.len() < 160, "{}", e);
+  Ok(())
+}
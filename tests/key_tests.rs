@@ -0,0 +1,44 @@
+extern crate cirru_edn;
+
+use cirru_edn::{Edn, EdnKey, EdnMapView, EdnStrictMapView};
+
+#[test]
+fn rejecting_keys() {
+  assert!(EdnKey::new(Edn::tag("a")).is_ok());
+  assert!(EdnKey::new(Edn::from(vec![Edn::Number(1.0), Edn::str("b")])).is_ok());
+
+  assert!(EdnKey::new(Edn::any_ref(1)).is_err());
+  assert!(EdnKey::new(Edn::atom(Edn::Nil)).is_err());
+  // nested references are rejected too
+  assert!(EdnKey::new(Edn::from(vec![Edn::atom(Edn::Nil)])).is_err());
+  assert!(EdnKey::new(Edn::tuple(Edn::tag("t"), vec![Edn::any_ref(1)])).is_err());
+}
+
+#[test]
+fn strict_map_lookup() -> Result<(), String> {
+  let mut map = EdnStrictMapView::default();
+  map.insert(EdnKey::new(Edn::tag("a"))?, Edn::Number(1.0));
+  map.try_insert(Edn::str("b"), Edn::Number(2.0))?;
+  assert!(map.try_insert(Edn::any_ref(1), Edn::Nil).is_err());
+
+  // lookup by `&Edn` through `Borrow`
+  assert_eq!(map.get(&Edn::tag("a")), Some(&Edn::Number(1.0)));
+  assert_eq!(map.0.get(&Edn::str("b")), Some(&Edn::Number(2.0)));
+  assert_eq!(map.get(&Edn::str("a")), None);
+  assert_eq!(map.get_or_nil("a"), Edn::Number(1.0));
+  assert_eq!(map.get_or_nil("c"), Edn::Nil);
+  assert_eq!(map.len(), 2);
+  Ok(())
+}
+
+#[test]
+fn strict_map_conversion() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:a 1) (|b 2)")?;
+  let strict = EdnStrictMapView::try_from(data.to_owned())?;
+  assert_eq!(Edn::from(strict), data);
+
+  let mut invalid = EdnMapView::default();
+  invalid.insert(Edn::atom(Edn::Nil), Edn::Nil);
+  assert!(EdnStrictMapView::try_from(invalid).is_err());
+  Ok(())
+}
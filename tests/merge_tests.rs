@@ -0,0 +1,80 @@
+extern crate cirru_edn;
+
+use cirru_edn::{merge_parse, Edn, MergeSource};
+
+#[test]
+fn layered_config() -> Result<(), String> {
+  let base = r#"
+{}
+  :server $ {} (:host |localhost) (:port 80)
+    :tls $ {} (:enabled false) (:cert nil)
+  :features $ [] :a :b
+  :user $ %{} :User (:name |guest) (:level 1)
+"#;
+  let overlays = [
+    "{} (:server $ {} (:tls $ {} (:enabled true)))",
+    "{} (:features $ [] :c) (:user $ %{} :User (:level 2) (:email |x@y))",
+    "{} (:server $ {} (:port 8080) (:tls $ {} (:enabled false))) (|env |prod)",
+  ];
+  let (data, provenance) = merge_parse(base, &overlays)?;
+
+  let expected = cirru_edn::parse(
+    r#"
+{}
+  :server $ {} (:host |localhost) (:port 8080)
+    :tls $ {} (:enabled false) (:cert nil)
+  :features $ [] :c
+  :user $ %{} :User (:name |guest) (:level 2) (:email |x@y)
+  |env |prod
+"#,
+  )?;
+  assert_eq!(data, expected);
+
+  use MergeSource::*;
+  // set by overlay 0 and then again by overlay 2
+  assert_eq!(provenance.source_of(&["server", "tls", "enabled"]), Some(Overlay(2)));
+  assert_eq!(provenance.source_of(&["server", "tls", "cert"]), Some(Base));
+  assert_eq!(provenance.source_of(&["server", "tls"]), Some(Overlay(2)));
+  assert_eq!(provenance.source_of(&["server", "host"]), Some(Base));
+  assert_eq!(provenance.source_of(&["features"]), Some(Overlay(1)));
+  assert_eq!(provenance.source_of(&["features", "0"]), Some(Overlay(1)));
+  assert_eq!(provenance.source_of(&["user", "name"]), Some(Base));
+  assert_eq!(provenance.source_of(&["user", "email"]), Some(Overlay(1)));
+  assert_eq!(provenance.source_of(&["env"]), Some(Overlay(2)));
+  assert_eq!(provenance.source_of(&["server", "missing"]), None);
+  assert_eq!(provenance.source_of(&[]), Some(Overlay(2)));
+  assert_eq!(provenance.source(), Overlay(2));
+  assert_eq!(
+    format!("port is from {}", provenance.source_of(&["server", "port"]).unwrap()),
+    "port is from overlay 2"
+  );
+  Ok(())
+}
+
+#[test]
+fn replacing_values() -> Result<(), String> {
+  // records of other names and values of other types are replaced as a whole
+  let (data, provenance) = merge_parse(
+    "{} (:a $ %{} :A (:x 1) (:y 2)) (:b $ {} (:c 1))",
+    &["{} (:a $ %{} :B (:x 3)) (:b 4)"],
+  )?;
+  assert_eq!(data, cirru_edn::parse("{} (:a $ %{} :B (:x 3)) (:b 4)")?);
+  assert_eq!(provenance.source_of(&["a", "y"]), None);
+  assert_eq!(provenance.source_of(&["b", "c"]), Some(MergeSource::Overlay(0)));
+
+  let (data, provenance) = merge_parse("[] 1 2", &[])?;
+  assert_eq!(data, Edn::from(vec![Edn::Number(1.0), Edn::Number(2.0)]));
+  assert_eq!(provenance.source(), MergeSource::Base);
+
+  assert_eq!(
+    merge_parse("{} (:a 1)", &["{} (:a 2)", "{} ("]).map(|(x, _)| x),
+    Err(format!(
+      "failed to parse overlay 1: {}",
+      cirru_edn::parse("{} (").unwrap_err()
+    ))
+  );
+  assert!(merge_parse("{} (", &[])
+    .unwrap_err()
+    .starts_with("failed to parse base: "));
+  Ok(())
+}
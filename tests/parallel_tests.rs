@@ -0,0 +1,70 @@
+#![cfg(feature = "rayon")]
+
+extern crate cirru_edn;
+
+use cirru_edn::{Edn, EdnFormatOptions, EdnListView};
+
+fn record(i: usize) -> Edn {
+  Edn::map([
+    (Edn::tag("id"), Edn::Number(i as f64)),
+    (Edn::tag("name"), Edn::str(format!("item \"{}\"\n", i))),
+    (
+      Edn::tag("tags"),
+      Edn::list((0..i % 7).map(|j| Edn::tag(format!("t{}", j)))),
+    ),
+    (
+      Edn::tag("nested"),
+      Edn::map([(Edn::tag("tuple"), Edn::tuple(Edn::tag("t"), vec![Edn::empty_list()]))]),
+    ),
+  ])
+}
+
+fn format_with_min_items(data: &Edn, options: EdnFormatOptions, min_items: usize) -> Result<String, String> {
+  cirru_edn::format_with_options(
+    data,
+    EdnFormatOptions {
+      parallel_min_items: Some(min_items),
+      ..options
+    },
+  )
+}
+
+#[test]
+fn parallel_format_is_identical() -> Result<(), String> {
+  let records = (0..500).map(record).collect::<Vec<_>>();
+  let mixed = (0..100)
+    .map(|i| if i % 10 == 3 { Edn::Number(i as f64) } else { record(i) })
+    .collect::<Vec<_>>();
+  let nested_lists = (0..100)
+    .map(|i| Edn::List(EdnListView(vec![Edn::list([i, i + 1]), Edn::Nil])))
+    .collect::<Vec<_>>();
+  let plain_lists = (0..100).map(|i| Edn::list([i, i + 1])).collect::<Vec<_>>();
+  let single = vec![cirru_edn::parse("{} (:a 1) (:b $ [] 1 2)")?];
+  let pair = vec![record(3), record(4)];
+
+  for items in [records, mixed, nested_lists, plain_lists, single, pair, vec![]] {
+    let data = Edn::List(EdnListView(items));
+    for options in [
+      true.into(),
+      false.into(),
+      EdnFormatOptions {
+        max_width: Some(20),
+        ..Default::default()
+      },
+    ] {
+      let sequential = format_with_min_items(&data, options, usize::MAX)?;
+      for min_items in [0, 1, 2, 3, 50] {
+        let parallel = format_with_min_items(&data, options, min_items)?;
+        assert_eq!(sequential, parallel, "min_items {}", min_items);
+      }
+      assert_eq!(cirru_edn::parse(&sequential)?, data);
+    }
+  }
+
+  let data = cirru_edn::parse("[] ({} (:a 1) (:b $ [] 1 2))")?;
+  assert_eq!(
+    format_with_min_items(&data, false.into(), 1)?,
+    "\n[] $ {} (:a 1)\n  :b $ [] 1 2\n"
+  );
+  Ok(())
+}
@@ -0,0 +1,120 @@
+extern crate cirru_edn;
+
+use cirru_edn::Edn;
+
+const DICT_DEMO: &str = r#"
+{} (:a 1.0)
+  :b $ [] 2.0 3.0 4.0
+  :c $ {} (:d 4.0)
+    :e true
+    :f :g
+    :h $ {} (|a 1.0)
+      |b true
+"#;
+
+#[test]
+fn select_in_dict() -> Result<(), String> {
+  let data = cirru_edn::parse(DICT_DEMO)?;
+  assert_eq!(data.select(":a")?, Some(&Edn::Number(1.0)));
+  assert_eq!(data.select(":b[1]")?, Some(&Edn::Number(3.0)));
+  assert_eq!(data.select(":b[3]")?, None);
+  assert_eq!(data.select(":c.:f")?, Some(&Edn::tag("g")));
+  assert_eq!(data.select(":c.:h.a")?, Some(&Edn::Number(1.0)));
+  assert_eq!(data.select(":c.:h.\"b\"")?, Some(&Edn::Bool(true)));
+  // string keys are not tags
+  assert_eq!(data.select("a")?, None);
+  assert_eq!(data.select(":c.:h.:a")?, None);
+  assert_eq!(data.select(":x.:y[1]")?, None);
+  assert_eq!(data.select("")?, Some(&data));
+  Ok(())
+}
+
+#[test]
+fn select_other_structures() -> Result<(), String> {
+  let data = cirru_edn::parse(
+    r#"
+{}
+  |items $ []
+    %{} :Item (:meta $ {} (:version 2))
+    :: :point 1 2
+  "|a.b" $ #{} :x |y 1
+"#,
+  )?;
+  assert_eq!(data.select("items[0].meta.:version")?, Some(&Edn::Number(2.0)));
+  assert_eq!(data.select("items[0].:meta.:version")?, Some(&Edn::Number(2.0)));
+  assert_eq!(data.select("items[1][0]")?, Some(&Edn::tag("point")));
+  assert_eq!(data.select("items[1][2]")?, Some(&Edn::Number(2.0)));
+  assert_eq!(data.select("\"a.b\"#:x")?, Some(&Edn::tag("x")));
+  assert_eq!(data.select("\"a.b\"#y")?, Some(&Edn::str("y")));
+  assert_eq!(data.select("\"a.b\"#1")?, Some(&Edn::Number(1.0)));
+  assert_eq!(data.select("\"a.b\"#\"1\"")?, None);
+  assert_eq!(data.select("\"a.b\"#:z")?, None);
+  Ok(())
+}
+
+#[test]
+fn select_errors() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:a $ [] 1 2)")?;
+  assert!(data.select(":a[x]").is_err());
+  assert!(data.select(":a[1").is_err());
+  assert!(data.select(":a.").is_err());
+  assert!(data.select(":").is_err());
+  assert!(data.select("\"a").is_err());
+  assert!(data.select(":a]").is_err());
+  // mismatched types
+  assert!(data.select("[0]").is_err());
+  assert!(data.select(":a.:b").unwrap_err().contains("list"));
+  assert!(data.select(":a#1").is_err());
+  Ok(())
+}
+
+#[test]
+fn extracting_path() -> Result<(), String> {
+  let code = r#"
+{}
+  :version |1.2.0
+  |name |demo
+  :items $ []
+    %{} :Item (:id 1) (:tags $ [] :a :b)
+    do $ %{} :Item (:id 2) (:tags $ [])
+  ; "siblings are not converted"
+  :broken $ [] (x 1)
+"#;
+  use cirru_edn::extract_path;
+  assert_eq!(extract_path(code, &["version"]), Ok(Edn::str("1.2.0")));
+  assert_eq!(extract_path(code, &["name"]), Ok(Edn::str("demo")));
+  assert_eq!(extract_path(code, &["items", "0", "tags", "1"]), Ok(Edn::tag("b")));
+  assert_eq!(extract_path(code, &["items", "1", "id"]), Ok(Edn::Number(2.0)));
+  assert_eq!(
+    extract_path(code, &["items", "0"])?,
+    cirru_edn::parse("%{} :Item (:id 1) (:tags $ [] :a :b)")?
+  );
+  assert!(cirru_edn::parse(code).is_err());
+
+  assert_eq!(
+    extract_path(code, &["items", "2", "id"]),
+    Err(String::from("missing value at `.items.2`"))
+  );
+  assert_eq!(
+    extract_path(code, &["author"]),
+    Err(String::from("missing value at `.author`"))
+  );
+  assert_eq!(
+    extract_path(code, &["version", "major"]),
+    Err(String::from("cannot select `major` from string at `.version`: |1.2.0"))
+  );
+  let e = extract_path(code, &["items", "first"]).unwrap_err();
+  assert!(
+    e.starts_with("cannot select `first` from list at `.items`: ([] (%{} :Item"),
+    "{}",
+    e
+  );
+  assert!(extract_path(code, &["broken", "0"]).is_err());
+
+  // atoms sharing a label in the value are checked, same as parsing
+  let code = "{} (:a $ [] (atom 'x 1) (atom 'x 2)) (:b 1)";
+  assert_eq!(extract_path(code, &["a"]), cirru_edn::parse(code));
+  assert!(extract_path(code, &["a"]).is_err());
+  assert_eq!(extract_path(code, &["b"]), Ok(Edn::Number(1.0)));
+  Ok(())
+}
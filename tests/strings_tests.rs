@@ -0,0 +1,81 @@
+extern crate cirru_edn;
+
+use std::sync::Arc;
+
+use cirru_edn::{Edn, EdnListView};
+
+#[test]
+fn string_statistics() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:a |x) (:b $ [] |x |yy 'x) (:c $ %{} :R (:a 1))")?;
+  let stats = data.string_stats();
+  assert_eq!(stats.strings, 3);
+  assert_eq!(stats.symbols, 1);
+  // tags in map keys, record name and record field
+  assert_eq!(stats.tags, 5);
+  assert_eq!(stats.unique, 6);
+  assert_eq!(stats.total_bytes, 4 + 1 + 5);
+  assert_eq!(stats.unique_bytes, 1 + 1 + 1 + 1 + 2 + 1);
+  assert_eq!(stats.top[0], (Arc::from("x"), 3));
+  assert_eq!(stats.top[1], (Arc::from("a"), 2));
+  Ok(())
+}
+
+#[test]
+fn dedup_same_tags() {
+  let mut data = Edn::List(EdnListView((0..10_000).map(|_| Edn::tag("same")).collect()));
+  let before = data.to_owned();
+  data.dedup_strings();
+  assert_eq!(data, before);
+  drop(before);
+
+  match &data {
+    Edn::List(xs) => {
+      let shared = match &xs.0[0] {
+        Edn::Tag(t) => t.0.to_owned(),
+        _ => unreachable!(),
+      };
+      for x in &xs.0 {
+        match x {
+          Edn::Tag(t) => assert!(Arc::ptr_eq(&t.0, &shared)),
+          _ => unreachable!(),
+        }
+      }
+      // all tags and the local clone
+      assert_eq!(Arc::strong_count(&shared), 10_001);
+    }
+    _ => unreachable!(),
+  }
+}
+
+#[test]
+fn dedup_nested() -> Result<(), String> {
+  let mut data = cirru_edn::parse("{} (|k $ #{} |k :k) (([] |k) $ :: :k |k) (:k $ %{} :k (:k |k))")?;
+  let before = data.to_owned();
+  data.dedup_strings();
+  assert_eq!(data, before);
+  let stats = data.string_stats();
+  assert_eq!(stats.unique, 1);
+  assert_eq!(stats.top, vec![(Arc::from("k"), stats.strings + stats.tags)]);
+  Ok(())
+}
+
+#[test]
+fn dedup_deep_sets() {
+  // nested sets are as deep as hashing allows
+  let mut data = Edn::Nil;
+  for _ in 0..500 {
+    data = Edn::Set([Edn::map([(Edn::tag("k"), data)])].into_iter().collect());
+  }
+  data.dedup_strings();
+  match &data {
+    Edn::Set(items) => match items.0.iter().next() {
+      Some(Edn::Map(m)) => match m.0.keys().next() {
+        Some(Edn::Tag(t)) => assert_eq!(Arc::strong_count(&t.0), 500),
+        _ => unreachable!(),
+      },
+      _ => unreachable!(),
+    },
+    _ => unreachable!(),
+  }
+  data.drop_iteratively();
+}
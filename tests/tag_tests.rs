@@ -0,0 +1,127 @@
+extern crate cirru_edn;
+
+use std::sync::Arc;
+
+use cirru_edn::{edn_tag, tag_dispatch, Edn, EdnTag};
+
+fn lookup_tag() -> EdnTag {
+  edn_tag!("cached")
+}
+
+#[test]
+fn cached_tags() {
+  let a = lookup_tag();
+  let b = lookup_tag();
+  assert!(Arc::ptr_eq(&a.0, &b.0));
+  assert_eq!(a, EdnTag::new("cached"));
+
+  // different call sites share strings through `from_static`
+  let c = edn_tag!("cached");
+  assert!(Arc::ptr_eq(&a.0, &c.0));
+
+  let d = EdnTag::from_static("other");
+  let e = EdnTag::from_static("other");
+  assert!(Arc::ptr_eq(&d.0, &e.0));
+  assert!(!Arc::ptr_eq(&EdnTag::new("other").0, &d.0));
+
+  let data = cirru_edn::parse("{} (:cached 1)").unwrap();
+  assert_eq!(
+    data.view_map().unwrap().get(&Edn::Tag(edn_tag!("cached"))),
+    Some(&Edn::Number(1.0))
+  );
+}
+
+#[test]
+fn lenient_tags() {
+  let tag = EdnTag::new("cat");
+  assert_eq!(EdnTag::try_from_lenient(&Edn::tag("cat")), Ok(tag.to_owned()));
+  assert_eq!(EdnTag::try_from_lenient(&Edn::str("cat")), Ok(tag.to_owned()));
+  assert_eq!(EdnTag::try_from_lenient(&Edn::sym("cat")), Ok(tag.to_owned()));
+  assert_eq!(EdnTag::try_from_lenient(&Edn::str("b-c?")), Ok(EdnTag::new("b-c?")));
+
+  assert!(EdnTag::try_from_lenient(&Edn::str("a cat")).is_err());
+  assert!(EdnTag::try_from_lenient(&Edn::str("")).is_err());
+  assert!(EdnTag::try_from_lenient(&Edn::str("(a)")).is_err());
+  for name in ["a\tb", "a)", "a\"b", "\"a"] {
+    assert!(EdnTag::try_from_lenient(&Edn::str(name)).is_err(), "{}", name);
+  }
+
+  // names written as tag leaves are accepted and round-trip
+  for name in ["a_b", "a/b", "x!", "1x", "a.b", "a:b"] {
+    let tag = EdnTag::try_from_lenient(&Edn::str(name)).unwrap();
+    assert_eq!(tag.ref_str(), name);
+    let data = Edn::Tag(tag);
+    let written = cirru_edn::format(&data, true).unwrap();
+    assert_eq!(written.trim(), format!("do :{}", name));
+    assert_eq!(cirru_edn::parse(&written), Ok(data));
+  }
+  assert!(EdnTag::try_from_lenient(&Edn::Number(1.0)).is_err());
+
+  // the strict conversion is unchanged
+  assert!(EdnTag::try_from(Edn::str("cat")).is_err());
+}
+
+#[test]
+fn tag_prefix_in_constructors() {
+  assert_eq!(Edn::tag(":a"), cirru_edn::parse("do :a").unwrap());
+  assert_eq!(Edn::tag(":a"), Edn::tag("a"));
+  assert_eq!(EdnTag::new(":a"), EdnTag::from(":a"));
+  assert_eq!(EdnTag::from_static(":a").ref_str(), "a");
+  assert_eq!(cirru_edn::format(&Edn::tag(":a"), true).unwrap().trim(), "do :a");
+
+  // names starting with `:` need the raw constructors
+  assert_eq!(Edn::tag_raw(":a"), cirru_edn::parse("do ::a").unwrap());
+  assert_eq!(EdnTag::new_raw(":a").ref_str(), ":a");
+  let written = cirru_edn::format(&Edn::tag_raw(":a"), true).unwrap();
+  assert_eq!(cirru_edn::parse(&written), Ok(Edn::tag_raw(":a")));
+
+  // lookups by name take the name as is, so keys starting with `:` are found
+  let data = cirru_edn::parse("{} (::x 1) (:y 2)").unwrap();
+  let map = data.view_map().unwrap();
+  assert_eq!(map.get_tag_key(":x"), Some(&Edn::Number(1.0)));
+  assert!(map.contains_key(":x"));
+  assert_eq!(map.get_or_nil(":x"), Edn::Number(1.0));
+  assert_eq!(map.get_by(&EdnTag::new_raw(":x")), Some(&Edn::Number(1.0)));
+  assert_eq!(map.get_tag_key("x"), None);
+  assert_eq!(map.get_tag_key(":y"), None);
+  assert!(!map.contains_key(":y"));
+  let mut map = cirru_edn::EdnMapView::default();
+  map.insert(Edn::Tag(EdnTag::new_raw(":x")), Edn::Nil);
+  assert!(map.contains_key(":x"));
+
+  // strings are kept as is, `|` is read as the prefix only in code
+  assert_eq!(Edn::str("a"), cirru_edn::parse("do |a").unwrap());
+  let written = cirru_edn::format(&Edn::str("|a"), true).unwrap();
+  assert_eq!(cirru_edn::parse(&written), Ok(Edn::str("|a")));
+}
+
+#[test]
+fn dispatching_tags() -> Result<(), String> {
+  let ops = cirru_edn::parse("[] :add :remove :move-to :clear |add nil ([] :add)")?;
+  let mut counts = (0, 0, 0);
+  let mut others = vec![];
+  for op in ops.view_list()?.iter() {
+    tag_dispatch!(op, {
+      add => counts.0 += 1,
+      remove => {
+        counts.1 += 1;
+      },
+      "move-to" => counts.2 += 1,
+      _ => others.push(op.to_string()),
+    });
+  }
+  assert_eq!(counts, (1, 1, 1));
+  assert_eq!(others, vec![":clear", "|add", "nil", "([] :add)"]);
+
+  let describe = |tag: EdnTag| {
+    tag_dispatch!(tag, {
+      add => "adding",
+      _ => "unknown",
+    })
+  };
+  assert_eq!(describe(EdnTag::new("add")), "adding");
+  assert_eq!(describe(EdnTag::new(":add")), "adding");
+  assert_eq!(describe(lookup_tag()), "unknown");
+  assert_eq!(tag_dispatch!(Edn::Nil, { _ => 0 }), 0);
+  Ok(())
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "toml")]
+
+extern crate cirru_edn;
+
+use cirru_edn::{edn_to_toml, toml_to_edn, Edn};
+
+const MANIFEST: &str = r#"
+[package]
+name = "demo"
+version = "0.1.0"
+edition = "2021"
+authors = ["a <a@example.com>"]
+published = 1979-05-27T07:32:00Z
+
+[dependencies]
+hex = "0.4.3"
+serde = { version = "1.0", features = ["derive"], optional = true }
+
+[features]
+default = []
+
+[profile.release]
+debug = true
+opt-level = 3
+lto-ratio = 0.5
+"#;
+
+#[test]
+fn manifest_roundtrip() -> Result<(), String> {
+  let value: toml::Value = toml::from_str(MANIFEST).map_err(|e| e.to_string())?;
+  let data = toml_to_edn(&value);
+
+  assert_eq!(data.select("package.name")?, Some(&Edn::str("demo")));
+  assert_eq!(
+    data.select("dependencies.serde.features[0]")?,
+    Some(&Edn::str("derive"))
+  );
+  assert_eq!(data.select("profile.release.opt-level")?, Some(&Edn::Number(3.0)));
+  assert_eq!(data.select("features.default")?, Some(&Edn::list(Vec::<Edn>::new())));
+  assert_eq!(
+    data.select("package.published")?,
+    Some(&Edn::tuple(
+      Edn::tag("datetime"),
+      vec![Edn::str("1979-05-27T07:32:00Z")]
+    ))
+  );
+
+  // through Cirru text and back
+  let text = cirru_edn::format(&data, false)?;
+  assert!(text.contains(":: :datetime |1979-05-27T07:32:00Z"), "{}", text);
+  let back = edn_to_toml(&cirru_edn::parse(&text)?)?;
+  assert_eq!(back, value);
+  assert!(back["package"]["published"].is_datetime());
+  assert_eq!(back["profile"]["release"]["lto-ratio"].as_float(), Some(0.5));
+
+  // integral floats are written as integers
+  let value: toml::Value = toml::from_str("x = 2.0\ny = 2.5\nz = 1e300").map_err(|e| e.to_string())?;
+  let back = edn_to_toml(&toml_to_edn(&value))?;
+  assert_eq!(back["x"].as_integer(), Some(2));
+  assert_eq!(back["y"].as_float(), Some(2.5));
+  assert_eq!(back["z"].as_float(), Some(1e300));
+  Ok(())
+}
+
+#[test]
+fn unsupported_values() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (|a $ {} (|b $ [] 1 (#{} 2)))")?;
+  assert_eq!(
+    edn_to_toml(&data).unwrap_err(),
+    "failed to convert set to toml at .|a.|b[1]"
+  );
+
+  let data = cirru_edn::parse("{} (|a nil)")?;
+  assert_eq!(edn_to_toml(&data).unwrap_err(), "failed to convert nil to toml at .|a");
+  assert!(edn_to_toml(&cirru_edn::parse("{} (1 2)")?)
+    .unwrap_err()
+    .contains("key is not a string: 1"));
+  assert!(edn_to_toml(&cirru_edn::parse("[] (buf 01)")?).is_err());
+  assert!(edn_to_toml(&cirru_edn::parse("[] (:: :point 1 2)")?).is_err());
+  assert!(edn_to_toml(&cirru_edn::parse(":: :datetime |yesterday")?)
+    .unwrap_err()
+    .contains("invalid datetime"));
+
+  // tags are written as strings
+  let v = edn_to_toml(&cirru_edn::parse("{} (:a :b)")?)?;
+  assert_eq!(v[":a"].as_str(), Some(":b"));
+  Ok(())
+}
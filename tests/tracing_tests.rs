@@ -0,0 +1,63 @@
+#![cfg(feature = "tracing")]
+
+extern crate cirru_edn;
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::Attributes;
+use tracing::{Event, Id, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+/// records spans and events as `name field=value ...` lines
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+struct FieldsText(String);
+
+impl Visit for FieldsText {
+  fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+    self.0.push_str(&format!(" {}={:?}", field.name(), value));
+  }
+}
+
+impl<S: Subscriber> Layer<S> for Recorder {
+  fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+    let mut text = FieldsText(attrs.metadata().name().to_owned());
+    attrs.record(&mut text);
+    self.0.lock().unwrap().push(text.0);
+  }
+
+  fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+    let mut text = FieldsText(String::from("event"));
+    event.record(&mut text);
+    self.0.lock().unwrap().push(text.0);
+  }
+}
+
+#[test]
+fn spans_of_parse_and_format() -> Result<(), String> {
+  let recorder = Recorder::default();
+  let subscriber = tracing_subscriber::registry().with(recorder.clone());
+  tracing::subscriber::with_default(subscriber, || -> Result<(), String> {
+    let data = cirru_edn::parse("[] 1 ([] 2 3)")?;
+    cirru_edn::format(&data, true)?;
+    Ok(())
+  })?;
+
+  let lines = recorder.0.lock().unwrap().to_owned();
+  assert_eq!(
+    lines,
+    vec![
+      "cirru_edn::parse bytes=13",
+      "cirru_edn::read_cirru",
+      "cirru_edn::extract nodes=7",
+      "cirru_edn::extract_item path=[0] nodes=1",
+      "cirru_edn::extract_item path=[1] nodes=4",
+      "cirru_edn::format values=5 use_inline=true",
+      "event message=formatted bytes=15 ok=true",
+    ]
+  );
+  Ok(())
+}
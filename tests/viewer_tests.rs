@@ -2,7 +2,7 @@ extern crate cirru_edn;
 
 use std::collections::HashSet;
 
-use cirru_edn::{Edn, EdnListView, EdnMapView, EdnRecordView, EdnSetView, EdnTag};
+use cirru_edn::{Edn, EdnListView, EdnMapView, EdnRecordView, EdnSetView, EdnTag, EdnTupleView};
 
 #[test]
 fn building_record() {
@@ -22,6 +22,24 @@ fn building_record() {
   );
 }
 
+#[test]
+fn building_map_in_chain() {
+  let map = EdnMapView::default()
+    .with_key("a", 1)
+    .with_str_key("a", "x")
+    .with_key("b", true);
+  assert_eq!(map.len(), 3);
+  assert_eq!(map.get_tag_key("a"), Some(&Edn::Number(1.0)));
+  assert_eq!(map.get_str_key("a"), Some(&Edn::str("x")));
+  assert_eq!(map.get_or_nil("b"), Edn::Bool(true));
+
+  let mut other = EdnMapView::default();
+  other.insert_key("b", true);
+  other.insert_str_key("a", "x");
+  other.insert_key("a", 1);
+  assert_eq!(other, map);
+}
+
 #[test]
 fn building_map() {
   let mut map = EdnMapView::default();
@@ -73,3 +91,520 @@ fn building_set() {
   s.insert(Edn::Number(2.0));
   assert_eq!(Edn::from(s), Edn::from(set));
 }
+
+#[test]
+fn borrowed_getters() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:a $ [] 1 2) (|b 2) (:c $ %{} :R (:x 1))")?;
+  assert_eq!(data.map_get("a")?.list_get(1)?, &Edn::Number(2.0));
+  assert_eq!(data.map_get("a")?.list_get(2)?, &Edn::Nil);
+  assert_eq!(data.map_get("b")?, &Edn::Number(2.0));
+  assert_eq!(data.map_get("d")?, &Edn::Nil);
+  assert_eq!(data.map_get("c")?.record_get("x")?, &Edn::Number(1.0));
+  assert_eq!(data.map_get("c")?.record_get("y")?, &Edn::Nil);
+  assert_eq!(Edn::Nil.map_get("a")?, &Edn::Nil);
+  assert_eq!(Edn::Nil.list_get(0)?, &Edn::Nil);
+
+  assert!(data.list_get(0).is_err());
+  assert!(data.record_get("a").is_err());
+  assert!(Edn::Number(1.0).map_get("a").unwrap_err().contains("number"));
+  Ok(())
+}
+
+#[test]
+fn borrowed_getters_without_clone() -> Result<(), String> {
+  let mut map = EdnMapView::default();
+  for i in 0..100_000 {
+    map.insert(Edn::tag(format!("k{}", i)), Edn::from(vec![Edn::Number(i as f64)]));
+  }
+  let data = Edn::Map(map);
+  let inner = match &data {
+    Edn::Map(xs) => xs.get_tag_key("k99999").unwrap(),
+    _ => unreachable!(),
+  };
+  // same reference as inside the map, so nothing is cloned
+  assert!(std::ptr::eq(data.map_get("k99999")?, inner));
+  Ok(())
+}
+
+#[test]
+fn viewers_on_nil() {
+  // collections read nil as empty
+  assert_eq!(Edn::Nil.view_list(), Ok(EdnListView::default()));
+  assert_eq!(Edn::Nil.view_map(), Ok(EdnMapView::default()));
+  assert_eq!(Edn::Nil.view_set(), Ok(EdnSetView::default()));
+  assert_eq!(EdnListView::try_from(Edn::Nil), Ok(EdnListView::default()));
+  assert_eq!(EdnMapView::try_from(Edn::Nil), Ok(EdnMapView::default()));
+  assert_eq!(EdnSetView::try_from(Edn::Nil), Ok(EdnSetView::default()));
+
+  // record and tuple require tags
+  assert!(Edn::Nil.view_record().is_err());
+  assert!(Edn::Nil.view_tuple().is_err());
+  assert!(EdnRecordView::try_from(Edn::Nil).is_err());
+  assert!(EdnTupleView::try_from(Edn::Nil).is_err());
+  assert_eq!(Edn::Nil.view_record_or_nil(), Ok(None));
+  assert_eq!(Edn::Nil.view_tuple_or_nil(), Ok(None));
+
+  let record = Edn::Record(EdnRecordView::new(EdnTag::new("A")));
+  assert_eq!(
+    record.view_record_or_nil(),
+    Ok(Some(EdnRecordView::new(EdnTag::new("A"))))
+  );
+  assert!(record.view_tuple_or_nil().is_err());
+}
+
+#[test]
+fn reference_or_nil() {
+  let xs = EdnListView(vec![Edn::Number(1.0), Edn::from(vec![Edn::Number(2.0)])]);
+  assert!(std::ptr::eq(xs.get_ref_or_nil(1), &xs.0[1]));
+  assert_eq!(xs.get_ref_or_nil(2), &Edn::Nil);
+
+  let mut map = EdnMapView::default();
+  map.insert(Edn::str("a"), Edn::Number(1.0));
+  map.insert_key("b", Edn::Number(2.0));
+  assert_eq!(map.get_ref_or_nil("a"), &Edn::Number(1.0));
+  assert_eq!(map.get_ref_or_nil("b"), &Edn::Number(2.0));
+  assert_eq!(map.get_ref_or_nil("c"), &Edn::Nil);
+}
+
+#[test]
+fn record_iteration() -> Result<(), String> {
+  let mut record = EdnRecordView::new(EdnTag::new("A"));
+  record.insert("a", Edn::Number(1.0));
+  record.insert("b", Edn::str("x"));
+  record.insert("c", Edn::Number(3.0));
+  assert_eq!(record.len(), 3);
+  assert!(!record.is_empty());
+
+  assert_eq!(
+    record.keys().map(|k| k.to_string()).collect::<Vec<_>>(),
+    vec!["a", "b", "c"]
+  );
+  assert_eq!(record.values().filter(|v| matches!(v, Edn::Number(_))).count(), 2);
+
+  for (_k, v) in record.iter_mut() {
+    if let Edn::Number(n) = v {
+      *n += 1.0;
+    }
+  }
+  assert_eq!(record["c"], Edn::Number(4.0));
+
+  // filtered record with the same tag
+  let mut numbers = EdnRecordView::new(record.tag.to_owned());
+  numbers.extend(
+    (&record)
+      .into_iter()
+      .filter(|(_, v)| matches!(v, Edn::Number(_)))
+      .map(|(k, v)| (k.to_owned(), v.to_owned())),
+  );
+  assert_eq!(numbers.tag, EdnTag::new("A"));
+  assert_eq!(numbers.len(), 2);
+  assert!(!numbers.has_key("b"));
+
+  let tag = record.tag.to_owned();
+  let collected = EdnRecordView::from_fields(tag.to_owned(), record.into_iter().filter(|(k, _)| k.ref_str() != "a"))?;
+  assert_eq!(collected.tag, tag);
+  assert_eq!(collected.keys().count(), 2);
+  assert_eq!(collected["b"], Edn::str("x"));
+
+  // a tag is required, fields are not repeated
+  assert_eq!(
+    EdnRecordView::from_fields(EdnTag::new(""), []),
+    Err(String::from("record needs a tag"))
+  );
+  assert_eq!(
+    EdnRecordView::from_fields(tag, [(EdnTag::new("a"), Edn::Nil), (EdnTag::new("a"), Edn::Nil)]),
+    Err(String::from("duplicated field `a` in record :A"))
+  );
+  Ok(())
+}
+
+#[test]
+fn concat_and_flatten() -> Result<(), String> {
+  let parts = [
+    cirru_edn::parse("[] 1 2")?,
+    cirru_edn::parse("[] ([] 3)")?,
+    Edn::Nil,
+    cirru_edn::parse("[] ({} (:a $ [] 4))")?,
+  ];
+  let joined = Edn::concat(&parts)?;
+  assert_eq!(joined, cirru_edn::parse("[] 1 2 ([] 3) ({} (:a $ [] 4))")?);
+  let err = Edn::concat(&[Edn::Nil, Edn::Number(1.0)]).unwrap_err();
+  assert!(err.contains("part 1"), "{}", err);
+
+  let xs = EdnListView(vec![Edn::Number(1.0)]).concat(EdnListView(vec![Edn::Number(2.0)]));
+  assert_eq!(xs, EdnListView(vec![Edn::Number(1.0), Edn::Number(2.0)]));
+
+  let nested = cirru_edn::parse("[] 1 ([] 2 ([] 3 ([] 4))) ({} (:a $ [] 5))")?;
+  assert_eq!(nested.flatten(0)?, nested);
+  assert_eq!(
+    nested.flatten(1)?,
+    cirru_edn::parse("[] 1 2 ([] 3 ([] 4)) ({} (:a $ [] 5))")?
+  );
+  // maps are untouched
+  assert_eq!(
+    nested.flatten(2)?,
+    cirru_edn::parse("[] 1 2 3 ([] 4) ({} (:a $ [] 5))")?
+  );
+  assert_eq!(
+    nested.into_flattened(10)?,
+    cirru_edn::parse("[] 1 2 3 4 ({} (:a $ [] 5))")?
+  );
+  assert_eq!(Edn::Nil.flatten(1)?, Edn::List(EdnListView::default()));
+  assert!(Edn::tag("a").flatten(1).is_err());
+
+  // deeply nested lists
+  let mut deep = Edn::Number(0.0);
+  for i in 1..100_000 {
+    deep = Edn::from(vec![Edn::Number(i as f64), deep]);
+  }
+  let flat = deep.into_flattened(usize::MAX)?;
+  assert_eq!(flat.view_list()?.len(), 100_000);
+  Ok(())
+}
+
+#[test]
+fn checked_records() -> Result<(), String> {
+  let pairs = [
+    (EdnTag::new("b"), Edn::Number(1.0)),
+    (EdnTag::new("a"), Edn::str("x")),
+    (EdnTag::new("b"), Edn::Nil),
+  ];
+  let err = Edn::record_from_pairs_checked(EdnTag::new("R"), &pairs).unwrap_err();
+  assert_eq!(err, "duplicated field `b` in record :R");
+  let err = EdnRecordView::from_sorted_pairs(EdnTag::new("R"), pairs.to_vec()).unwrap_err();
+  assert_eq!(err, "duplicated field `b` in record :R");
+
+  let valid = &pairs[..2];
+  let checked = Edn::record_from_pairs_checked(EdnTag::new("R"), valid)?;
+  let unchecked = Edn::record_from_pairs(EdnTag::new("R"), valid);
+  assert_eq!(checked, unchecked);
+  assert_eq!(cirru_edn::format(&checked, true)?, cirru_edn::format(&unchecked, true)?);
+
+  let sorted = EdnRecordView::from_sorted_pairs(EdnTag::new("R"), valid.to_vec())?;
+  assert_eq!(sorted.keys().map(|k| k.ref_str()).collect::<Vec<_>>(), ["a", "b"]);
+  assert_eq!(
+    cirru_edn::format(&Edn::from(sorted), true)?,
+    "\n%{} :R (:a |x) (:b 1)\n"
+  );
+  Ok(())
+}
+
+#[test]
+fn map_string_and_tag_keys() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:k 1) (|k 2) (:t 3) (|s 4)")?;
+  let map = data.view_map()?;
+  assert_eq!(map.get_tag_key("k"), Some(&Edn::Number(1.0)));
+  assert_eq!(map.get_str_key("k"), Some(&Edn::Number(2.0)));
+  // string key wins in the view, consistent with `get_or_nil`
+  assert_eq!(map.get_by_name("k"), Some(&Edn::Number(2.0)));
+  assert_eq!(map.get_or_nil("k"), Edn::Number(2.0));
+  assert_eq!(data.map_get("k")?, &Edn::Number(2.0));
+  let mut fields = cirru_edn::EdnFields::new(&data);
+  assert_eq!(fields.field::<f64>("k"), Some(2.0));
+
+  assert_eq!(map.get_by_name("t"), Some(&Edn::Number(3.0)));
+  assert_eq!(map.get_str_key("t"), None);
+  assert_eq!(map.get_by_name("s"), Some(&Edn::Number(4.0)));
+  assert_eq!(map.get_tag_key("s"), None);
+  assert_eq!(map.get_by_name("x"), None);
+  assert!(map.contains_key("t") && map.contains_key("s"));
+
+  assert_eq!(map.get(&Edn::tag("k")), Some(&Edn::Number(1.0)));
+  assert_eq!(map.get(&Edn::str("t")), None);
+  Ok(())
+}
+
+fn keys_of(m: &EdnMapView) -> impl Iterator<Item = &Edn> {
+  m.keys()
+}
+
+#[test]
+fn set_and_map_iterators() -> Result<(), String> {
+  let set = cirru_edn::parse("#{} 1 2 3")?.view_set()?;
+  let it = set.iter();
+  assert_eq!(it.len(), 3);
+  let mut items: Vec<&Edn> = it.collect();
+  items.sort();
+  assert_eq!(items, [&Edn::Number(1.0), &Edn::Number(2.0), &Edn::Number(3.0)]);
+  assert_eq!((&set).into_iter().count(), 3);
+  let mut owned: Vec<Edn> = set.into_iter().collect();
+  owned.sort();
+  assert_eq!(owned, [Edn::Number(1.0), Edn::Number(2.0), Edn::Number(3.0)]);
+
+  let map = cirru_edn::parse("{} (:a 1) (:b 2)")?.view_map()?;
+  assert_eq!(map.iter().len(), 2);
+  let mut keys: Vec<&Edn> = keys_of(&map).collect();
+  keys.sort();
+  assert_eq!(keys, [&Edn::tag("a"), &Edn::tag("b")]);
+  assert_eq!(map.values().map(|v| v.read_number().unwrap()).sum::<f64>(), 3.0);
+  for (k, v) in &map {
+    assert_eq!(map.get(k), Some(v));
+  }
+  let mut pairs: Vec<(Edn, Edn)> = map.into_iter().collect();
+  pairs.sort();
+  assert_eq!(
+    pairs,
+    [(Edn::tag("a"), Edn::Number(1.0)), (Edn::tag("b"), Edn::Number(2.0))]
+  );
+  Ok(())
+}
+
+#[test]
+fn list_dedup() -> Result<(), String> {
+  let mut xs = EdnListView(vec![
+    Edn::str(String::from("a")),
+    Edn::Number(0.0),
+    Edn::str(String::from("a")),
+    Edn::tag("a"),
+    Edn::Number(-0.0),
+    Edn::Number(0.1 + 0.2),
+    Edn::Number(0.3),
+    Edn::tag("a"),
+  ]);
+  assert!(xs.contains(&Edn::tag("a")));
+  assert!(!xs.contains(&Edn::sym("a")));
+  assert_eq!(xs.position(&Edn::tag("a")), Some(3));
+  assert_eq!(xs.position(&Edn::Number(-0.0)), Some(1));
+  assert_eq!(xs.position(&Edn::Nil), None);
+
+  let unique = xs.unique();
+  xs.dedup();
+  assert_eq!(xs, unique);
+  // first occurrences survive, numbers are compared exactly
+  assert_eq!(
+    xs,
+    EdnListView(vec![
+      Edn::str("a"),
+      Edn::Number(0.0),
+      Edn::tag("a"),
+      Edn::Number(0.1 + 0.2),
+      Edn::Number(0.3),
+    ])
+  );
+  assert!(xs.get(1).unwrap().read_number()?.is_sign_positive());
+  Ok(())
+}
+
+#[test]
+fn borrowed_tuples() -> Result<(), String> {
+  let describe = |v: &Edn| match v.as_tuple() {
+    Some((tag, [])) if tag.is_tag_named("none") => String::from("none"),
+    Some((tag, [x])) if tag.is_tag_named("some") => format!("some {}", x),
+    Some((tag, [x, y])) if tag.is_tag_named("point") => format!("point {} {}", x, y),
+    Some((tag, rest)) => format!("{} with {} values", tag, rest.len()),
+    None => String::from("not tuple"),
+  };
+  assert_eq!(describe(&cirru_edn::parse(":: :none")?), "none");
+  assert_eq!(describe(&cirru_edn::parse(":: :some 1")?), "some 1");
+  assert_eq!(describe(&cirru_edn::parse(":: :point 1 2")?), "point 1 2");
+  // arity mismatches fall through
+  assert_eq!(describe(&cirru_edn::parse(":: :point 1 2 3")?), ":point with 3 values");
+  assert_eq!(describe(&cirru_edn::parse(":: :some")?), ":some with 0 values");
+  assert_eq!(describe(&cirru_edn::parse("[] :point 1 2")?), "not tuple");
+  assert_eq!(describe(&Edn::Nil), "not tuple");
+  Ok(())
+}
+
+#[test]
+fn map_lookup_by_key_kinds() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:a 1) (|b 2) (:c 3) (|c 4) (([] 1) 5)")?;
+  let map = data.view_map()?;
+
+  assert_eq!(map.get_by("a"), Some(&Edn::Number(1.0)));
+  assert_eq!(map.get_by("b"), Some(&Edn::Number(2.0)));
+  assert_eq!(map.get_by("c"), Some(&Edn::Number(4.0)));
+
+  assert_eq!(map.get_by(&EdnTag::new("a")), Some(&Edn::Number(1.0)));
+  assert_eq!(map.get_by(&EdnTag::new("b")), None);
+  assert_eq!(map.get_by(&EdnTag::new("c")), Some(&Edn::Number(3.0)));
+
+  assert_eq!(map.get_by(&Edn::str("c")), Some(&Edn::Number(4.0)));
+  assert_eq!(map.get_by(&Edn::list([1])), Some(&Edn::Number(5.0)));
+  assert_eq!(map.get_by(&Edn::sym("a")), None);
+
+  assert_eq!(map.get_or_nil_by(&EdnTag::new("c")), Edn::Number(3.0));
+  assert_eq!(map.get_or_nil_by("missing"), Edn::Nil);
+  Ok(())
+}
+
+#[test]
+fn map_take_keys() -> Result<(), String> {
+  let data = cirru_edn::parse("{} (:a 1) (|b 2) (:c 3) (|c 4) (([] 1) 5)")?;
+  let map = data.view_map()?;
+  let expected = [
+    Edn::Number(1.0),
+    Edn::Number(2.0),
+    Edn::Number(4.0),
+    Edn::Nil,
+    Edn::Number(1.0),
+  ];
+  // tags and strings mixed, a missing key and a duplicated key
+  let keys = ["a", "b", "c", "missing", "a"];
+  assert_eq!(map.take_keys(keys), expected);
+  assert_eq!(map.take_keys(keys), keys.map(|k| map.get_or_nil(k)));
+  assert_eq!(map.to_owned().into_take_keys(keys), expected);
+
+  assert_eq!(map.take_keys([]), [] as [Edn; 0]);
+  assert_eq!(EdnMapView::default().into_take_keys(["a"]), [Edn::Nil]);
+  Ok(())
+}
+
+#[test]
+fn project_onto_fields() -> Result<(), String> {
+  let spec = [
+    ("name", Edn::str("")),
+    ("age", Edn::Number(0.0)),
+    ("tags", Edn::list::<Edn>([])),
+  ];
+
+  let data = cirru_edn::parse("{} (:name |Kii) (|tags ([] :a))")?;
+  let map = data.view_map()?;
+  // values follow `spec` order, defaults for missing keys
+  assert_eq!(
+    map.project(&spec, true)?,
+    vec![Edn::str("Kii"), Edn::Number(0.0), Edn::list([Edn::tag("a")])]
+  );
+
+  let data = cirru_edn::parse("{} (:name |Kii) (:z 1) (:color |white) (1 2)")?;
+  let map = data.view_map()?;
+  assert_eq!(map.project(&spec, false)?[0], Edn::str("Kii"));
+  assert_eq!(
+    map.project(&spec, true),
+    Err(String::from("unexpected keys in map: 1 :color :z"))
+  );
+
+  let data = cirru_edn::parse("%{} :Cat (:age 2) (:color |white) (:name |Kii)")?;
+  let record = data.view_record()?;
+  assert_eq!(
+    record.project(&spec, false)?,
+    vec![Edn::str("Kii"), Edn::Number(2.0), Edn::list::<Edn>([])]
+  );
+  assert_eq!(
+    record.project(&spec, true),
+    Err(String::from("unexpected fields in record :Cat: :color"))
+  );
+  Ok(())
+}
+
+/// payload compared by a value that can change behind the any-ref
+#[derive(Debug, Clone)]
+struct SharedCell(std::sync::Arc<std::sync::Mutex<i32>>);
+
+impl PartialEq for SharedCell {
+  fn eq(&self, other: &Self) -> bool {
+    *self.0.lock().unwrap() == *other.0.lock().unwrap()
+  }
+}
+
+#[test]
+fn validate_changed_keys() -> Result<(), String> {
+  let a = SharedCell(std::sync::Arc::new(std::sync::Mutex::new(1)));
+  let b = SharedCell(std::sync::Arc::new(std::sync::Mutex::new(2)));
+  let mut map = EdnMapView::default();
+  map.insert(Edn::any_ref(a.to_owned()), Edn::Number(1.0));
+  map.insert(Edn::any_ref(b.to_owned()), Edn::Number(2.0));
+  let mut set = EdnSetView::default();
+  set.insert(Edn::any_ref(a.to_owned()));
+  set.insert(Edn::any_ref(b.to_owned()));
+  assert_eq!(map.debug_validate(), Ok(()));
+  assert_eq!(set.debug_validate(), Ok(()));
+
+  // keys become equal after insertion, one of them is shadowed by the other
+  *b.0.lock().unwrap() = 1;
+  let e = map.debug_validate().unwrap_err();
+  assert!(e.starts_with("map keys no longer found by lookup: "), "{}", e);
+  let e = set.debug_validate().unwrap_err();
+  assert!(e.starts_with("set items no longer found by lookup: "), "{}", e);
+
+  // checked before writing in debug builds
+  if cfg!(debug_assertions) {
+    let e = cirru_edn::format(&Edn::Map(map), true).unwrap_err();
+    assert!(e.starts_with("map keys no longer found"), "{}", e);
+  }
+  Ok(())
+}
+
+#[test]
+fn indexing_with_hints() -> Result<(), String> {
+  let config = cirru_edn::parse(
+    r#"
+{}
+  :skill-level 3
+  :skill-name |fireball
+  :player-name |Ada
+  :max-hp 120
+  |theme |dark
+"#,
+  )?;
+  let m = config.view_map()?;
+  assert_eq!(m["skill-level"], Edn::Number(3.0));
+  assert_eq!(m["theme"], Edn::str("dark"));
+
+  let hints = |key: &str| m.similar_keys(key).iter().map(|k| k.to_string()).collect::<Vec<_>>();
+  assert_eq!(hints("skil-level"), vec![":skill-level"]);
+  assert_eq!(hints("skill"), vec![":skill-level", ":skill-name"]);
+  assert_eq!(hints("max_hp"), vec![":max-hp"]);
+  assert_eq!(hints("Theme"), vec!["|theme"]);
+  assert_eq!(hints("player"), vec![":player-name"]);
+  assert!(hints("volume").is_empty());
+
+  let missing = std::panic::catch_unwind(|| m["skil-level"].to_owned());
+  let message = missing.unwrap_err().downcast::<String>().unwrap();
+  assert_eq!(
+    *message,
+    "no key `skil-level` in map of 5 entries, did you mean :skill-level?"
+  );
+  assert_eq!(m.try_get_by_name("skil-level"), Err(*message));
+  assert_eq!(
+    m.try_get_by_name("volume"),
+    Err(String::from("no key `volume` in map of 5 entries"))
+  );
+  assert_eq!(m.try_get_by_name("theme"), Ok(&m["theme"]));
+
+  let xs = EdnListView::from(vec![Edn::Nil, Edn::Bool(true)]);
+  assert_eq!(xs[1], Edn::Bool(true));
+  let message = std::panic::catch_unwind(|| xs[2].to_owned())
+    .unwrap_err()
+    .downcast::<String>()
+    .unwrap();
+  assert_eq!(*message, "index 2 out of bounds for list of length 2");
+  Ok(())
+}
+
+#[test]
+fn dispatching_ops() -> Result<(), String> {
+  let program = cirru_edn::parse(
+    r#"
+[]
+  [] :add 1 2
+  [] :neg 3
+  [] :log |hi
+  [] 4 5
+"#,
+  )?;
+
+  let mut acc = 0.0;
+  let mut logs = vec![];
+  let mut skipped = vec![];
+  for entry in program.view_list()?.iter() {
+    match entry.as_op() {
+      Some((op, [Edn::Number(a), Edn::Number(b)])) if op.ref_str() == "add" => acc += a + b,
+      Some((op, [Edn::Number(a)])) if op.ref_str() == "neg" => acc -= a,
+      Some((op, [msg])) if op.ref_str() == "log" => logs.push(msg.read_str()?.to_string()),
+      _ => skipped.push(entry.to_owned()),
+    }
+  }
+  assert_eq!(acc, 0.0);
+  assert_eq!(logs, vec!["hi"]);
+  assert_eq!(skipped, vec![Edn::from(vec![Edn::Number(4.0), Edn::Number(5.0)])]);
+  assert_eq!(Edn::tag("add").as_op(), None);
+  assert_eq!(Edn::from(Vec::<Edn>::new()).as_op(), None);
+
+  let xs = EdnListView::from(vec![Edn::tag("neg"), Edn::Number(3.0)]);
+  assert_eq!(xs.split_first(), Some((&Edn::tag("neg"), &[Edn::Number(3.0)][..])));
+  assert_eq!(xs.rest(), &[Edn::Number(3.0)]);
+  let empty = EdnListView::default();
+  assert_eq!(empty.split_first(), None);
+  assert!(empty.rest().is_empty());
+  Ok(())
+}
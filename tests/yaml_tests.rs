@@ -0,0 +1,79 @@
+#![cfg(feature = "yaml")]
+
+extern crate cirru_edn;
+
+use cirru_edn::{edn_to_yaml_string, yaml_to_edn, Edn, EdnRecordView, EdnTag};
+
+const DICT_DEMO: &str = r#"
+{} (:a 1.0)
+  :b $ [] 2.0 3.0 4.0
+  :c $ {} (:d 4.0)
+    :e true
+    :f :g
+    :h $ {} (|a 1.0)
+      |b true
+"#;
+
+const DICT_DEMO_YAML: &str = r#":a: 1
+:b:
+- 2
+- 3
+- 4
+:c:
+  :d: 4
+  :e: true
+  :f: :g
+  :h:
+    a: 1
+    b: true
+"#;
+
+#[test]
+fn dict_to_yaml() -> Result<(), String> {
+  let data = cirru_edn::parse(DICT_DEMO)?;
+  let yaml = edn_to_yaml_string(&data)?;
+  assert_eq!(yaml, DICT_DEMO_YAML);
+
+  assert_eq!(yaml_to_edn(&yaml, true)?, data);
+  // without the option, tags stay as strings
+  assert_eq!(
+    yaml_to_edn(&yaml, false)?.view_map()?.get_str_key(":a"),
+    Some(&Edn::Number(1.0))
+  );
+  Ok(())
+}
+
+#[test]
+fn record_to_yaml() -> Result<(), String> {
+  let data = Edn::Record(EdnRecordView {
+    tag: EdnTag::new("Demo"),
+    pairs: vec![
+      (EdnTag::new("b"), Edn::Number(2.5)),
+      (
+        EdnTag::new("a"),
+        Edn::from(vec![Edn::sym("s"), Edn::Buffer(vec![1, 2, 255])]),
+      ),
+    ],
+  });
+  let yaml = edn_to_yaml_string(&data)?;
+  assert_eq!(
+    yaml,
+    "!record\n:Demo:\n  :b: 2.5\n  :a:\n  - !symbol s\n  - !binary AQL/\n"
+  );
+  assert_eq!(yaml_to_edn(&yaml, true)?, data);
+  Ok(())
+}
+
+#[test]
+fn tagged_values_to_yaml() -> Result<(), String> {
+  let data = cirru_edn::parse("[] (:: :point 1 2) (#{} 2 1) (quote (a (b c))) (atom nil) (atom 'x 1)")?;
+  let yaml = edn_to_yaml_string(&data)?;
+  assert_eq!(
+    yaml,
+    "- !tuple\n  - :point\n  - 1\n  - 2\n- !set\n  - 1\n  - 2\n- !quote a (b c)\n- !atom null\n- !labeled-atom\n  - x\n  - 1\n"
+  );
+  assert_eq!(yaml_to_edn(&yaml, false)?, data);
+
+  assert!(edn_to_yaml_string(&Edn::any_ref(1)).is_err());
+  Ok(())
+}